@@ -0,0 +1,270 @@
+// Prints compute units consumed by Deposit, Swap and Withdraw of constant product pool with SPL token mints.
+// Units are metered only for program built for SBF, run from repository root:
+// cargo build-sbf --manifest-path program/Cargo.toml
+// SBF_OUT_DIR=program/target/deploy cargo run --manifest-path bench/Cargo.toml
+use solana_program::bpf_loader_upgradeable;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::{tokio, BanksClient, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::hash::Hash;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use hodor_program::config::instruction::ConfigInstruction;
+use hodor_program::config::state::HodorConfig;
+use hodor_program::swap::curve::Curve;
+use hodor_program::swap::instruction::SwapInstruction;
+use hodor_program::swap::state::SwapPool;
+
+const LP_FEE_RATE: u32 = 300_000;
+const DAO_FEE_RATE: u32 = 50_000;
+const INITIAL_AMOUNT: u64 = 1_000_000_000_000;
+
+struct Pool {
+    state: Pubkey,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    lp_mint: Pubkey,
+    locked_lp: Pubkey,
+}
+
+// Returns units consumed by transaction simulation, then processes transaction
+async fn process_measured(banks_client: &mut BanksClient, payer: &Keypair, instruction: Instruction,
+                          blockhash: Hash) -> u64 {
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash);
+    let simulation = banks_client.simulate_transaction(transaction.clone()).await.unwrap();
+    simulation.result.unwrap().unwrap();
+    let units_consumed = simulation.simulation_details.unwrap().units_consumed;
+
+    banks_client.process_transaction(transaction).await.unwrap();
+    units_consumed
+}
+
+async fn process(banks_client: &mut BanksClient, payer: &Keypair, signers: &[&Keypair], instructions: &[Instruction],
+                 blockhash: Hash) {
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn create_mint(banks_client: &mut BanksClient, payer: &Keypair, blockhash: Hash) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = banks_client.get_rent().await.unwrap();
+    process(banks_client, payer, &[&mint], &[
+        system_instruction::create_account(&payer.pubkey(), &mint.pubkey(),
+                                           rent.minimum_balance(spl_token::state::Mint::LEN),
+                                           spl_token::state::Mint::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &payer.pubkey(), None, 6).unwrap(),
+    ], blockhash).await;
+
+    mint.pubkey()
+}
+
+async fn create_token_account(banks_client: &mut BanksClient, payer: &Keypair, mint: &Pubkey, amount: u64,
+                              blockhash: Hash) -> Pubkey {
+    let account = Keypair::new();
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut instructions = vec![
+        system_instruction::create_account(&payer.pubkey(), &account.pubkey(),
+                                           rent.minimum_balance(spl_token::state::Account::LEN),
+                                           spl_token::state::Account::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_account(&spl_token::id(), &account.pubkey(), mint, &payer.pubkey())
+            .unwrap(),
+    ];
+    if amount > 0 {
+        instructions.push(spl_token::instruction::mint_to(&spl_token::id(), mint, &account.pubkey(), &payer.pubkey(),
+                                                          &[], amount).unwrap());
+    }
+    process(banks_client, payer, &[&account], &instructions, blockhash).await;
+
+    account.pubkey()
+}
+
+async fn initialize_config(banks_client: &mut BanksClient, payer: &Keypair, blockhash: Hash) {
+    let program_id = hodor_program::id();
+    let (program_data_account, _) = Pubkey::find_program_address(
+        &[program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    process(banks_client, payer, &[], &[Instruction::new_with_bytes(
+        program_id,
+        &ConfigInstruction::pack(&ConfigInstruction::InitializeConfig {
+            admin: payer.pubkey(),
+            dao_fee_rate: DAO_FEE_RATE,
+            dao_fee_vault_owner: payer.pubkey(),
+            max_lp_fee_rate: HodorConfig::DEFAULT_MAX_LP_FEE_RATE,
+            max_creator_fee_rate: HodorConfig::DEFAULT_MAX_CREATOR_FEE_RATE,
+            fee_change_delay: HodorConfig::DEFAULT_FEE_CHANGE_DELAY,
+            guardian: payer.pubkey(),
+        }),
+        vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(HodorConfig::find_address(&program_id).0, false),
+            AccountMeta::new_readonly(program_data_account, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    )], blockhash).await;
+}
+
+async fn create_pool(banks_client: &mut BanksClient, payer: &Keypair, mint_a: &Pubkey, mint_b: &Pubkey,
+                     blockhash: Hash) -> Pool {
+    let program_id = hodor_program::id();
+    let curve = Curve::ConstantProduct;
+    let (state, _, seed) = SwapPool::find_canonical_address(mint_a, mint_b, LP_FEE_RATE, curve.curve_type(),
+                                                             &program_id);
+    let pool = Pool {
+        state,
+        token_a: Pubkey::find_program_address(&[state.as_ref(), b"A"], &program_id).0,
+        token_b: Pubkey::find_program_address(&[state.as_ref(), b"B"], &program_id).0,
+        lp_mint: Pubkey::find_program_address(&[state.as_ref(), b"LP"], &program_id).0,
+        locked_lp: SwapPool::find_locked_lp_address(&state, &program_id).0,
+    };
+
+    process(banks_client, payer, &[], &[Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::CreatePool {
+            seed,
+            lp_fee_rate: LP_FEE_RATE,
+            creator_fee_rate: 0,
+            curve,
+            canonical: true,
+        }),
+        vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new_readonly(*mint_a, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(pool.locked_lp, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&program_id).0, false),
+        ],
+    )], blockhash).await;
+
+    pool
+}
+
+#[tokio::main]
+async fn main() {
+    let program_id = hodor_program::id();
+    let mut program_test = ProgramTest::new("hodor_program", program_id, None);
+    program_test.prefer_bpf(true);
+
+    // Config can be initialized only by upgrade authority, so program data of upgradeable program is faked
+    let payer = Keypair::new();
+    program_test.add_account(payer.pubkey(), Account {
+        lamports: 100 * LAMPORTS_PER_SOL,
+        ..Account::default()
+    });
+
+    let (program_data_account, _) = Pubkey::find_program_address(
+        &[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let mut program_data = vec![3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    program_data.extend_from_slice(payer.pubkey().as_ref());
+    program_test.add_account(program_data_account, Account {
+        lamports: LAMPORTS_PER_SOL,
+        data: program_data,
+        owner: bpf_loader_upgradeable::id(),
+        ..Account::default()
+    });
+
+    let (mut banks_client, _, blockhash) = program_test.start().await;
+
+    initialize_config(&mut banks_client, &payer, blockhash).await;
+
+    let mint_1 = create_mint(&mut banks_client, &payer, blockhash).await;
+    let mint_2 = create_mint(&mut banks_client, &payer, blockhash).await;
+    let (mint_a, mint_b) = if mint_1 < mint_2 { (mint_1, mint_2) } else { (mint_2, mint_1) };
+
+    let user_a = create_token_account(&mut banks_client, &payer, &mint_a, INITIAL_AMOUNT, blockhash).await;
+    let user_b = create_token_account(&mut banks_client, &payer, &mint_b, INITIAL_AMOUNT, blockhash).await;
+
+    let pool = create_pool(&mut banks_client, &payer, &mint_a, &mint_b, blockhash).await;
+    let user_lp = create_token_account(&mut banks_client, &payer, &pool.lp_mint, 0, blockhash).await;
+
+    let deposit_units = process_measured(&mut banks_client, &payer, Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::Deposit {
+            min_a: 0,
+            max_a: INITIAL_AMOUNT / 2,
+            min_b: 0,
+            max_b: INITIAL_AMOUNT / 2,
+        }),
+        vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new(user_a, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(user_b, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(user_lp, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(pool.locked_lp, false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    ), blockhash).await;
+
+    let swap_units = process_measured(&mut banks_client, &payer, Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::Swap {
+            in_amount: 1_000_000,
+            min_out_amount: 0,
+        }),
+        vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new(user_a, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(user_b, false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&program_id).0, false),
+        ],
+    ), blockhash).await;
+
+    let lp_account = banks_client.get_account(user_lp).await.unwrap().unwrap();
+    let lp_amount = spl_token::state::Account::unpack(&lp_account.data).unwrap().amount;
+
+    let withdraw_units = process_measured(&mut banks_client, &payer, Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::Withdraw {
+            lp_amount: lp_amount / 2,
+            min_a: 0,
+            min_b: 0,
+        }),
+        vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(user_a, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(user_b, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(user_lp, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    ), blockhash).await;
+
+    println!("Deposit: {} CU", deposit_units);
+    println!("Swap: {} CU", swap_units);
+    println!("Withdraw: {} CU", withdraw_units);
+}
\ No newline at end of file
@@ -0,0 +1,200 @@
+use std::str::FromStr;
+use clap::ArgMatches;
+use dialoguer::Confirm;
+use dialoguer::theme::ColorfulTheme;
+use solana_program::bpf_loader_upgradeable;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+use solana_sdk::signature::read_keypair_file;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use hodor_program::config::instruction::ConfigInstruction;
+use hodor_program::config::state::HodorConfig;
+use crate::{Context, Error, print_transaction_result};
+
+pub fn initialize(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let dao_fee_rate = parse_fee_rate(matches.value_of("DAO-FEE-RATE").unwrap())?;
+
+    let dao_fee_vault_owner = Pubkey::from_str(matches.value_of("VAULT-OWNER").unwrap())
+        .map_err(|_| "Invalid DAO fee vault owner address".to_string())?;
+
+    let admin = matches.value_of("ADMIN")
+        .map_or(Ok(payer_keypair.pubkey()), Pubkey::from_str)
+        .map_err(|_| "Invalid admin address".to_string())?;
+
+    let max_lp_fee_rate = matches.value_of("max-lp-fee-rate")
+        .map_or(Ok(HodorConfig::DEFAULT_MAX_LP_FEE_RATE), parse_fee_rate)?;
+
+    let max_creator_fee_rate = matches.value_of("max-creator-fee-rate")
+        .map_or(Ok(HodorConfig::DEFAULT_MAX_CREATOR_FEE_RATE), parse_fee_rate)?;
+
+    let fee_change_delay = matches.value_of("fee-change-delay")
+        .map_or(Ok(HodorConfig::DEFAULT_FEE_CHANGE_DELAY), i64::from_str)
+        .map_err(|_| "Invalid fee change delay".to_string())?;
+
+    let guardian = matches.value_of("guardian")
+        .map_or(Ok(admin), Pubkey::from_str)
+        .map_err(|_| "Invalid guardian address".to_string())?;
+
+    let config = HodorConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate, max_creator_fee_rate,
+        fee_change_delay, guardian };
+    if !config.is_valid() {
+        return Err("Invalid fee rates".into());
+    }
+
+    let (config_account, _) = HodorConfig::find_address(&context.program_id);
+    let (program_data_account, _) = Pubkey::find_program_address(
+        &[context.program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    println!("Config account: {}", config_account);
+    print_config(&config);
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &ConfigInstruction::pack(&ConfigInstruction::InitializeConfig { admin, dao_fee_rate, dao_fee_vault_owner,
+            max_lp_fee_rate, max_creator_fee_rate, fee_change_delay, guardian }),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(config_account, false),
+            AccountMeta::new_readonly(program_data_account, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn update(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let (config_account, _) = HodorConfig::find_address(&context.program_id);
+    let config = get_config(&context)?;
+
+    if config.admin != payer_keypair.pubkey() {
+        return Err(format!("Config admin is {}", config.admin).into());
+    }
+
+    let dao_fee_rate = matches.value_of("dao-fee-rate")
+        .map_or(Ok(config.dao_fee_rate), parse_fee_rate)?;
+
+    let dao_fee_vault_owner = matches.value_of("vault-owner")
+        .map_or(Ok(config.dao_fee_vault_owner), Pubkey::from_str)
+        .map_err(|_| "Invalid DAO fee vault owner address".to_string())?;
+
+    let admin = matches.value_of("admin")
+        .map_or(Ok(config.admin), Pubkey::from_str)
+        .map_err(|_| "Invalid admin address".to_string())?;
+
+    let max_lp_fee_rate = matches.value_of("max-lp-fee-rate")
+        .map_or(Ok(config.max_lp_fee_rate), parse_fee_rate)?;
+
+    let max_creator_fee_rate = matches.value_of("max-creator-fee-rate")
+        .map_or(Ok(config.max_creator_fee_rate), parse_fee_rate)?;
+
+    let fee_change_delay = matches.value_of("fee-change-delay")
+        .map_or(Ok(config.fee_change_delay), i64::from_str)
+        .map_err(|_| "Invalid fee change delay".to_string())?;
+
+    let guardian = matches.value_of("guardian")
+        .map_or(Ok(config.guardian), Pubkey::from_str)
+        .map_err(|_| "Invalid guardian address".to_string())?;
+
+    let new_config = HodorConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate, max_creator_fee_rate,
+        fee_change_delay, guardian };
+    if !new_config.is_valid() {
+        return Err("Invalid fee rates".into());
+    }
+
+    println!("Current config:");
+    print_config(&config);
+    println!();
+    println!("New config:");
+    print_config(&new_config);
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &ConfigInstruction::pack(&ConfigInstruction::UpdateConfig { admin, dao_fee_rate, dao_fee_vault_owner,
+            max_lp_fee_rate, max_creator_fee_rate, fee_change_delay, guardian }),
+        vec![
+            AccountMeta::new_readonly(payer_keypair.pubkey(), true),
+            AccountMeta::new(config_account, false),
+        ],
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn print_info(context: Context, _matches: &ArgMatches) -> Result<(), Error> {
+    let (config_account, _) = HodorConfig::find_address(&context.program_id);
+    let config = get_config(&context)?;
+
+    println!("Config account: {}", config_account);
+    print_config(&config);
+
+    Ok(())
+}
+
+pub(crate) fn get_config(context: &Context) -> Result<HodorConfig, Error> {
+    let (config_account, _) = HodorConfig::find_address(&context.program_id);
+
+    let account = context.rpc_client.get_account_with_commitment(
+        &config_account,
+        context.commitment,
+    )?.value.ok_or("Hodor config isn't initialized".to_string())?;
+
+    Ok(HodorConfig::unpack(account.data())
+        .map_err(|_| "Failed to read Hodor config".to_string())?)
+}
+
+fn print_config(config: &HodorConfig) {
+    println!("Admin: {}", config.admin);
+    println!("DAO fee: {}%", config.dao_fee_rate as f64 / 1_000_000.0);
+    println!("DAO fee vault owner: {}", config.dao_fee_vault_owner);
+    println!("Max LP fee: {}%", config.max_lp_fee_rate as f64 / 1_000_000.0);
+    println!("Max creator fee: {}%", config.max_creator_fee_rate as f64 / 1_000_000.0);
+    println!("Fee change delay: {} seconds", config.fee_change_delay);
+    println!("Guardian: {}", config.guardian);
+}
+
+fn parse_fee_rate(value: &str) -> Result<u32, Error> {
+    Ok((f64::from_str(value).map_err(|_| "Invalid fee rate".to_string())? * 1_000_000.0) as u32)
+}
\ No newline at end of file
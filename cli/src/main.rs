@@ -1,10 +1,16 @@
+mod config;
 mod swap;
 
 use clap::{Arg, Command};
 use solana_cli_config::Config;
+use solana_client::client_error::ClientError;
 use solana_client::rpc_client::RpcClient;
+use solana_program::instruction::InstructionError;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use hodor_program::error::HodorError;
 
 pub(crate) type Error = Box<dyn std::error::Error>;
 
@@ -16,6 +22,20 @@ pub struct Context {
     pub assume_yes: bool,
 }
 
+pub(crate) fn print_transaction_result(result: Result<Signature, ClientError>) {
+    let program_error = result.as_ref().err()
+        .and_then(|error| error.get_transaction_error())
+        .and_then(|error| match error {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => HodorError::from_code(code),
+            _ => None,
+        });
+
+    match program_error {
+        Some(error) => println!("Transaction failed: {}", error),
+        None => println!("Transaction {:?}", result),
+    }
+}
+
 fn main() {
     let cmd = Command::new("hodor")
         .bin_name("hodor")
@@ -27,6 +47,42 @@ fn main() {
                 .help("Automatic yes to prompts. Assume \"yes\" as answer to all prompts and run non-interactively."),
         )
         .subcommand_required(true)
+        .subcommand(
+            Command::new("config")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("init")
+                        .about("Initialize program config, requires program upgrade authority")
+                        .arg(Arg::new("DAO-FEE-RATE").required(true).index(1))
+                        .arg(Arg::new("VAULT-OWNER").required(true).index(2)
+                            .long_help("Owner of token accounts receiving DAO fees"))
+                        .arg(Arg::new("ADMIN").required(false).index(3)
+                            .long_help("Config admin, fee payer if not provided"))
+                        .arg(Arg::new("max-lp-fee-rate").long("max-lp-fee-rate").takes_value(true))
+                        .arg(Arg::new("max-creator-fee-rate").long("max-creator-fee-rate").takes_value(true))
+                        .arg(Arg::new("fee-change-delay").long("fee-change-delay").takes_value(true)
+                            .value_name("SECONDS"))
+                        .arg(Arg::new("guardian").long("guardian").takes_value(true)
+                            .help("Authority which can pause swap pools"))
+                )
+                .subcommand(
+                    Command::new("update")
+                        .about("Update program config")
+                        .arg(Arg::new("dao-fee-rate").long("dao-fee-rate").takes_value(true))
+                        .arg(Arg::new("vault-owner").long("vault-owner").takes_value(true))
+                        .arg(Arg::new("admin").long("admin").takes_value(true))
+                        .arg(Arg::new("max-lp-fee-rate").long("max-lp-fee-rate").takes_value(true))
+                        .arg(Arg::new("max-creator-fee-rate").long("max-creator-fee-rate").takes_value(true))
+                        .arg(Arg::new("fee-change-delay").long("fee-change-delay").takes_value(true)
+                            .value_name("SECONDS"))
+                        .arg(Arg::new("guardian").long("guardian").takes_value(true)
+                            .help("Authority which can pause swap pools"))
+                )
+                .subcommand(
+                    Command::new("info")
+                        .about("Get details of program config")
+                )
+        )
         .subcommand(
             Command::new("swap")
                 .subcommand_required(true)
@@ -37,13 +93,23 @@ fn main() {
                         .arg(Arg::new("TOKEN-B").required(true).index(2))
                         .arg(Arg::new("LP-FEE-RATE").required(true).index(3))
                         .arg(Arg::new("CREATOR-FEE-RATE").required(false).index(4))
+                        .arg(Arg::new("stable").long("stable").takes_value(true).value_name("AMP")
+                            .help("Create StableSwap pool for pegged assets with provided amplification coefficient"))
+                        .arg(Arg::new("weight").long("weight").takes_value(true).value_name("PERCENT-A")
+                            .conflicts_with("stable")
+                            .help("Create weighted pool with provided token A weight in percent, e.g. 80 for 80/20 pool"))
+                        .arg(Arg::new("canonical").long("canonical")
+                            .help("Create pool at canonical address derived from mints, LP fee rate and curve type"))
                 )
                 .subcommand(
                     Command::new("deposit")
                         .about("Depositing tokens to swap pool")
                         .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
-                        .arg(Arg::new("AMOUNT-A").required(true).index(2))
-                        .arg(Arg::new("AMOUNT-B").required(true).index(3))
+                        .arg(Arg::new("AMOUNT-A").required(true).index(2)
+                            .long_help("Amount of token A, or amount of --only token for single token deposit"))
+                        .arg(Arg::new("AMOUNT-B").required_unless_present("only").conflicts_with("only").index(3))
+                        .arg(Arg::new("only").long("only").takes_value(true).value_name("MINT")
+                            .help("Deposit only provided token, part of it is swapped and charged with swap fees"))
                 )
                 .subcommand(
                     Command::new("info")
@@ -56,18 +122,94 @@ fn main() {
                         .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
                         .arg(Arg::new("INPUT-ACCOUNT").required(true).index(2)
                             .long_help("Mint or mint token account address which will be used as swap input. \
-                            If mint is provided associated token account will be used.")
+                            If mint is provided associated token account will be used, \
+                            native SOL mint is wrapped into temporary account and unwrapped after swap.")
                         )
                         .arg(
-                            Arg::new("INPUT-AMOUNT").required(true).index(3)
+                            Arg::new("INPUT-AMOUNT").required_unless_present("exact-out").conflicts_with("exact-out")
+                                .index(3)
                                 .long_help("Amount of tokens to swap")
                         )
+                        .arg(Arg::new("exact-out").long("exact-out").takes_value(true).value_name("AMOUNT")
+                            .help("Receive exact amount of output token, required input amount is calculated"))
+                )
+                .subcommand(
+                    Command::new("route")
+                        .about("Swap tokens through multiple swap pools in single instruction")
+                        .arg(Arg::new("INPUT-MINT").required(true).index(1))
+                        .arg(Arg::new("INPUT-AMOUNT").required(true).index(2))
+                        .arg(Arg::new("POOL-ACCOUNTS").required(true).index(3).multiple_values(true)
+                            .long_help("Swap pool accounts in route order, output of each pool is input of next one"))
                 )
                 .subcommand(
                     Command::new("withdraw")
                         .about("Withdraw tokens from swap pool")
                         .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
                         .arg(Arg::new("LP-AMOUNT").required(false).index(2))
+                        .arg(Arg::new("only").long("only").takes_value(true).value_name("MINT")
+                            .help("Withdraw only provided token, other token share is swapped and charged with swap fees"))
+                )
+                .subcommand(
+                    Command::new("collect-dao-fees")
+                        .about("Collect accrued DAO fees into DAO vault")
+                        .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                )
+                .subcommand(
+                    Command::new("pause")
+                        .about("Pause swap pool in emergency, requires config guardian")
+                        .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                )
+                .subcommand(
+                    Command::new("unpause")
+                        .about("Unpause swap pool, requires config guardian")
+                        .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                )
+                .subcommand(
+                    Command::new("close")
+                        .about("Close drained swap pool and reclaim rent, requires pool creator")
+                        .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                )
+                .subcommand(
+                    Command::new("events")
+                        .about("Decode swap pool events logged by transaction")
+                        .arg(Arg::new("SIGNATURE").required(true).index(1))
+                )
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Upgrade swap pool state of older layout version, can be done by anyone")
+                        .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                )
+                .subcommand(
+                    Command::new("creator")
+                        .about("Manage swap pool creator fee")
+                        .subcommand_required(true)
+                        .subcommand(
+                            Command::new("set-authority")
+                                .about("Change creator fee withdraw authority")
+                                .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                                .arg(Arg::new("NEW-AUTHORITY").required(true).index(2))
+                        )
+                        .subcommand(
+                            Command::new("withdraw")
+                                .about("Withdraw accrued creator fees")
+                                .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                                .arg(Arg::new("AMOUNT-A").required(false).index(2)
+                                    .long_help("Amount of token A to withdraw, all accrued fees if not provided"))
+                                .arg(Arg::new("AMOUNT-B").required(false).index(3)
+                                    .long_help("Amount of token B to withdraw, all accrued fees if not provided"))
+                        )
+                        .subcommand(
+                            Command::new("propose-fee")
+                                .about("Propose new LP and creator fee rates, they can be applied after delay set in config")
+                                .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                                .arg(Arg::new("LP-FEE-RATE").required(true).index(2))
+                                .arg(Arg::new("CREATOR-FEE-RATE").required(true).index(3))
+                        )
+                        .subcommand(
+                            Command::new("apply-fee")
+                                .about("Apply proposed fee rates once fee change delay has passed")
+                                .arg(Arg::new("POOL-ACCOUNT").required(true).index(1))
+                        )
                 )
         );
     let matches = cmd.get_matches();
@@ -89,6 +231,20 @@ fn main() {
     };
 
     let result = match matches.subcommand() {
+        Some(("config", matches)) => {
+            match matches.subcommand() {
+                Some(("init", matches)) => {
+                    config::initialize(context, matches)
+                }
+                Some(("update", matches)) => {
+                    config::update(context, matches)
+                }
+                Some(("info", matches)) => {
+                    config::print_info(context, matches)
+                }
+                _ => unreachable!()
+            }
+        }
         Some(("swap", matches)) => {
             match matches.subcommand() {
                 Some(("create", matches)) => {
@@ -103,9 +259,47 @@ fn main() {
                 Some(("swap", matches)) => {
                     swap::swap(context, matches)
                 }
+                Some(("route", matches)) => {
+                    swap::swap_route(context, matches)
+                }
                 Some(("withdraw", matches)) => {
                     swap::withdraw(context, matches)
                 }
+                Some(("collect-dao-fees", matches)) => {
+                    swap::collect_dao_fees(context, matches)
+                }
+                Some(("pause", matches)) => {
+                    swap::set_paused(context, matches, true)
+                }
+                Some(("unpause", matches)) => {
+                    swap::set_paused(context, matches, false)
+                }
+                Some(("close", matches)) => {
+                    swap::close_pool(context, matches)
+                }
+                Some(("events", matches)) => {
+                    swap::print_events(context, matches)
+                }
+                Some(("migrate", matches)) => {
+                    swap::migrate_pool(context, matches)
+                }
+                Some(("creator", matches)) => {
+                    match matches.subcommand() {
+                        Some(("set-authority", matches)) => {
+                            swap::set_creator_authority(context, matches)
+                        }
+                        Some(("withdraw", matches)) => {
+                            swap::withdraw_creator_fee(context, matches)
+                        }
+                        Some(("propose-fee", matches)) => {
+                            swap::propose_fee_change(context, matches)
+                        }
+                        Some(("apply-fee", matches)) => {
+                            swap::apply_fee_change(context, matches)
+                        }
+                        _ => unreachable!()
+                    }
+                }
                 _ => unreachable!()
             }
         }
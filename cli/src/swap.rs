@@ -3,35 +3,84 @@ use clap::ArgMatches;
 use dialoguer::Confirm;
 use dialoguer::theme::ColorfulTheme;
 use solana_account_decoder::parse_token::UiTokenAccount;
-use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_client::rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig};
 use solana_sdk::account::ReadableAccount;
-use solana_sdk::signature::{Keypair, read_keypair_file};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Keypair, read_keypair_file, Signature};
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
-use spl_associated_token_account::get_associated_token_address;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_transaction_status::UiTransactionEncoding;
+use spl_token::native_mint;
+use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id};
 use spl_token::{amount_to_ui_amount, ui_amount_to_amount};
-use hodor_program::swap::instruction::SwapInstruction;
-use hodor_program::swap::state::SwapPool;
-use crate::{Context, Error};
+use hodor_program::swap::instruction::{calculate_deposit_single_amounts, calculate_swap_amounts, calculate_swap_in_amount,
+                                       calculate_withdraw_single_amounts, FeeRates, PoolToken, SwapInstruction};
+use hodor_program::config::state::HodorConfig;
+use hodor_program::error::HodorError;
+use hodor_program::swap::curve::Curve;
+use hodor_program::swap::event::{FeeAmounts, SwapEvent};
+use hodor_program::swap::quote::SwapQuote;
+use hodor_program::swap::state::{FeeChangeProposal, SwapPool};
+use crate::{Context, Error, print_transaction_result};
+use crate::config::get_config;
 
 pub fn create_pool(context: Context, matches: &ArgMatches) -> Result<(), Error> {
-    let mint_a = Pubkey::from_str(matches.value_of("TOKEN-A").unwrap())
-        .map_err(|_| format!("Invalid token address"))?;
+    let mut mint_a = Pubkey::from_str(matches.value_of("TOKEN-A").unwrap())
+        .map_err(|_| "Invalid token address".to_string())?;
 
-    let mint_b = Pubkey::from_str(matches.value_of("TOKEN-B").unwrap())
-        .map_err(|_| format!("Invalid token address"))?;
+    let mut mint_b = Pubkey::from_str(matches.value_of("TOKEN-B").unwrap())
+        .map_err(|_| "Invalid token address".to_string())?;
 
-    let lp_fee_rate = (f64::from_str(matches.value_of("LP-FEE-RATE").unwrap())? * 1_000_000 as f64) as u32;
+    let canonical = matches.is_present("canonical");
+    if canonical && mint_a > mint_b {
+        // canonical pool has mints sorted
+        std::mem::swap(&mut mint_a, &mut mint_b);
+    }
+
+    let lp_fee_rate = (f64::from_str(matches.value_of("LP-FEE-RATE").unwrap())? * 1_000_000_f64) as u32;
     let creator_fee_rate = matches.value_of("CREATOR-FEE-RATE")
-        .map_or(Ok(0 as u32), |v| {
-            f64::from_str(v).map(|v| (v * 1_000_000 as f64) as u32)
+        .map_or(Ok(0_u32), |v| {
+            f64::from_str(v).map(|v| (v * 1_000_000_f64) as u32)
         })?;
 
+    let curve = match (matches.value_of("stable"), matches.value_of("weight")) {
+        (Some(amp), _) => Curve::StableSwap {
+            amp: u64::from_str(amp).map_err(|_| "Invalid amplification coefficient".to_string())?,
+        },
+        (_, Some(weight)) => Curve::Weighted {
+            weight_a: (f64::from_str(weight)? * (Curve::WEIGHT_BASE / 100) as f64) as u64,
+        },
+        _ => Curve::ConstantProduct,
+    };
+
+    if !curve.is_valid() {
+        return Err(match curve {
+            Curve::Weighted { .. } => format!("Token weight must be between {}% and {}%",
+                                              Curve::MIN_WEIGHT * 100 / Curve::WEIGHT_BASE,
+                                              100 - Curve::MIN_WEIGHT * 100 / Curve::WEIGHT_BASE),
+            _ => format!("Amplification coefficient must be between {} and {}", Curve::MIN_AMP, Curve::MAX_AMP),
+        }.into());
+    }
+
+    let config = get_config(&context)?;
+    if !config.is_pool_fee_valid(lp_fee_rate, creator_fee_rate) {
+        return Err(format!("Fee rates exceed config bounds, LP: {}%, creator: {}%",
+                           config.max_lp_fee_rate as f64 / 1_000_000.0,
+                           config.max_creator_fee_rate as f64 / 1_000_000.0).into());
+    }
+
     // todo: should be part of context
-    let payer_keypair = read_keypair_file(context.cli_config.keypair_path)?;
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
 
-    let (seed, state_account) = {
+    let (seed, state_account) = if canonical {
+        let (state_account, _, seed) = SwapPool::find_canonical_address(
+            &mint_a, &mint_b, lp_fee_rate, curve.curve_type(), &context.program_id);
+        (seed, state_account)
+    } else {
         let mut random_pubkey = Keypair::new().pubkey();
         let mut program_address;
         loop {
@@ -51,6 +100,9 @@ pub fn create_pool(context: Context, matches: &ArgMatches) -> Result<(), Error>
     println!("Mint A: {}", mint_a);
     println!("Mint B: {}", mint_b);
 
+    let token_a_program = get_token_program(&context, &mint_a)?;
+    let token_b_program = get_token_program(&context, &mint_b)?;
+
     let seed_a = [state_account.as_ref(), b"A"];
     let (token_a_account, _) = Pubkey::find_program_address(&seed_a, &context.program_id);
     println!("Token A account: {}", token_a_account);
@@ -61,10 +113,11 @@ pub fn create_pool(context: Context, matches: &ArgMatches) -> Result<(), Error>
 
     let seeds_mint = [state_account.as_ref(), b"LP"];// todo: make it helper method in program ?
     let (lp_mint_account, _) = Pubkey::find_program_address(&seeds_mint, &context.program_id);
+    let (locked_lp_account, _) = SwapPool::find_locked_lp_address(&state_account, &context.program_id);
 
     let create_swap_pool_instruction = Instruction::new_with_bytes(
         context.program_id,
-        &SwapInstruction::pack(&SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate }),
+        &SwapInstruction::pack(&SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate, curve, canonical }),
         vec![
             AccountMeta::new(payer_keypair.pubkey(), true),
             AccountMeta::new(state_account, false),
@@ -73,8 +126,12 @@ pub fn create_pool(context: Context, matches: &ArgMatches) -> Result<(), Error>
             AccountMeta::new_readonly(mint_b, false),
             AccountMeta::new(token_b_account, false),
             AccountMeta::new(lp_mint_account, false),
+            AccountMeta::new(locked_lp_account, false),
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(token_a_program, false),
+            AccountMeta::new_readonly(token_b_program, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
         ],
     );
 
@@ -86,14 +143,18 @@ pub fn create_pool(context: Context, matches: &ArgMatches) -> Result<(), Error>
     );
 
     let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
-    println!("Transaction {:?}", transaction_result);
+    print_transaction_result(transaction_result);
 
     Ok(())
 }
 
 pub fn deposit(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    if matches.is_present("only") {
+        return deposit_single(context, matches);
+    }
+
     let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
-        .map_err(|_| format!("Invalid swap pool account"))?;
+        .map_err(|_| "Invalid swap pool account".to_string())?;
 
     // todo: should be part of context
     let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
@@ -102,24 +163,30 @@ pub fn deposit(context: Context, matches: &ArgMatches) -> Result<(), Error> {
         = get_pool_state_and_token_accounts(&context, &pool_key)?;
 
     let mint_a = Pubkey::from_str(&pool_account_a.mint)?;
+    let token_a_program = get_token_program(&context, &mint_a)?;
 
     let amount_a = spl_token::ui_amount_to_amount(
         f64::from_str(matches.value_of("AMOUNT-A").unwrap())?,
         pool_account_a.token_amount.decimals);
 
+    let mut sol_wrapper = SolWrapper::default();
+
     // todo: possibility to override through CLI param
-    let source_account_a_key = get_associated_token_address(&payer_keypair.pubkey(), &mint_a);
+    let source_account_a_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &mint_a, &token_a_program, amount_a)?;
 
     // todo: read account A state & check if enough balance
 
     let mint_b = Pubkey::from_str(&pool_account_b.mint)?;
+    let token_b_program = get_token_program(&context, &mint_b)?;
 
     let amount_b = spl_token::ui_amount_to_amount(
         f64::from_str(matches.value_of("AMOUNT-B").unwrap())?,
         pool_account_b.token_amount.decimals);
 
     // todo: possibility to override through CLI param
-    let source_account_b_key = get_associated_token_address(&payer_keypair.pubkey(), &mint_b);
+    let source_account_b_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &mint_b, &token_b_program, amount_b)?;
 
     // todo: read account B state & check if enough balance
 
@@ -132,6 +199,7 @@ pub fn deposit(context: Context, matches: &ArgMatches) -> Result<(), Error> {
             &payer_keypair.pubkey(),
             &payer_keypair.pubkey(),
             &pool_state.lp_mint,
+            &spl_token::id(),
         ));
     }
 
@@ -152,28 +220,126 @@ pub fn deposit(context: Context, matches: &ArgMatches) -> Result<(), Error> {
             AccountMeta::new(pool_state.lp_mint, false),
             AccountMeta::new(lp_destination, false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(SwapPool::find_locked_lp_address(&pool_key, &context.program_id).0, false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(token_a_program, false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(token_b_program, false),
         ],
     ));
 
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&payer_keypair.pubkey()),
-        &[&payer_keypair],
-        context.rpc_client.get_latest_blockhash()?,
-    );
+    let transaction = sol_wrapper.transaction(&context, &payer_keypair, instructions)?;
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+fn deposit_single(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let (pool_state, pool_account_a, pool_account_b)
+        = get_pool_state_and_token_accounts(&context, &pool_key)?;
+    let config = get_config(&context)?;
+
+    let only_mint = Pubkey::from_str(matches.value_of("only").unwrap())
+        .map_err(|_| "Invalid token address".to_string())?;
+
+    let (token, pool_account_key, pool_account, pool_in_amount, pool_other_amount)
+        = if only_mint == Pubkey::from_str(&pool_account_a.mint)? {
+        (PoolToken::A, pool_state.token_account_a, &pool_account_a, pool_state.balance_a, pool_state.balance_b)
+    } else if only_mint == Pubkey::from_str(&pool_account_b.mint)? {
+        (PoolToken::B, pool_state.token_account_b, &pool_account_b, pool_state.balance_b, pool_state.balance_a)
+    } else {
+        return Err("Provided token isn't part of swap pool".into());
+    };
+
+    let amount_in = spl_token::ui_amount_to_amount(
+        f64::from_str(matches.value_of("AMOUNT-A").unwrap())?,
+        pool_account.token_amount.decimals);
+
+    let token_program = get_token_program(&context, &only_mint)?;
+
+    let mut sol_wrapper = SolWrapper::default();
+
+    // todo: possibility to override through CLI param
+    let source_account_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &only_mint, &token_program, amount_in)?;
+
+    let lp_mint = context.rpc_client.get_token_supply_with_commitment(&pool_state.lp_mint, context.commitment)?.value;
+    let lp_supply = u64::from_str(&lp_mint.amount)?;
+
+    let (expected_lp_amount, _, _, _) = calculate_deposit_single_amounts(
+        &pool_state.curve()?.oriented(token),
+        pool_in_amount,
+        pool_other_amount,
+        lp_supply,
+        amount_in,
+        FeeRates { dao: config.dao_fee_rate, lp: pool_state.lp_fee_rate, creator: pool_state.creator_fee_rate() },
+    ).ok_or("Failed to calculate expected LP amount".to_string())?;
+
+    // todo: slippage control through CLI, for now hardcoded 1%
+    let min_lp_out = expected_lp_amount - (expected_lp_amount / 100);
+
+    println!("Expected received LP amount: {}", amount_to_ui_amount(expected_lp_amount, lp_mint.decimals));
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let mut instructions = Vec::new();
+
+    // todo: configurable through param
+    let lp_destination = get_associated_token_address(&payer_keypair.pubkey(), &pool_state.lp_mint);
+    if context.rpc_client.get_token_account(&lp_destination).is_err() {
+        instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
+            &payer_keypair.pubkey(),
+            &payer_keypair.pubkey(),
+            &pool_state.lp_mint,
+            &spl_token::id(),
+        ));
+    }
+
+    instructions.push(Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::DepositSingle { token, amount_in, min_lp_out }),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new(source_account_key, false),
+            AccountMeta::new(pool_account_key, false),
+            AccountMeta::new(pool_state.lp_mint, false),
+            AccountMeta::new(lp_destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+            AccountMeta::new_readonly(only_mint, false),
+            AccountMeta::new_readonly(token_program, false),
+        ],
+    ));
+
+    let transaction = sol_wrapper.transaction(&context, &payer_keypair, instructions)?;
 
     let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
-    println!("Transaction {:?}", transaction_result);
+    print_transaction_result(transaction_result);
 
     Ok(())
 }
 
 pub fn print_info(context: Context, matches: &ArgMatches) -> Result<(), Error> {
     let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
-        .map_err(|_| format!("Invalid swap pool account"))?;
+        .map_err(|_| "Invalid swap pool account".to_string())?;
 
     let (pool_state, token_acc_a, token_acc_b)
         = get_pool_state_and_token_accounts(&context, &pool_key)?;
+    let config = get_config(&context)?;
 
     println!("Token A:");
     println!("MINT: {}", token_acc_a.mint);
@@ -185,12 +351,36 @@ pub fn print_info(context: Context, matches: &ArgMatches) -> Result<(), Error> {
     println!("Account: {}", pool_state.token_account_b);
     println!("Balance: {}", amount_to_ui_amount(pool_state.balance_b, token_acc_b.token_amount.decimals));
     println!();
+    println!("Token account balances (tracked / actual / difference):");
+    let (tracked_a, tracked_b) = pool_state.tracked_amounts()
+        .ok_or("Tracked pool balances overflow".to_string())?;
+    print_balance_difference("A", tracked_a, u64::from_str(&token_acc_a.token_amount.amount)?,
+                             token_acc_a.token_amount.decimals);
+    print_balance_difference("B", tracked_b, u64::from_str(&token_acc_b.token_amount.amount)?,
+                             token_acc_b.token_amount.decimals);
+    println!();
     println!("LP MINT: {}", pool_state.lp_mint);
+    println!("Canonical: {}", if pool_state.canonical_bump().is_some() { "yes" } else { "no" });
+    if pool_state.creator != Pubkey::default() {
+        println!("Creator: {}", pool_state.creator);
+    }
+    if pool_state.is_paused() {
+        println!("Paused: swaps and deposits are disabled, withdrawals still work");
+    }
     println!();
 
-    let creator_fee = pool_state.creator_fee.as_ref().map_or(0, |cf| cf.rate);
+    match pool_state.curve()? {
+        Curve::ConstantProduct => println!("Curve: constant product"),
+        Curve::StableSwap { amp } => println!("Curve: stable swap, amplification: {}", amp),
+        Curve::Weighted { weight_a } => println!("Curve: weighted, weights: A: {}%, B: {}%",
+                                                 weight_a as f64 * 100.0 / Curve::WEIGHT_BASE as f64,
+                                                 (Curve::WEIGHT_BASE - weight_a) as f64 * 100.0 / Curve::WEIGHT_BASE as f64),
+    }
+    println!();
+
+    let creator_fee = pool_state.creator_fee_rate();
     let total_fee = pool_state.lp_fee_rate
-        .checked_add(50_000).unwrap() // dao 0.05% - todo: read from config acc
+        .checked_add(config.dao_fee_rate).unwrap()
         .checked_add(creator_fee).unwrap();
 
     let fee_rate_divider = 1_000_000.0;
@@ -198,11 +388,25 @@ pub fn print_info(context: Context, matches: &ArgMatches) -> Result<(), Error> {
     println!("Fee: {}% (LP: {}%, DAO: {}%, creator: {}%)",
              (total_fee as f64 / fee_rate_divider),
              (pool_state.lp_fee_rate as f64 / fee_rate_divider),
-             (50_000 as f64 / fee_rate_divider),
+             (config.dao_fee_rate as f64 / fee_rate_divider),
              (creator_fee as f64 / fee_rate_divider),
     );
 
-    if let Some(creator_fee) = &pool_state.creator_fee {
+    println!();
+    println!("DAO:");
+    println!("Uncollected fees: A: {}, B: {}",
+             amount_to_ui_amount(pool_state.dao_fee_balance_a, token_acc_a.token_amount.decimals),
+             amount_to_ui_amount(pool_state.dao_fee_balance_b, token_acc_b.token_amount.decimals)
+    );
+    println!("Vault owner: {}", config.dao_fee_vault_owner);
+
+    println!();
+    println!("Price oracle:");
+    println!("Last update timestamp: {}", { pool_state.last_update_timestamp });
+    println!("Cumulative prices (UQ64.64): A: {}, B: {}",
+             { pool_state.price_a_cumulative }, { pool_state.price_b_cumulative });
+
+    if let Some(creator_fee) = pool_state.creator_fee() {
         println!();
         println!("Creator:");
         println!("Uncollected fees: A: {}, B: {}",
@@ -210,40 +414,54 @@ pub fn print_info(context: Context, matches: &ArgMatches) -> Result<(), Error> {
                  amount_to_ui_amount(creator_fee.balance_b, token_acc_b.token_amount.decimals)
         );
         println!("Withdraw authority: {}", creator_fee.withdraw_authority);
+
+        if let Some(proposal) = get_fee_change_proposal(&context, &pool_key)? {
+            println!("Proposed fee: LP: {}%, creator: {}%, can be applied after: {}",
+                     proposal.lp_fee_rate as f64 / fee_rate_divider,
+                     proposal.creator_fee_rate as f64 / fee_rate_divider,
+                     proposal.apply_after);
+        }
     }
 
     Ok(())
 }
 
+fn print_balance_difference(token: &str, tracked: u64, actual: u64, decimals: u8) {
+    let difference = if actual >= tracked {
+        format!("{}", amount_to_ui_amount(actual - tracked, decimals))
+    } else {
+        format!("-{}", amount_to_ui_amount(tracked - actual, decimals))
+    };
+
+    println!("{}: {} / {} / {}", token, amount_to_ui_amount(tracked, decimals),
+             amount_to_ui_amount(actual, decimals), difference);
+}
+
 pub fn swap(context: Context, matches: &ArgMatches) -> Result<(), Error> {
     let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
-        .map_err(|_| format!("Invalid swap pool account"))?;
+        .map_err(|_| "Invalid swap pool account".to_string())?;
 
     // todo: should be part of context
     let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
 
     let (pool_state, pool_acc_a, pool_acc_b)
         = get_pool_state_and_token_accounts(&context, &pool_key)?;
+    let config = get_config(&context)?;
 
     let pool_mint_a = Pubkey::from_str(&pool_acc_a.mint)?;
     let pool_mint_b = Pubkey::from_str(&pool_acc_b.mint)?;
+    let token_a_program = get_token_program(&context, &pool_mint_a)?;
+    let token_b_program = get_token_program(&context, &pool_mint_b)?;
 
     let input_account_key = Pubkey::from_str(matches.value_of("INPUT-ACCOUNT").unwrap())
-        .map_err(|_| format!("Invalid input account"))?;
+        .map_err(|_| "Invalid input account".to_string())?;
 
+    // Source account is resolved after input amount is known, native SOL input has to be wrapped
     let (in_source_key, in_destination_key, in_destination_acc) = {
         if input_account_key == pool_mint_a {
-            (
-                get_associated_token_address(&payer_keypair.pubkey(), &pool_mint_a),
-                pool_state.token_account_a,
-                &pool_acc_a
-            )
+            (None, pool_state.token_account_a, &pool_acc_a)
         } else if input_account_key == pool_mint_b {
-            (
-                get_associated_token_address(&payer_keypair.pubkey(), &pool_mint_b),
-                pool_state.token_account_b,
-                &pool_acc_b
-            )
+            (None, pool_state.token_account_b, &pool_acc_b)
         } else {
             let input_account = context.rpc_client.get_token_account_with_commitment(
                 &input_account_key, context.commitment,
@@ -252,49 +470,75 @@ pub fn swap(context: Context, matches: &ArgMatches) -> Result<(), Error> {
             let mint = Pubkey::from_str(&input_account.mint)?;
 
             if mint == pool_mint_a {
-                (input_account_key, pool_state.token_account_a, &pool_acc_a)
+                (Some(input_account_key), pool_state.token_account_a, &pool_acc_a)
             } else if mint == pool_mint_b {
-                (input_account_key, pool_state.token_account_b, &pool_acc_b)
+                (Some(input_account_key), pool_state.token_account_b, &pool_acc_b)
             } else {
-                return Err(format!("Provided token account is of incorrect mint").try_into()?);
+                return Err("Provided token account is of incorrect mint".into());
             }
         }
     };
 
-    let (out_source_key, out_source_acc, pool_balance_in, pool_balance_out)
+    let (out_source_key, out_source_acc, pool_balance_in, pool_balance_out, in_token)
         = if in_destination_key == pool_state.token_account_a {
-        (pool_state.token_account_b, &pool_acc_b, pool_state.balance_a, pool_state.balance_b)
+        (pool_state.token_account_b, &pool_acc_b, pool_state.balance_a, pool_state.balance_b, PoolToken::A)
     } else {
-        (pool_state.token_account_a, &pool_acc_a, pool_state.balance_b, pool_state.balance_a)
+        (pool_state.token_account_a, &pool_acc_a, pool_state.balance_b, pool_state.balance_a, PoolToken::B)
     };
 
-    let out_destination_key = {
-        // todo: possibility to set through CLI
-        let mint = Pubkey::from_str(&out_source_acc.mint)?;
-        get_associated_token_address(&payer_keypair.pubkey(), &mint)
+    let ((in_mint, in_token_program), (out_mint, out_token_program)) = match in_token {
+        PoolToken::A => ((pool_mint_a, token_a_program), (pool_mint_b, token_b_program)),
+        PoolToken::B => ((pool_mint_b, token_b_program), (pool_mint_a, token_a_program)),
     };
 
-    let in_amount = matches.value_of("INPUT-AMOUNT")
-        .map(|v| f64::from_str(v).map_err(|_| format!("Provided input amount is incorrect")))
-        .ok_or(format!("Missing input amount"))?
-        .map(|v| ui_amount_to_amount(v, in_destination_acc.token_amount.decimals))?;
-
-    let (expected_out_amount, _, _, _) = hodor_program::swap::instruction::calculate_swap_amounts(
-        pool_balance_in,
-        pool_balance_out,
-        in_amount,
-        50_000, // hardcoded 0.05% - needs to be read from config acc
-        pool_state.lp_fee_rate,
-        pool_state.creator_fee.as_ref()
-            .map_or(0, |cf| cf.rate),
-    ).ok_or(format!("Failed to calculate expected swap out amount"))?;
+    let (swap_instruction, max_spent_amount) = if let Some(out_amount) = matches.value_of("exact-out") {
+        let out_amount = f64::from_str(out_amount)
+            .map(|v| ui_amount_to_amount(v, out_source_acc.token_amount.decimals))
+            .map_err(|_| "Provided output amount is incorrect".to_string())?;
 
-    // todo: slippage control through CLI, for now hardcoded 1%
-    let min_out_amount = expected_out_amount - (expected_out_amount / 100);
+        let (expected_in_amount, _, _, _) = calculate_swap_in_amount(
+            &pool_state.curve()?.oriented(in_token),
+            pool_balance_in,
+            pool_balance_out,
+            out_amount,
+            FeeRates { dao: config.dao_fee_rate, lp: pool_state.lp_fee_rate, creator: pool_state.creator_fee_rate() },
+        ).ok_or("Failed to calculate expected swap in amount".to_string())?;
+
+        // todo: slippage control through CLI, for now hardcoded 1%
+        let max_in_amount = expected_in_amount + (expected_in_amount / 100);
+
+        println!("Expected spent token amount: {}",
+                 amount_to_ui_amount(expected_in_amount, in_destination_acc.token_amount.decimals));
+
+        (SwapInstruction::SwapExactOut { out_amount, max_in_amount }, max_in_amount)
+    } else {
+        let in_amount = matches.value_of("INPUT-AMOUNT")
+            .map(|v| f64::from_str(v).map_err(|_| "Provided input amount is incorrect".to_string()))
+            .ok_or("Missing input amount".to_string())?
+            .map(|v| ui_amount_to_amount(v, in_destination_acc.token_amount.decimals))?;
+
+        let quote_data = simulate_quote(
+            &context,
+            &payer_keypair.pubkey(),
+            SwapInstruction::Quote { in_token, in_amount },
+            vec![
+                AccountMeta::new_readonly(pool_key, false),
+                AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+            ],
+        )?;
+        let expected_out_amount = SwapQuote::unpack(&quote_data)
+            .map_err(|_| "Failed to calculate expected swap out amount".to_string())?
+            .out_amount;
+
+        // todo: slippage control through CLI, for now hardcoded 1%
+        let min_out_amount = expected_out_amount - (expected_out_amount / 100);
+
+        println!("Expected received token amount: {}",
+                 amount_to_ui_amount(expected_out_amount, out_source_acc.token_amount.decimals));
+
+        (SwapInstruction::Swap { in_amount, min_out_amount }, in_amount)
+    };
 
-    println!("Expected received token amount: {}",
-             amount_to_ui_amount(expected_out_amount, out_source_acc.token_amount.decimals));
-    
     if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt("Do you want to execute transaction?")
         .interact()
@@ -302,9 +546,21 @@ pub fn swap(context: Context, matches: &ArgMatches) -> Result<(), Error> {
         return Ok(());
     }
 
+    let mut sol_wrapper = SolWrapper::default();
+
+    let in_source_key = match in_source_key {
+        Some(in_source_key) => in_source_key,
+        None => sol_wrapper.token_account(
+            &context, &payer_keypair.pubkey(), &in_mint, &in_token_program, max_spent_amount)?,
+    };
+
+    // todo: possibility to set through CLI
+    let out_destination_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &out_mint, &out_token_program, 0)?;
+
     let instruction = Instruction::new_with_bytes(
         context.program_id,
-        &SwapInstruction::pack(&SwapInstruction::Swap { in_amount, min_out_amount }),
+        &SwapInstruction::pack(&swap_instruction),
         vec![
             AccountMeta::new(payer_keypair.pubkey(), true),
             AccountMeta::new(pool_key, false),
@@ -312,26 +568,136 @@ pub fn swap(context: Context, matches: &ArgMatches) -> Result<(), Error> {
             AccountMeta::new(in_destination_key, false),
             AccountMeta::new(out_source_key, false),
             AccountMeta::new(out_destination_key, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(in_mint, false),
+            AccountMeta::new_readonly(in_token_program, false),
+            AccountMeta::new_readonly(out_mint, false),
+            AccountMeta::new_readonly(out_token_program, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
         ],
     );
 
-    let transaction = Transaction::new_signed_with_payer(
-        &[instruction],
-        Some(&payer_keypair.pubkey()),
-        &[&payer_keypair],
-        context.rpc_client.get_latest_blockhash()?,
+    let transaction = sol_wrapper.transaction(&context, &payer_keypair, vec![instruction])?;
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn swap_route(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let input_mint = Pubkey::from_str(matches.value_of("INPUT-MINT").unwrap())
+        .map_err(|_| "Invalid input mint".to_string())?;
+
+    let pool_keys = matches.values_of("POOL-ACCOUNTS").unwrap()
+        .map(|v| Pubkey::from_str(v).map_err(|_| format!("Invalid swap pool account: {}", v)))
+        .collect::<Result<Vec<Pubkey>, String>>()?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+    let config = get_config(&context)?;
+
+    let input_token_program = get_token_program(&context, &input_mint)?;
+
+    let mut hop_accounts = Vec::new();
+    let mut hop_mint = input_mint;
+    let mut hop_token_program = input_token_program;
+    let mut out_decimals = 0;
+    let mut expected_out_amount = 0;
+    let mut in_amount = 0;
+
+    for (index, pool_key) in pool_keys.iter().enumerate() {
+        let (pool_state, pool_acc_a, pool_acc_b)
+            = get_pool_state_and_token_accounts(&context, pool_key)?;
+
+        let (pool_input_key, pool_output_key, in_acc, out_acc, pool_balance_in, pool_balance_out, in_token)
+            = if hop_mint == Pubkey::from_str(&pool_acc_a.mint)? {
+            (pool_state.token_account_a, pool_state.token_account_b, &pool_acc_a, &pool_acc_b,
+             pool_state.balance_a, pool_state.balance_b, PoolToken::A)
+        } else if hop_mint == Pubkey::from_str(&pool_acc_b.mint)? {
+            (pool_state.token_account_b, pool_state.token_account_a, &pool_acc_b, &pool_acc_a,
+             pool_state.balance_b, pool_state.balance_a, PoolToken::B)
+        } else {
+            return Err(format!("Swap pool {} doesn't contain token {}", pool_key, hop_mint).into());
+        };
+
+        // First hop input amount is provided by user, every next one is expected output of previous hop
+        if index == 0 {
+            in_amount = f64::from_str(matches.value_of("INPUT-AMOUNT").unwrap())
+                .map(|v| ui_amount_to_amount(v, in_acc.token_amount.decimals))
+                .map_err(|_| "Provided input amount is incorrect".to_string())?;
+            expected_out_amount = in_amount;
+        }
+
+        let (hop_out_amount, _, _, _) = calculate_swap_amounts(
+            &pool_state.curve()?.oriented(in_token),
+            pool_balance_in,
+            pool_balance_out,
+            expected_out_amount,
+            FeeRates { dao: config.dao_fee_rate, lp: pool_state.lp_fee_rate, creator: pool_state.creator_fee_rate() },
+        ).ok_or("Failed to calculate expected swap out amount".to_string())?;
+
+        expected_out_amount = hop_out_amount;
+        out_decimals = out_acc.token_amount.decimals;
+        hop_mint = Pubkey::from_str(&out_acc.mint)?;
+        hop_token_program = get_token_program(&context, &hop_mint)?;
+
+        hop_accounts.push(AccountMeta::new(*pool_key, false));
+        hop_accounts.push(AccountMeta::new(pool_input_key, false));
+        hop_accounts.push(AccountMeta::new(pool_output_key, false));
+        hop_accounts.push(AccountMeta::new_readonly(hop_mint, false));
+        hop_accounts.push(AccountMeta::new_readonly(hop_token_program, false));
+    }
+
+    // todo: slippage control through CLI, for now hardcoded 1%
+    let min_final_out = expected_out_amount - (expected_out_amount / 100);
+
+    println!("Route: {} -> {}", input_mint, hop_mint);
+    println!("Expected received token amount: {}", amount_to_ui_amount(expected_out_amount, out_decimals));
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let mut sol_wrapper = SolWrapper::default();
+    let source_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &input_mint, &input_token_program, in_amount)?;
+    let destination_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &hop_mint, &hop_token_program, 0)?;
+
+    let mut accounts = vec![
+        AccountMeta::new(payer_keypair.pubkey(), true),
+        AccountMeta::new(source_key, false),
+        AccountMeta::new(destination_key, false),
+        AccountMeta::new_readonly(input_mint, false),
+        AccountMeta::new_readonly(input_token_program, false),
+        AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+    ];
+    accounts.extend(hop_accounts);
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::SwapRoute { in_amount, min_final_out }),
+        accounts,
     );
 
+    let transaction = sol_wrapper.transaction(&context, &payer_keypair, vec![instruction])?;
+
     let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
-    println!("Transaction {:?}", transaction_result);
+    print_transaction_result(transaction_result);
 
     Ok(())
 }
 
 pub fn withdraw(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    if matches.is_present("only") {
+        return withdraw_single(context, matches);
+    }
+
     let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
-        .map_err(|_| format!("Invalid swap pool account"))?;
+        .map_err(|_| "Invalid swap pool account".to_string())?;
 
     // todo: should be part of context
     let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
@@ -339,33 +705,25 @@ pub fn withdraw(context: Context, matches: &ArgMatches) -> Result<(), Error> {
     let (pool_state, pool_account_a, pool_account_b)
         = get_pool_state_and_token_accounts(&context, &pool_key)?;
 
-    let lp_account_key = get_associated_token_address(
-        &payer_keypair.pubkey(),
-        &pool_state.lp_mint);
-
-    let lp_account = context.rpc_client.get_token_account_with_commitment(
-        &lp_account_key, context.commitment)?
-        .value.ok_or(format!("Unable to resolve source LP account: {}, mint: {}", lp_account_key, pool_state.lp_mint))?;
-
-    let lp_amount = matches.value_of("LP-AMOUNT")
-        .map_or_else(
-            || u64::from_str(lp_account.token_amount.amount.as_str())
-                .map_err(|_| format!("Unable to read available LP token amount")),
-            |v| f64::from_str(v).map_err(|_| format!("Provided LP amount is incorrect"))
-                .map(|v| ui_amount_to_amount(v, lp_account.token_amount.decimals)),
-        )?;
+    let (lp_account_key, lp_amount) = get_source_lp_account_and_amount(&context, matches, &payer_keypair, &pool_state)?;
 
     // todo: slippage
 
     let mint_a = Pubkey::from_str(&pool_account_a.mint)?;
+    let token_a_program = get_token_program(&context, &mint_a)?;
+
+    let mut sol_wrapper = SolWrapper::default();
 
     // todo: possibility to override through CLI param
-    let destination_account_a_key = get_associated_token_address(&payer_keypair.pubkey(), &mint_a);
+    let destination_account_a_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &mint_a, &token_a_program, 0)?;
 
     let mint_b = Pubkey::from_str(&pool_account_b.mint)?;
+    let token_b_program = get_token_program(&context, &mint_b)?;
 
     // todo: possibility to override through CLI param
-    let destination_account_b_key = get_associated_token_address(&payer_keypair.pubkey(), &mint_b);
+    let destination_account_b_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &mint_b, &token_b_program, 0)?;
 
     // todo: option to create destination token accounts
 
@@ -386,6 +744,55 @@ pub fn withdraw(context: Context, matches: &ArgMatches) -> Result<(), Error> {
             AccountMeta::new(pool_state.lp_mint, false),
             AccountMeta::new(lp_account_key, false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(token_a_program, false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(token_b_program, false),
+        ],
+    );
+
+    let transaction = sol_wrapper.transaction(&context, &payer_keypair, vec![instruction])?;
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn set_creator_authority(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    let new_authority = Pubkey::from_str(matches.value_of("NEW-AUTHORITY").unwrap())
+        .map_err(|_| "Invalid new authority address".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let pool_state = get_pool_state(&context, &pool_key)?;
+    let creator_fee = pool_state.creator_fee()
+        .ok_or("Swap pool doesn't have creator fee".to_string())?;
+
+    if creator_fee.withdraw_authority != payer_keypair.pubkey() {
+        return Err(format!("Current withdraw authority is {}", creator_fee.withdraw_authority).into());
+    }
+
+    println!("Current withdraw authority: {}", creator_fee.withdraw_authority);
+    println!("New withdraw authority: {}", new_authority);
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::ChangeCreatorWithdrawAuthority { new_authority }),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
         ],
     );
 
@@ -397,19 +804,814 @@ pub fn withdraw(context: Context, matches: &ArgMatches) -> Result<(), Error> {
     );
 
     let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
-    println!("Transaction {:?}", transaction_result);
+    print_transaction_result(transaction_result);
 
     Ok(())
 }
 
-fn get_pool_state(context: &Context, pool_state_account: &Pubkey) -> Result<SwapPool, Error> {
-    let account = context.rpc_client.get_account_with_commitment(
-        pool_state_account,
-        context.commitment,
-    )?.value.ok_or(format!("Swap pool doesn't exist"))?;
+pub fn withdraw_creator_fee(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let (pool_state, pool_account_a, pool_account_b)
+        = get_pool_state_and_token_accounts(&context, &pool_key)?;
+
+    let creator_fee = pool_state.creator_fee()
+        .ok_or("Swap pool doesn't have creator fee".to_string())?;
+
+    if creator_fee.withdraw_authority != payer_keypair.pubkey() {
+        return Err(format!("Withdraw authority is {}", creator_fee.withdraw_authority).into());
+    }
+
+    let amount_a = matches.value_of("AMOUNT-A")
+        .map_or(Ok(creator_fee.balance_a), |v| f64::from_str(v)
+            .map_err(|_| "Provided amount A is incorrect".to_string())
+            .map(|v| ui_amount_to_amount(v, pool_account_a.token_amount.decimals)))?;
+
+    let amount_b = matches.value_of("AMOUNT-B")
+        .map_or(Ok(creator_fee.balance_b), |v| f64::from_str(v)
+            .map_err(|_| "Provided amount B is incorrect".to_string())
+            .map(|v| ui_amount_to_amount(v, pool_account_b.token_amount.decimals)))?;
+
+    println!("Claimable fees: A: {}, B: {}",
+             amount_to_ui_amount(creator_fee.balance_a, pool_account_a.token_amount.decimals),
+             amount_to_ui_amount(creator_fee.balance_b, pool_account_b.token_amount.decimals)
+    );
+    println!("Withdrawing: A: {}, B: {}",
+             amount_to_ui_amount(amount_a, pool_account_a.token_amount.decimals),
+             amount_to_ui_amount(amount_b, pool_account_b.token_amount.decimals)
+    );
+
+    if amount_a > creator_fee.balance_a || amount_b > creator_fee.balance_b {
+        return Err("Withdraw amount exceeds claimable fees".into());
+    }
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let mint_a = Pubkey::from_str(&pool_account_a.mint)?;
+    let mint_b = Pubkey::from_str(&pool_account_b.mint)?;
+    let token_a_program = get_token_program(&context, &mint_a)?;
+    let token_b_program = get_token_program(&context, &mint_b)?;
+
+    // todo: possibility to override through CLI param
+    let destination_account_a_key = get_associated_token_address_with_program_id(
+        &payer_keypair.pubkey(), &mint_a, &token_a_program);
+    let destination_account_b_key = get_associated_token_address_with_program_id(
+        &payer_keypair.pubkey(), &mint_b, &token_b_program);
+
+    let mut instructions = Vec::new();
+
+    for (destination, mint, token_program) in [(&destination_account_a_key, &mint_a, &token_a_program),
+                                               (&destination_account_b_key, &mint_b, &token_b_program)] {
+        if context.rpc_client.get_token_account(destination).is_err() {
+            instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
+                &payer_keypair.pubkey(),
+                &payer_keypair.pubkey(),
+                mint,
+                token_program,
+            ));
+        }
+    }
+
+    instructions.push(Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::WithdrawCreatorFee { amount_a, amount_b }),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new(pool_state.token_account_a, false),
+            AccountMeta::new(destination_account_a_key, false),
+            AccountMeta::new(pool_state.token_account_b, false),
+            AccountMeta::new(destination_account_b_key, false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(token_a_program, false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(token_b_program, false),
+        ],
+    ));
 
-    Ok(SwapPool::unpack(account.data())
-        .map_err(|_| format!("Provided account is not a swap pool"))?)
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn collect_dao_fees(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let (pool_state, pool_account_a, pool_account_b)
+        = get_pool_state_and_token_accounts(&context, &pool_key)?;
+    let config = get_config(&context)?;
+
+    println!("Uncollected fees: A: {}, B: {}",
+             amount_to_ui_amount(pool_state.dao_fee_balance_a, pool_account_a.token_amount.decimals),
+             amount_to_ui_amount(pool_state.dao_fee_balance_b, pool_account_b.token_amount.decimals)
+    );
+
+    let mint_a = Pubkey::from_str(&pool_account_a.mint)?;
+    let mint_b = Pubkey::from_str(&pool_account_b.mint)?;
+    let token_a_program = get_token_program(&context, &mint_a)?;
+    let token_b_program = get_token_program(&context, &mint_b)?;
+
+    let vault_account_a_key = get_associated_token_address_with_program_id(
+        &config.dao_fee_vault_owner, &mint_a, &token_a_program);
+    let vault_account_b_key = get_associated_token_address_with_program_id(
+        &config.dao_fee_vault_owner, &mint_b, &token_b_program);
+
+    let mut instructions = Vec::new();
+
+    for (vault, mint, token_program) in [(&vault_account_a_key, &mint_a, &token_a_program),
+                                         (&vault_account_b_key, &mint_b, &token_b_program)] {
+        if context.rpc_client.get_token_account(vault).is_err() {
+            instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
+                &payer_keypair.pubkey(),
+                &config.dao_fee_vault_owner,
+                mint,
+                token_program,
+            ));
+        }
+    }
+
+    instructions.push(Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::CollectDaoFees),
+        vec![
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new(pool_state.token_account_a, false),
+            AccountMeta::new(vault_account_a_key, false),
+            AccountMeta::new(pool_state.token_account_b, false),
+            AccountMeta::new(vault_account_b_key, false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(token_a_program, false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(token_b_program, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+        ],
+    ));
+
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn propose_fee_change(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    let lp_fee_rate = (f64::from_str(matches.value_of("LP-FEE-RATE").unwrap())? * 1_000_000.0) as u32;
+    let creator_fee_rate = (f64::from_str(matches.value_of("CREATOR-FEE-RATE").unwrap())? * 1_000_000.0) as u32;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let pool_state = get_pool_state(&context, &pool_key)?;
+    let config = get_config(&context)?;
+    check_fee_change_authority(&pool_state, &config, &payer_keypair.pubkey())?;
+
+    if pool_state.creator_fee().is_none() && creator_fee_rate != 0 {
+        return Err("Creator fee can't be enabled for swap pool created without it".into());
+    }
+
+    if pool_state.canonical_bump().is_some() && lp_fee_rate != pool_state.lp_fee_rate {
+        return Err("LP fee of canonical pool can't be changed".into());
+    }
+
+    if !config.is_pool_fee_valid(lp_fee_rate, creator_fee_rate) {
+        return Err(format!("Fee rates exceed config bounds, LP: {}%, creator: {}%",
+                           config.max_lp_fee_rate as f64 / 1_000_000.0,
+                           config.max_creator_fee_rate as f64 / 1_000_000.0).into());
+    }
+
+    println!("Current fee: LP: {}%, creator: {}%", pool_state.lp_fee_rate as f64 / 1_000_000.0,
+             pool_state.creator_fee_rate() as f64 / 1_000_000.0);
+    println!("Proposed fee: LP: {}%, creator: {}%", lp_fee_rate as f64 / 1_000_000.0,
+             creator_fee_rate as f64 / 1_000_000.0);
+    println!("Change can be applied in {} seconds", config.fee_change_delay);
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::ProposeFeeChange { lp_fee_rate, creator_fee_rate }),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new_readonly(pool_key, false),
+            AccountMeta::new(FeeChangeProposal::find_address(&pool_key, &context.program_id).0, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn apply_fee_change(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let pool_state = get_pool_state(&context, &pool_key)?;
+    check_fee_change_authority(&pool_state, &get_config(&context)?, &payer_keypair.pubkey())?;
+
+    let proposal = get_fee_change_proposal(&context, &pool_key)?
+        .ok_or("Swap pool doesn't have pending fee change".to_string())?;
+
+    println!("New fee: LP: {}%, creator: {}%", proposal.lp_fee_rate as f64 / 1_000_000.0,
+             proposal.creator_fee_rate as f64 / 1_000_000.0);
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::ApplyFeeChange),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new(FeeChangeProposal::find_address(&pool_key, &context.program_id).0, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+        ],
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+// Fees of pool without creator fee are changed by config admin
+fn check_fee_change_authority(pool_state: &SwapPool, config: &HodorConfig, payer: &Pubkey) -> Result<(), Error> {
+    let authority = pool_state.creator_fee()
+        .map_or(config.admin, |creator_fee| creator_fee.withdraw_authority);
+
+    if authority != *payer {
+        return Err(format!("Fee change authority is {}", authority).into());
+    }
+
+    Ok(())
+}
+
+pub fn set_paused(context: Context, matches: &ArgMatches, paused: bool) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let pool_state = get_pool_state(&context, &pool_key)?;
+    if pool_state.is_paused() == paused {
+        println!("Swap pool is already {}", if paused { "paused" } else { "unpaused" });
+        return Ok(());
+    }
+
+    let config = get_config(&context)?;
+    if config.guardian != payer_keypair.pubkey() {
+        return Err(format!("Config guardian is {}", config.guardian).into());
+    }
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(if paused { &SwapInstruction::Pause } else { &SwapInstruction::Unpause }),
+        vec![
+            AccountMeta::new_readonly(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+        ],
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn close_pool(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let (pool_state, pool_account_a, pool_account_b)
+        = get_pool_state_and_token_accounts(&context, &pool_key)?;
+    let config = get_config(&context)?;
+
+    // pools created before creator was recorded are closed by config admin
+    let authority = if pool_state.creator == Pubkey::default() { config.admin } else { pool_state.creator };
+    if authority != payer_keypair.pubkey() {
+        return Err(format!("Swap pool can be closed only by {}", authority).into());
+    }
+
+    let (locked_lp_key, _) = SwapPool::find_locked_lp_address(&pool_key, &context.program_id);
+    let lp_supply = context.rpc_client.get_token_supply(&pool_state.lp_mint)?;
+    let locked_lp_amount = context.rpc_client.get_token_account_balance(&locked_lp_key)?;
+    if lp_supply.amount != locked_lp_amount.amount {
+        return Err(format!("All LP tokens have to be withdrawn, LP supply: {}, locked: {}",
+                           lp_supply.ui_amount_string, locked_lp_amount.ui_amount_string).into());
+    }
+
+    println!("Remaining tokens: A: {}, B: {}",
+             pool_account_a.token_amount.ui_amount_string, pool_account_b.token_amount.ui_amount_string);
+
+    let mint_a = Pubkey::from_str(&pool_account_a.mint)?;
+    let mint_b = Pubkey::from_str(&pool_account_b.mint)?;
+    let token_a_program = get_token_program(&context, &mint_a)?;
+    let token_b_program = get_token_program(&context, &mint_b)?;
+
+    // remaining tokens back locked liquidity or weren't tracked by pool, they go to DAO vaults
+    let vault_account_a_key = get_associated_token_address_with_program_id(
+        &config.dao_fee_vault_owner, &mint_a, &token_a_program);
+    let vault_account_b_key = get_associated_token_address_with_program_id(
+        &config.dao_fee_vault_owner, &mint_b, &token_b_program);
+
+    let mut instructions = Vec::new();
+
+    for (vault, mint, token_program) in [(&vault_account_a_key, &mint_a, &token_a_program),
+                                         (&vault_account_b_key, &mint_b, &token_b_program)] {
+        if context.rpc_client.get_token_account(vault).is_err() {
+            instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
+                &payer_keypair.pubkey(),
+                &config.dao_fee_vault_owner,
+                mint,
+                token_program,
+            ));
+        }
+    }
+
+    instructions.push(Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::ClosePool),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new(pool_state.token_account_a, false),
+            AccountMeta::new(vault_account_a_key, false),
+            AccountMeta::new(pool_state.token_account_b, false),
+            AccountMeta::new(vault_account_b_key, false),
+            AccountMeta::new(pool_state.lp_mint, false),
+            AccountMeta::new(locked_lp_key, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(token_a_program, false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(token_b_program, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+        ],
+    ));
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to close swap pool?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+pub fn print_events(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let signature = Signature::from_str(matches.value_of("SIGNATURE").unwrap())
+        .map_err(|_| "Invalid transaction signature".to_string())?;
+
+    let transaction = context.rpc_client.get_transaction_with_config(&signature, RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    })?;
+
+    let log_messages: Option<Vec<String>> = transaction.transaction.meta
+        .and_then(|meta| meta.log_messages.into());
+    let log_messages = log_messages.ok_or("Transaction logs are not available".to_string())?;
+
+    // other programs can log data too, only entries logged directly by this program are decoded
+    let program_invoke = format!("Program {} invoke [", context.program_id);
+    let mut invoke_stack = Vec::new();
+    let mut events_count = 0;
+
+    for log in log_messages {
+        if log.starts_with("Program ") && log.contains(" invoke [") {
+            invoke_stack.push(log.starts_with(&program_invoke));
+        } else if log.starts_with("Program ") && (log.ends_with(" success") || log.contains(" failed: ")) {
+            invoke_stack.pop();
+        } else if let Some(data) = log.strip_prefix("Program data: ") {
+            if invoke_stack.last() != Some(&true) {
+                continue;
+            }
+
+            for field in data.split(' ') {
+                let event = base64::decode(field).ok()
+                    .and_then(|bytes| SwapEvent::unpack(&bytes).ok());
+
+                match event {
+                    Some(event) => print_event(&event),
+                    None => println!("Unknown event data: {}", field),
+                }
+                events_count += 1;
+            }
+        }
+    }
+
+    if events_count == 0 {
+        println!("Transaction has no swap pool events");
+    }
+
+    Ok(())
+}
+
+// Amounts are printed in token base units, as they are logged by program
+fn print_event(event: &SwapEvent) {
+    fn format_fees(fees: &FeeAmounts) -> String {
+        format!("DAO: {}, LP: {}, creator: {}", fees.dao, fees.lp, fees.creator)
+    }
+
+    fn format_token(token: &PoolToken) -> &'static str {
+        match token {
+            PoolToken::A => "A",
+            PoolToken::B => "B",
+        }
+    }
+
+    match event {
+        SwapEvent::Swap { pool, user, in_token, in_amount, out_amount, fees, balance_a, balance_b } => {
+            let out_token = match in_token {
+                PoolToken::A => PoolToken::B,
+                PoolToken::B => PoolToken::A,
+            };
+            println!("Swap: pool: {}, user: {}", pool, user);
+            println!("In: {} {}, out: {} {}", in_amount, format_token(in_token), out_amount, format_token(&out_token));
+            println!("Fees: {}", format_fees(fees));
+            println!("Pool balances: A: {}, B: {}", balance_a, balance_b);
+        }
+        SwapEvent::Deposit { pool, user, amount_a, amount_b, lp_amount, fees, balance_a, balance_b } => {
+            println!("Deposit: pool: {}, user: {}", pool, user);
+            println!("Deposited: A: {}, B: {}, LP minted: {}", amount_a, amount_b, lp_amount);
+            println!("Fees: {}", format_fees(fees));
+            println!("Pool balances: A: {}, B: {}", balance_a, balance_b);
+        }
+        SwapEvent::Withdraw { pool, user, lp_amount, amount_a, amount_b, fees, balance_a, balance_b } => {
+            println!("Withdraw: pool: {}, user: {}", pool, user);
+            println!("LP burned: {}, withdrawn: A: {}, B: {}", lp_amount, amount_a, amount_b);
+            println!("Fees: {}", format_fees(fees));
+            println!("Pool balances: A: {}, B: {}", balance_a, balance_b);
+        }
+        SwapEvent::FlashLoan { pool, receiver, token, amount, fees, balance_a, balance_b } => {
+            println!("Flash loan: pool: {}, receiver program: {}", pool, receiver);
+            println!("Loaned: {} {}", amount, format_token(token));
+            println!("Fees: {}", format_fees(fees));
+            println!("Pool balances: A: {}, B: {}", balance_a, balance_b);
+        }
+        SwapEvent::PauseChanged { pool, guardian, paused } => {
+            println!("{}: pool: {}, guardian: {}", if *paused { "Pause" } else { "Unpause" }, pool, guardian);
+        }
+    }
+    println!();
+}
+
+pub fn migrate_pool(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let account = context.rpc_client.get_account_with_commitment(&pool_key, context.commitment)?
+        .value.ok_or("Swap pool doesn't exist".to_string())?;
+
+    if SwapPool::unpack(account.data()).is_ok() {
+        println!("Swap pool is already in current layout version");
+        return Ok(());
+    }
+
+    let pool_state = SwapPool::unpack_outdated(account.data())
+        .map_err(|_| "Provided account is not a swap pool".to_string())?;
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::MigratePool),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new_readonly(pool_state.token_account_a, false),
+            AccountMeta::new_readonly(pool_state.token_account_b, false),
+            AccountMeta::new_readonly(pool_state.lp_mint, false),
+            AccountMeta::new(SwapPool::find_locked_lp_address(&pool_key, &context.program_id).0, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        context.rpc_client.get_latest_blockhash()?,
+    );
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+fn withdraw_single(context: Context, matches: &ArgMatches) -> Result<(), Error> {
+    let pool_key = Pubkey::from_str(matches.value_of("POOL-ACCOUNT").unwrap())
+        .map_err(|_| "Invalid swap pool account".to_string())?;
+
+    // todo: should be part of context
+    let payer_keypair = read_keypair_file(&context.cli_config.keypair_path)?;
+
+    let (pool_state, pool_account_a, pool_account_b)
+        = get_pool_state_and_token_accounts(&context, &pool_key)?;
+
+    let (lp_account_key, lp_amount) = get_source_lp_account_and_amount(&context, matches, &payer_keypair, &pool_state)?;
+
+    let only_mint = Pubkey::from_str(matches.value_of("only").unwrap())
+        .map_err(|_| "Invalid token address".to_string())?;
+
+    let (token, pool_account_key, pool_account, pool_out_amount, pool_other_amount)
+        = if only_mint == Pubkey::from_str(&pool_account_a.mint)? {
+        (PoolToken::A, pool_state.token_account_a, &pool_account_a, pool_state.balance_a, pool_state.balance_b)
+    } else if only_mint == Pubkey::from_str(&pool_account_b.mint)? {
+        (PoolToken::B, pool_state.token_account_b, &pool_account_b, pool_state.balance_b, pool_state.balance_a)
+    } else {
+        return Err("Provided token isn't part of swap pool".into());
+    };
+
+    let config = get_config(&context)?;
+    let lp_supply = u64::from_str(&context.rpc_client.get_token_supply_with_commitment(
+        &pool_state.lp_mint, context.commitment)?.value.amount)?;
+
+    let (expected_out_amount, _, _, _) = calculate_withdraw_single_amounts(
+        &pool_state.curve()?.oriented(token),
+        pool_out_amount,
+        pool_other_amount,
+        lp_supply,
+        lp_amount,
+        FeeRates { dao: config.dao_fee_rate, lp: pool_state.lp_fee_rate, creator: pool_state.creator_fee_rate() },
+    ).ok_or("Failed to calculate expected withdraw amount".to_string())?;
+
+    // todo: slippage control through CLI, for now hardcoded 1%
+    let min_out = expected_out_amount - (expected_out_amount / 100);
+
+    println!("Expected received token amount: {}",
+             amount_to_ui_amount(expected_out_amount, pool_account.token_amount.decimals));
+
+    if !context.assume_yes && !Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Do you want to execute transaction?")
+        .interact()
+        .unwrap() {
+        return Ok(());
+    }
+
+    let token_program = get_token_program(&context, &only_mint)?;
+
+    let mut sol_wrapper = SolWrapper::default();
+
+    // todo: possibility to override through CLI param
+    let destination_account_key = sol_wrapper.token_account(
+        &context, &payer_keypair.pubkey(), &only_mint, &token_program, 0)?;
+
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &SwapInstruction::pack(&SwapInstruction::WithdrawSingle { lp_amount, token, min_out }),
+        vec![
+            AccountMeta::new(payer_keypair.pubkey(), true),
+            AccountMeta::new(pool_key, false),
+            AccountMeta::new(pool_account_key, false),
+            AccountMeta::new(destination_account_key, false),
+            AccountMeta::new(pool_state.lp_mint, false),
+            AccountMeta::new(lp_account_key, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&context.program_id).0, false),
+            AccountMeta::new_readonly(only_mint, false),
+            AccountMeta::new_readonly(token_program, false),
+        ],
+    );
+
+    let transaction = sol_wrapper.transaction(&context, &payer_keypair, vec![instruction])?;
+
+    let transaction_result = context.rpc_client.send_and_confirm_transaction(&transaction);
+    print_transaction_result(transaction_result);
+
+    Ok(())
+}
+
+fn get_source_lp_account_and_amount(context: &Context, matches: &ArgMatches, payer_keypair: &Keypair, pool_state: &SwapPool)
+                                    -> Result<(Pubkey, u64), Error> {
+    let lp_account_key = get_associated_token_address(
+        &payer_keypair.pubkey(),
+        &pool_state.lp_mint);
+
+    let lp_account = context.rpc_client.get_token_account_with_commitment(
+        &lp_account_key, context.commitment)?
+        .value.ok_or(format!("Unable to resolve source LP account: {}, mint: {}", lp_account_key, pool_state.lp_mint))?;
+
+    let lp_amount = matches.value_of("LP-AMOUNT")
+        .map_or_else(
+            || u64::from_str(lp_account.token_amount.amount.as_str())
+                .map_err(|_| "Unable to read available LP token amount".to_string()),
+            |v| f64::from_str(v).map_err(|_| "Provided LP amount is incorrect".to_string())
+                .map(|v| ui_amount_to_amount(v, lp_account.token_amount.decimals)),
+        )?;
+
+    Ok((lp_account_key, lp_amount))
+}
+
+
+// Native SOL legs use temporary wrapped SOL account instead of associated token account. Account is created
+// and funded with lamports before pool instruction and closed back to lamports after it, in the same transaction
+#[derive(Default)]
+struct SolWrapper {
+    keypairs: Vec<Keypair>,
+    wrap_instructions: Vec<Instruction>,
+    unwrap_instructions: Vec<Instruction>,
+}
+
+impl SolWrapper {
+    // Returns associated token account for mint, or temporary wrapped SOL account holding provided amount
+    fn token_account(&mut self, context: &Context, owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey,
+                     amount: u64) -> Result<Pubkey, Error> {
+        if *mint != native_mint::id() {
+            return Ok(get_associated_token_address_with_program_id(owner, mint, token_program));
+        }
+
+        let keypair = Keypair::new();
+        let account_key = keypair.pubkey();
+        let rent = context.rpc_client.get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+
+        self.wrap_instructions.push(system_instruction::create_account(
+            owner,
+            &account_key,
+            rent.checked_add(amount).ok_or("Wrapped SOL amount overflow".to_string())?,
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ));
+        // Lamports above rent exemption become token amount of native account
+        self.wrap_instructions.push(spl_token::instruction::initialize_account3(
+            &spl_token::id(),
+            &account_key,
+            &native_mint::id(),
+            owner,
+        )?);
+        self.unwrap_instructions.push(spl_token::instruction::close_account(
+            &spl_token::id(),
+            &account_key,
+            owner,
+            owner,
+            &[],
+        )?);
+        self.keypairs.push(keypair);
+
+        Ok(account_key)
+    }
+
+    fn transaction(&self, context: &Context, payer_keypair: &Keypair, instructions: Vec<Instruction>)
+                   -> Result<Transaction, Error> {
+        let instructions: Vec<Instruction> = self.wrap_instructions.iter().cloned()
+            .chain(instructions)
+            .chain(self.unwrap_instructions.iter().cloned())
+            .collect();
+
+        let mut signers: Vec<&dyn Signer> = vec![payer_keypair];
+        signers.extend(self.keypairs.iter().map(|keypair| keypair as &dyn Signer));
+
+        Ok(Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer_keypair.pubkey()),
+            &signers,
+            context.rpc_client.get_latest_blockhash()?,
+        ))
+    }
+}
+
+// Quote instructions return result through return data, transaction is simulated without signature
+fn simulate_quote(context: &Context, payer: &Pubkey, quote_instruction: SwapInstruction, accounts: Vec<AccountMeta>)
+                  -> Result<Vec<u8>, Error> {
+    let instruction = Instruction::new_with_bytes(context.program_id, &quote_instruction.pack(), accounts);
+    let transaction = Transaction::new_with_payer(&[instruction], Some(payer));
+
+    let result = context.rpc_client.simulate_transaction_with_config(&transaction, RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(context.commitment),
+        ..RpcSimulateTransactionConfig::default()
+    })?.value;
+
+    if let Some(error) = result.err {
+        let program_error = match error {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => HodorError::from_code(code),
+            _ => None,
+        };
+
+        return Err(match program_error {
+            Some(error) => format!("Quote failed: {}", error),
+            None => format!("Quote failed: {:?}", error),
+        }.into());
+    }
+
+    match result.return_data {
+        Some(return_data) if return_data.program_id == context.program_id.to_string() => {
+            Ok(base64::decode(return_data.data.0)?)
+        }
+        _ => Err("Quote returned no data".into()),
+    }
+}
+
+// Mint is owned either by SPL token or Token-2022 program
+fn get_token_program(context: &Context, mint: &Pubkey) -> Result<Pubkey, Error> {
+    let account = context.rpc_client.get_account_with_commitment(mint, context.commitment)?
+        .value.ok_or(format!("Token mint {} doesn't exist", mint))?;
+
+    Ok(account.owner)
+}
+
+fn get_pool_state(context: &Context, pool_state_account: &Pubkey) -> Result<SwapPool, Error> {
+    let account = context.rpc_client.get_account_with_commitment(
+        pool_state_account,
+        context.commitment,
+    )?.value.ok_or("Swap pool doesn't exist".to_string())?;
+
+    SwapPool::unpack(account.data())
+        .map_err(|error| match error {
+            error if error == HodorError::OutdatedPoolVersion.into() =>
+                format!("Swap pool has outdated layout, migrate it first with `swap migrate {}`", pool_state_account).into(),
+            _ => "Provided account is not a swap pool".to_string().into(),
+        })
+}
+
+fn get_fee_change_proposal(context: &Context, pool_key: &Pubkey) -> Result<Option<FeeChangeProposal>, Error> {
+    let (proposal_account, _) = FeeChangeProposal::find_address(pool_key, &context.program_id);
+
+    match context.rpc_client.get_account_with_commitment(&proposal_account, context.commitment)?.value {
+        Some(account) => Ok(Some(FeeChangeProposal::unpack(account.data())
+            .map_err(|_| "Failed to read fee change proposal".to_string())?)),
+        None => Ok(None),
+    }
 }
 
 fn get_pool_token_accounts(context: &Context, pool_state: &SwapPool) -> Result<(UiTokenAccount, UiTokenAccount), Error> {
@@ -0,0 +1,171 @@
+use solana_program::program_error::ProgramError;
+use solana_program::program_error::ProgramError::InvalidInstructionData;
+use solana_program::pubkey::Pubkey;
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigInstruction {
+    // 0-0
+    // Initialize program config, only once and only by program upgrade authority
+    // 0. [signer] Fee payer, program upgrade authority
+    // 1. [writeable] Config account - PDA
+    // 2. [] Program data account
+    // 3. [] System program
+    InitializeConfig {
+        admin: Pubkey,
+        dao_fee_rate: u32,
+        dao_fee_vault_owner: Pubkey,
+        max_lp_fee_rate: u32,
+        max_creator_fee_rate: u32,
+        fee_change_delay: i64,
+        guardian: Pubkey,
+    },
+
+    // 0-1
+    // Update program config
+    // 0. [signer] Config admin
+    // 1. [writeable] Config account - PDA
+    UpdateConfig {
+        admin: Pubkey,
+        dao_fee_rate: u32,
+        dao_fee_vault_owner: Pubkey,
+        max_lp_fee_rate: u32,
+        max_creator_fee_rate: u32,
+        fee_change_delay: i64,
+        guardian: Pubkey,
+    },
+}
+
+impl ConfigInstruction {
+    const MODULE_TAG: u8 = 0;
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer.push(ConfigInstruction::MODULE_TAG);
+
+        match self {
+            ConfigInstruction::InitializeConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate,
+                max_creator_fee_rate, fee_change_delay, guardian } => {
+                buffer.push(0);
+                buffer.extend_from_slice(admin.as_ref());
+                buffer.extend_from_slice(&dao_fee_rate.to_le_bytes());
+                buffer.extend_from_slice(dao_fee_vault_owner.as_ref());
+                buffer.extend_from_slice(&max_lp_fee_rate.to_le_bytes());
+                buffer.extend_from_slice(&max_creator_fee_rate.to_le_bytes());
+                buffer.extend_from_slice(&fee_change_delay.to_le_bytes());
+                buffer.extend_from_slice(guardian.as_ref());
+            }
+            ConfigInstruction::UpdateConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate,
+                max_creator_fee_rate, fee_change_delay, guardian } => {
+                buffer.push(1);
+                buffer.extend_from_slice(admin.as_ref());
+                buffer.extend_from_slice(&dao_fee_rate.to_le_bytes());
+                buffer.extend_from_slice(dao_fee_vault_owner.as_ref());
+                buffer.extend_from_slice(&max_lp_fee_rate.to_le_bytes());
+                buffer.extend_from_slice(&max_creator_fee_rate.to_le_bytes());
+                buffer.extend_from_slice(&fee_change_delay.to_le_bytes());
+                buffer.extend_from_slice(guardian.as_ref());
+            }
+        };
+
+        buffer
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (module_tag, rest) = input.split_first().ok_or(InvalidInstructionData)?;
+        if *module_tag != ConfigInstruction::MODULE_TAG {
+            return Err(InvalidInstructionData);
+        }
+
+        let (tag, rest) = rest.split_first().ok_or(InvalidInstructionData)?;
+
+        match tag {
+            0 | 1 => {
+                let admin = rest.get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstructionData)?;
+
+                let dao_fee_rate = rest.get(32..36)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let dao_fee_vault_owner = rest.get(36..68)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstructionData)?;
+
+                let max_lp_fee_rate = rest.get(68..72)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let max_creator_fee_rate = rest.get(72..76)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let fee_change_delay = rest.get(76..84)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(i64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let guardian = rest.get(84..116)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstructionData)?;
+
+                if *tag == 0 {
+                    Ok(ConfigInstruction::InitializeConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate,
+                        max_creator_fee_rate, fee_change_delay, guardian })
+                } else {
+                    Ok(ConfigInstruction::UpdateConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate,
+                        max_creator_fee_rate, fee_change_delay, guardian })
+                }
+            }
+            _ => Err(InvalidInstructionData)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_config_instruction() {
+        let initialize_instruction = ConfigInstruction::InitializeConfig {
+            admin: Pubkey::new_unique(),
+            dao_fee_rate: 50_000,
+            dao_fee_vault_owner: Pubkey::new_unique(),
+            max_lp_fee_rate: 1_000_000,
+            max_creator_fee_rate: 500_000,
+            fee_change_delay: 3_600,
+            guardian: Pubkey::new_unique(),
+        };
+        assert_eq!(initialize_instruction, ConfigInstruction::unpack(&initialize_instruction.pack()).unwrap());
+
+        let update_instruction = ConfigInstruction::UpdateConfig {
+            admin: Pubkey::new_unique(),
+            dao_fee_rate: 10_000,
+            dao_fee_vault_owner: Pubkey::new_unique(),
+            max_lp_fee_rate: 1_000_000,
+            max_creator_fee_rate: 500_000,
+            fee_change_delay: 3_600,
+            guardian: Pubkey::new_unique(),
+        };
+        assert_eq!(update_instruction, ConfigInstruction::unpack(&update_instruction.pack()).unwrap());
+        assert_ne!(update_instruction, ConfigInstruction::unpack(&ConfigInstruction::InitializeConfig {
+            admin: Pubkey::new_unique(),
+            dao_fee_rate: 10_000,
+            dao_fee_vault_owner: Pubkey::new_unique(),
+            max_lp_fee_rate: 1_000_000,
+            max_creator_fee_rate: 500_000,
+            fee_change_delay: 3_600,
+            guardian: Pubkey::new_unique(),
+        }.pack()).unwrap());
+
+        assert!(ConfigInstruction::unpack(&update_instruction.pack()[..100]).is_err());
+    }
+}
\ No newline at end of file
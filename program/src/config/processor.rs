@@ -0,0 +1,165 @@
+use solana_program::account_info::{AccountInfo, next_account_info};
+use solana_program::bpf_loader_upgradeable;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::program_error::ProgramError::{IllegalOwner, InvalidAccountData, InvalidSeeds, MissingRequiredSignature};
+use solana_program::pubkey::Pubkey;
+use crate::config::instruction::ConfigInstruction;
+use crate::config::state::HodorConfig;
+use crate::error::HodorError;
+use crate::processor::create_pda_account;
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    match ConfigInstruction::unpack(instruction_data)? {
+        ConfigInstruction::InitializeConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate,
+            max_creator_fee_rate, fee_change_delay, guardian } => {
+            msg!("Config:InitializeConfig");
+            process_initialize_config(program_id, accounts, HodorConfig {
+                admin,
+                dao_fee_rate,
+                dao_fee_vault_owner,
+                max_lp_fee_rate,
+                max_creator_fee_rate,
+                fee_change_delay,
+                guardian,
+            })
+        }
+        ConfigInstruction::UpdateConfig { admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate,
+            max_creator_fee_rate, fee_change_delay, guardian } => {
+            msg!("Config:UpdateConfig");
+            process_update_config(program_id, accounts, HodorConfig {
+                admin,
+                dao_fee_rate,
+                dao_fee_vault_owner,
+                max_lp_fee_rate,
+                max_creator_fee_rate,
+                fee_change_delay,
+                guardian,
+            })
+        }
+    }
+}
+
+pub(crate) fn load_config(program_id: &Pubkey, config_info: &AccountInfo) -> Result<HodorConfig, ProgramError> {
+    if config_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    HodorConfig::unpack(&config_info.try_borrow_data()?)
+}
+
+fn process_initialize_config(program_id: &Pubkey, accounts: &[AccountInfo], config: HodorConfig) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let fee_payer_info = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+    let program_data_info = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !fee_payer_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    let (program_data_account, _) = Pubkey::find_program_address(
+        &[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if *program_data_info.key != program_data_account || *program_data_info.owner != bpf_loader_upgradeable::id() {
+        return Err(InvalidAccountData);
+    }
+
+    if get_upgrade_authority(&program_data_info.try_borrow_data()?)? != Some(*fee_payer_info.key) {
+        return Err(HodorError::InvalidAuthority.into());
+    }
+
+    let (config_account, bump_seed) = HodorConfig::find_address(program_id);
+    if *config_info.key != config_account {
+        return Err(InvalidSeeds);
+    }
+
+    if !config.is_valid() {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    create_pda_account(
+        config_info,
+        HodorConfig::SIZE,
+        program_id,
+        fee_payer_info,
+        system_program,
+        &[HodorConfig::SEED, &[bump_seed]],
+    )?;
+
+    config.pack(&mut config_info.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
+
+fn process_update_config(program_id: &Pubkey, accounts: &[AccountInfo], new_config: HodorConfig) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin_info = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if !admin_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    let config = load_config(program_id, config_info)?;
+    if config.admin != *admin_info.key {
+        return Err(HodorError::InvalidAuthority.into());
+    }
+
+    if !new_config.is_valid() {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    new_config.pack(&mut config_info.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
+
+// Reads upgrade authority from serialized UpgradeableLoaderState::ProgramData:
+// u32 enum tag (3), u64 slot, Option<Pubkey> upgrade authority
+fn get_upgrade_authority(program_data: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    let tag = program_data.get(..4)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(InvalidAccountData)?;
+
+    if tag != 3 {
+        return Err(InvalidAccountData);
+    }
+
+    match program_data.get(12) {
+        Some(0) => Ok(None),
+        Some(1) => program_data.get(13..45)
+            .and_then(|slice| slice.try_into().ok())
+            .map(|authority| Some(Pubkey::new_from_array(authority)))
+            .ok_or(InvalidAccountData),
+        _ => Err(InvalidAccountData),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_upgrade_authority() {
+        let authority = Pubkey::new_unique();
+
+        let mut program_data = vec![0u8; 45];
+        program_data[..4].copy_from_slice(&3u32.to_le_bytes());
+        program_data[4..12].copy_from_slice(&1234u64.to_le_bytes());
+        assert_eq!(None, get_upgrade_authority(&program_data).unwrap());
+
+        program_data[12] = 1;
+        program_data[13..45].copy_from_slice(authority.as_ref());
+        assert_eq!(Some(authority), get_upgrade_authority(&program_data).unwrap());
+
+        assert!(get_upgrade_authority(&program_data[..20]).is_err());
+
+        program_data[..4].copy_from_slice(&2u32.to_le_bytes());
+        assert!(get_upgrade_authority(&program_data).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,146 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use crate::swap::instruction::is_total_fee_rate_valid;
+
+#[derive(Debug, PartialEq)]
+pub struct HodorConfig {
+    pub admin: Pubkey,
+    pub dao_fee_rate: u32,
+    pub dao_fee_vault_owner: Pubkey,
+    // Upper bounds of pool fee rates, checked on pool creation and fee change
+    pub max_lp_fee_rate: u32,
+    pub max_creator_fee_rate: u32,
+    // Seconds between fee change proposal and its earliest application
+    pub fee_change_delay: i64,
+    // Can pause and unpause swap pools in emergency
+    pub guardian: Pubkey,
+}
+
+impl HodorConfig {
+    pub const SIZE: usize = 1 + 32 + 4 + 32 + 4 + 4 + 8 + 32;
+    pub const TYPE_MARKER: u8 = 2;
+    pub const SEED: &'static [u8] = b"config";
+    // 1% - upper bound protecting traders from misconfigured DAO fee
+    pub const MAX_DAO_FEE_RATE: u32 = 1_000_000;
+    // Default bounds of new config: 10% LP fee, 5% creator fee, 3 days delay
+    pub const DEFAULT_MAX_LP_FEE_RATE: u32 = 10_000_000;
+    pub const DEFAULT_MAX_CREATOR_FEE_RATE: u32 = 5_000_000;
+    pub const DEFAULT_FEE_CHANGE_DELAY: i64 = 3 * 24 * 60 * 60;
+
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[HodorConfig::SEED], program_id)
+    }
+
+    pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() != HodorConfig::SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let dst_ref = array_mut_ref![dst, 0, HodorConfig::SIZE];
+        let (type_marker_dst, admin_dst, dao_fee_rate_dst, dao_fee_vault_owner_dst, max_lp_fee_rate_dst,
+            max_creator_fee_rate_dst, fee_change_delay_dst, guardian_dst)
+            = mut_array_refs![dst_ref, 1, 32, 4, 32, 4, 4, 8, 32];
+
+        *type_marker_dst = [HodorConfig::TYPE_MARKER];
+        admin_dst.copy_from_slice(self.admin.as_ref());
+        *dao_fee_rate_dst = self.dao_fee_rate.to_le_bytes();
+        dao_fee_vault_owner_dst.copy_from_slice(self.dao_fee_vault_owner.as_ref());
+        *max_lp_fee_rate_dst = self.max_lp_fee_rate.to_le_bytes();
+        *max_creator_fee_rate_dst = self.max_creator_fee_rate.to_le_bytes();
+        *fee_change_delay_dst = self.fee_change_delay.to_le_bytes();
+        guardian_dst.copy_from_slice(self.guardian.as_ref());
+
+        Ok(())
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != HodorConfig::SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src_array_ref = array_ref![src, 0, HodorConfig::SIZE];
+        let (type_marker, admin, dao_fee_rate, dao_fee_vault_owner, max_lp_fee_rate, max_creator_fee_rate,
+            fee_change_delay, guardian)
+            = array_refs![src_array_ref, 1, 32, 4, 32, 4, 4, 8, 32];
+
+        if *type_marker != [HodorConfig::TYPE_MARKER] {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(HodorConfig {
+            admin: Pubkey::new_from_array(*admin),
+            dao_fee_rate: u32::from_le_bytes(*dao_fee_rate),
+            dao_fee_vault_owner: Pubkey::new_from_array(*dao_fee_vault_owner),
+            max_lp_fee_rate: u32::from_le_bytes(*max_lp_fee_rate),
+            max_creator_fee_rate: u32::from_le_bytes(*max_creator_fee_rate),
+            fee_change_delay: i64::from_le_bytes(*fee_change_delay),
+            guardian: Pubkey::new_from_array(*guardian),
+        })
+    }
+
+    // Every pool within fee bounds has to be able to swap, fee rates summed with maximal DAO fee stay below 100%
+    pub fn is_valid(&self) -> bool {
+        self.dao_fee_rate <= HodorConfig::MAX_DAO_FEE_RATE
+            && is_total_fee_rate_valid(HodorConfig::MAX_DAO_FEE_RATE, self.max_lp_fee_rate, self.max_creator_fee_rate)
+            && self.fee_change_delay >= 0
+    }
+
+    pub fn is_pool_fee_valid(&self, lp_fee_rate: u32, creator_fee_rate: u32) -> bool {
+        lp_fee_rate <= self.max_lp_fee_rate && creator_fee_rate <= self.max_creator_fee_rate
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_pack_unpack() {
+        let config = HodorConfig {
+            admin: Pubkey::new_unique(),
+            dao_fee_rate: 50_000,
+            dao_fee_vault_owner: Pubkey::new_unique(),
+            max_lp_fee_rate: 2_000_000,
+            max_creator_fee_rate: 1_000_000,
+            fee_change_delay: 86_400,
+            guardian: Pubkey::new_unique(),
+        };
+        let mut state_array = [0u8; HodorConfig::SIZE];
+        config.pack(&mut state_array).unwrap();
+        assert_eq!(config, HodorConfig::unpack(&state_array).unwrap());
+        assert!(config.pack(&mut [0u8; HodorConfig::SIZE + 1]).is_err());
+        assert!(HodorConfig::unpack(&state_array[1..]).is_err());
+
+        state_array[0] = 1;
+        assert!(HodorConfig::unpack(&state_array).is_err());
+    }
+
+    #[test]
+    fn test_config_fee_bounds() {
+        let mut config = HodorConfig {
+            admin: Pubkey::new_unique(),
+            dao_fee_rate: 50_000,
+            dao_fee_vault_owner: Pubkey::new_unique(),
+            max_lp_fee_rate: 2_000_000,
+            max_creator_fee_rate: 1_000_000,
+            fee_change_delay: 86_400,
+            guardian: Pubkey::new_unique(),
+        };
+        assert!(config.is_valid());
+        assert!(config.is_pool_fee_valid(2_000_000, 1_000_000));
+        assert!(config.is_pool_fee_valid(0, 0));
+        assert!(!config.is_pool_fee_valid(2_000_001, 0));
+        assert!(!config.is_pool_fee_valid(0, 1_000_001));
+
+        // bounds leaving no room for maximal DAO fee would allow pools which can't swap
+        config.max_lp_fee_rate = 98_000_000;
+        config.max_creator_fee_rate = 1_000_000;
+        assert!(!config.is_valid());
+
+        config.max_lp_fee_rate = 2_000_000;
+        config.fee_change_delay = -1;
+        assert!(!config.is_valid());
+    }
+}
\ No newline at end of file
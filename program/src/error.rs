@@ -0,0 +1,111 @@
+use std::fmt::{Display, Formatter};
+use solana_program::program_error::ProgramError;
+
+// Codes are part of program interface, never reorder or reuse them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HodorError {
+    MathOverflow = 0,
+    SlippageExceeded = 1,
+    ZeroAmount = 2,
+    ZeroOutputAmount = 3,
+    InvalidFeeRate = 4,
+    InvalidAuthority = 5,
+    CreatorFeeNotEnabled = 6,
+    InsufficientFeeBalance = 7,
+    InvalidDaoFeeVault = 8,
+    EmptyPool = 9,
+    InsufficientLiquidity = 10,
+    InvalidCurve = 11,
+    PoolLocked = 12,
+    FlashLoanNotRepaid = 13,
+    UnsupportedMint = 14,
+    OutdatedPoolVersion = 15,
+    FeeChangeNotReady = 16,
+    PoolPaused = 17,
+    PoolNotEmpty = 18,
+}
+
+impl HodorError {
+    const ALL: [HodorError; 19] = [
+        HodorError::MathOverflow,
+        HodorError::SlippageExceeded,
+        HodorError::ZeroAmount,
+        HodorError::ZeroOutputAmount,
+        HodorError::InvalidFeeRate,
+        HodorError::InvalidAuthority,
+        HodorError::CreatorFeeNotEnabled,
+        HodorError::InsufficientFeeBalance,
+        HodorError::InvalidDaoFeeVault,
+        HodorError::EmptyPool,
+        HodorError::InsufficientLiquidity,
+        HodorError::InvalidCurve,
+        HodorError::PoolLocked,
+        HodorError::FlashLoanNotRepaid,
+        HodorError::UnsupportedMint,
+        HodorError::OutdatedPoolVersion,
+        HodorError::FeeChangeNotReady,
+        HodorError::PoolPaused,
+        HodorError::PoolNotEmpty,
+    ];
+
+    pub fn from_code(code: u32) -> Option<HodorError> {
+        HodorError::ALL.iter()
+            .find(|error| **error as u32 == code)
+            .copied()
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            HodorError::MathOverflow => "Math operation overflow",
+            HodorError::SlippageExceeded => "Slippage tolerance exceeded",
+            HodorError::ZeroAmount => "Amount must be greater than zero",
+            HodorError::ZeroOutputAmount => "Output amount is zero",
+            HodorError::InvalidFeeRate => "Invalid fee rate",
+            HodorError::InvalidAuthority => "Invalid authority",
+            HodorError::CreatorFeeNotEnabled => "Creator fee is not enabled for swap pool",
+            HodorError::InsufficientFeeBalance => "Insufficient accrued fee balance",
+            HodorError::InvalidDaoFeeVault => "Token account isn't owned by DAO fee vault owner",
+            HodorError::EmptyPool => "Swap pool has no liquidity",
+            HodorError::InsufficientLiquidity => "Not enough pool liquidity for operation",
+            HodorError::InvalidCurve => "Invalid swap curve parameters",
+            HodorError::PoolLocked => "Swap pool is locked by ongoing flash loan",
+            HodorError::FlashLoanNotRepaid => "Flash loan wasn't repaid with fee",
+            HodorError::UnsupportedMint => "Token mint has extension not supported by swap pool",
+            HodorError::OutdatedPoolVersion => "Swap pool state has outdated layout, it has to be migrated",
+            HodorError::FeeChangeNotReady => "Fee change delay hasn't passed yet",
+            HodorError::PoolPaused => "Swap pool is paused by guardian",
+            HodorError::PoolNotEmpty => "Swap pool has LP tokens or uncollected fees left",
+        }
+    }
+}
+
+impl From<HodorError> for ProgramError {
+    fn from(error: HodorError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
+
+impl Display for HodorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for HodorError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        for (code, error) in HodorError::ALL.iter().enumerate() {
+            assert_eq!(code as u32, *error as u32);
+            assert_eq!(Some(*error), HodorError::from_code(code as u32));
+            assert_eq!(ProgramError::Custom(code as u32), ProgramError::from(*error));
+        }
+
+        assert_eq!(None, HodorError::from_code(HodorError::ALL.len() as u32));
+    }
+}
\ No newline at end of file
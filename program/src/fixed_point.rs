@@ -0,0 +1,205 @@
+// Unsigned fixed point arithmetic with 18 decimals, every operation is overflow checked and rounds
+// in explicitly requested direction, so results are deterministic and can always favor the pool
+use crate::math::{mul_div, mul_div_ceil, sqrt};
+
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+
+// Number of exponent fraction bits evaluated by pow, remaining bits are covered by relative error margin
+const POW_FRACTION_BITS: usize = 40;
+
+// Upper bound of pow relative error caused by exponent truncation and intermediate rounding (1e-10)
+pub const MAX_POW_RELATIVE_ERROR: u128 = 100_000_000;
+
+pub fn from_ratio_down(numerator: u128, denominator: u128) -> Option<u128> {
+    mul_div(numerator, ONE, denominator)
+}
+
+pub fn from_ratio_up(numerator: u128, denominator: u128) -> Option<u128> {
+    mul_div_ceil(numerator, ONE, denominator)
+}
+
+pub fn mul_down(a: u128, b: u128) -> Option<u128> {
+    mul_div(a, b, ONE)
+}
+
+pub fn mul_up(a: u128, b: u128) -> Option<u128> {
+    mul_div_ceil(a, b, ONE)
+}
+
+pub fn div_down(a: u128, b: u128) -> Option<u128> {
+    mul_div(a, ONE, b)
+}
+
+pub fn div_up(a: u128, b: u128) -> Option<u128> {
+    mul_div_ceil(a, ONE, b)
+}
+
+pub fn sqrt_down(value: u128) -> Option<u128> {
+    Some(sqrt(value.checked_mul(ONE)?))
+}
+
+pub fn sqrt_up(value: u128) -> Option<u128> {
+    let scaled_value = value.checked_mul(ONE)?;
+    let root = sqrt(scaled_value);
+
+    if root.checked_mul(root)? < scaled_value {
+        root.checked_add(1)
+    } else {
+        Some(root)
+    }
+}
+
+// Returns base^exponent, result is never greater than exact value
+pub fn pow_down(base: u128, exponent: u128) -> Option<u128> {
+    if exponent == 0 || base == 0 {
+        return pow_approximate(base, exponent, false);
+    }
+
+    let result = pow_approximate(base, exponent, false)?;
+    let max_error = mul_up(result, MAX_POW_RELATIVE_ERROR)?.checked_add(1)?;
+
+    Some(result.saturating_sub(max_error))
+}
+
+// Returns base^exponent, result is never lower than exact value
+pub fn pow_up(base: u128, exponent: u128) -> Option<u128> {
+    if exponent == 0 || base == 0 {
+        return pow_approximate(base, exponent, true);
+    }
+
+    let result = pow_approximate(base, exponent, true)?;
+    let max_error = mul_up(result, MAX_POW_RELATIVE_ERROR)?.checked_add(1)?;
+
+    result.checked_add(max_error)
+}
+
+// x^y = x^integer(y) * product of x^(2^-i) for every set fraction bit i of y,
+// x^(2^-i) is calculated by repeated square roots
+fn pow_approximate(base: u128, exponent: u128, round_up: bool) -> Option<u128> {
+    if exponent == 0 {
+        return Some(ONE);
+    }
+
+    if base == 0 {
+        return Some(0);
+    }
+
+    let mul = if round_up { mul_up } else { mul_down };
+    let root = if round_up { sqrt_up } else { sqrt_down };
+
+    let mut result = ONE;
+    let mut integer_power = base;
+    let mut integer_exponent = exponent / ONE;
+    while integer_exponent > 0 {
+        if integer_exponent & 1 == 1 {
+            result = mul(result, integer_power)?;
+        }
+
+        integer_exponent >>= 1;
+        if integer_exponent > 0 {
+            integer_power = mul(integer_power, integer_power)?;
+        }
+    }
+
+    let mut fraction_root = base;
+    let mut fraction_exponent = exponent % ONE;
+    for _ in 0..POW_FRACTION_BITS {
+        if fraction_exponent == 0 {
+            break;
+        }
+
+        fraction_root = root(fraction_root)?;
+        fraction_exponent *= 2;
+
+        if fraction_exponent >= ONE {
+            fraction_exponent -= ONE;
+            result = mul(result, fraction_root)?;
+        }
+    }
+
+    Some(result)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: u128, actual: u128, relative_tolerance: u128) {
+        let tolerance = mul_up(expected, relative_tolerance).unwrap().max(1);
+        assert!(expected.abs_diff(actual) <= tolerance, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(Some(333_333_333_333_333_333), from_ratio_down(1, 3));
+        assert_eq!(Some(333_333_333_333_333_334), from_ratio_up(1, 3));
+        assert_eq!(Some(ONE / 2), mul_down(ONE, ONE / 2));
+        assert_eq!(Some(0), mul_down(1, 1));
+        assert_eq!(Some(1), mul_up(1, 1));
+        assert_eq!(Some(2 * ONE), div_down(ONE, ONE / 2));
+        assert_eq!(None, div_down(ONE, 0));
+        assert_eq!(None, mul_down(u128::MAX, 2 * ONE));
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(Some(2 * ONE), sqrt_down(4 * ONE));
+        assert_eq!(Some(2 * ONE), sqrt_up(4 * ONE));
+        assert_eq!(Some(1_414_213_562_373_095_048), sqrt_down(2 * ONE));
+        assert_eq!(Some(1_414_213_562_373_095_049), sqrt_up(2 * ONE));
+        assert_eq!(None, sqrt_down(u128::MAX));
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(Some(ONE), pow_down(0, 0));
+        assert_eq!(Some(0), pow_up(0, ONE / 2));
+
+        // 2^3 = 8
+        assert_close(8 * ONE, pow_down(2 * ONE, 3 * ONE).unwrap(), 1_000_000_000);
+
+        // 2^0.5
+        assert_close(1_414_213_562_373_095_048, pow_down(2 * ONE, ONE / 2).unwrap(), 1_000_000_000);
+
+        // 0.5^4 = 0.0625
+        assert_close(62_500_000_000_000_000, pow_up(ONE / 2, 4 * ONE).unwrap(), 1_000_000_000);
+
+        // 0.8^0.25 = 0.945741609003176...
+        assert_close(945_741_609_003_176_000, pow_down(800_000_000_000_000_000, 250_000_000_000_000_000).unwrap(), 1_000_000_000);
+
+        // 1.1^4.3 = 1.506567332...
+        assert_close(1_506_567_332_000_000_000, pow_up(1_100_000_000_000_000_000, 4_300_000_000_000_000_000).unwrap(), 1_000_000_000);
+
+        // 0.9^(1/3) = 0.965489384...
+        assert_close(965_489_384_000_000_000, pow_down(900_000_000_000_000_000, from_ratio_down(1, 3).unwrap()).unwrap(), 1_000_000_000);
+    }
+
+    #[test]
+    fn test_pow_rounding_direction() {
+        let bases = [1, 10_000_000, 500_000_000_000_000_000, 999_999_999_999_999_999, ONE, ONE + 1, 3 * ONE, 1_000_000 * ONE];
+        let exponents = [ONE / 50, ONE / 4, ONE / 3, ONE, 4 * ONE, 49 * ONE / 2];
+
+        for base in bases {
+            for exponent in exponents {
+                let down = pow_down(base, exponent);
+                let up = pow_up(base, exponent);
+
+                match (down, up) {
+                    (Some(down), Some(up)) => assert!(down <= up),
+                    (None, None) => {}
+                    _ => panic!("inconsistent pow overflow for {} ^ {}", base, exponent),
+                }
+            }
+        }
+
+        // exact powers are bracketed
+        assert!(pow_down(2 * ONE, 10 * ONE).unwrap() <= 1024 * ONE);
+        assert!(pow_up(2 * ONE, 10 * ONE).unwrap() >= 1024 * ONE);
+        assert!(pow_down(4 * ONE, ONE / 2).unwrap() <= 2 * ONE);
+        assert!(pow_up(4 * ONE, ONE / 2).unwrap() >= 2 * ONE);
+        assert!(pow_up(ONE / 4, ONE / 2).unwrap() >= ONE / 2);
+
+        assert_eq!(None, pow_down(u128::MAX / 2, 2 * ONE));
+    }
+}
\ No newline at end of file
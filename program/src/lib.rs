@@ -1,3 +1,7 @@
+pub mod config;
+pub mod error;
+pub mod fixed_point;
+pub mod math;
 pub mod swap;
 pub mod processor;
 
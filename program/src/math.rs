@@ -0,0 +1,140 @@
+// Integer square root, rounded down
+pub fn sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's method starting from power of two above the root, strictly decreasing until converged
+    let mut result = 1u128 << (128 - value.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (result + value / result) / 2;
+        if next >= result {
+            return result;
+        }
+        result = next;
+    }
+}
+
+
+// Returns floor(a * b / denominator) using 256 bit intermediate product,
+// None if denominator is zero or result doesn't fit into u128
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+
+    if let Some(product) = a.checked_mul(b) {
+        return Some(product / denominator);
+    }
+
+    let (product_high, product_low) = full_mul(a, b);
+    if product_high >= denominator {
+        return None;
+    }
+
+    // Binary long division, remainder is always lower than denominator
+    let mut remainder = product_high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((product_low >> bit) & 1);
+        quotient <<= 1;
+
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+
+    Some(quotient)
+}
+
+// Returns ceil(a * b / denominator)
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    let result = mul_div(a, b, denominator)?;
+
+    let (product_high, product_low) = full_mul(a, b);
+    let (result_high, result_low) = full_mul(result, denominator);
+
+    if (product_high, product_low) == (result_high, result_low) {
+        Some(result)
+    } else {
+        result.checked_add(1)
+    }
+}
+
+// Returns 256 bit product as (high, low) 128 bit words
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (high_low & MASK) + (low_high & MASK);
+
+    let low = (low_low & MASK) | (middle << 64);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+
+    (high, low)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(0, sqrt(0));
+        assert_eq!(1, sqrt(1));
+        assert_eq!(1, sqrt(2));
+        assert_eq!(1, sqrt(3));
+        assert_eq!(2, sqrt(4));
+        assert_eq!(3, sqrt(15));
+        assert_eq!(4, sqrt(16));
+        assert_eq!(1_000_000, sqrt(1_000_000_000_000));
+        assert_eq!(999_999, sqrt(999_999_999_999));
+        assert_eq!(u64::MAX as u128, sqrt(u128::MAX));
+        assert_eq!(u64::MAX as u128 - 1, sqrt((u64::MAX as u128 - 1) * (u64::MAX as u128 - 1) + 1));
+
+        for value in (1..100_000u128).step_by(7) {
+            let root = sqrt(value);
+            assert!(root * root <= value && (root + 1) * (root + 1) > value);
+        }
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(None, mul_div(1, 1, 0));
+        assert_eq!(Some(0), mul_div(0, u128::MAX, 1));
+        assert_eq!(Some(3), mul_div(7, 3, 7));
+        assert_eq!(Some(2), mul_div(5, 1, 2));
+        assert_eq!(Some(u128::MAX), mul_div(u128::MAX, u128::MAX, u128::MAX));
+        assert_eq!(Some(u128::MAX / 3), mul_div(u128::MAX, 1 << 64, 3 << 64));
+        assert_eq!(Some(1 << 127), mul_div(1 << 127, 1 << 127, 1 << 127));
+        assert_eq!(Some(u128::MAX - 1), mul_div(u128::MAX - 1, u128::MAX, u128::MAX));
+        assert_eq!(Some(u128::MAX - 2), mul_div(u128::MAX - 1, u128::MAX - 1, u128::MAX));
+        assert_eq!(None, mul_div(u128::MAX, 2, 1));
+        assert_eq!(None, mul_div(1 << 64, 1 << 64, 1));
+        assert_eq!(Some(u64::MAX as u128), mul_div(u64::MAX as u128 * 1_000, u64::MAX as u128 * 7, u64::MAX as u128 * 7_000));
+
+        for (a, b, denominator) in [(123_456_789u128, 987_654_321u128, 1_000u128), (u64::MAX as u128, 3, 7), (99, 99, 98)] {
+            assert_eq!(Some(a * b / denominator), mul_div(a, b, denominator));
+        }
+    }
+
+    #[test]
+    fn test_mul_div_ceil() {
+        assert_eq!(Some(3), mul_div_ceil(7, 3, 7));
+        assert_eq!(Some(3), mul_div_ceil(5, 1, 2));
+        assert_eq!(Some(1), mul_div_ceil(1, 1, u128::MAX));
+        assert_eq!(Some(u128::MAX / 3), mul_div_ceil(u128::MAX, 1 << 64, 3 << 64));
+        assert_eq!(Some(u128::MAX - 1), mul_div_ceil(u128::MAX - 1, u128::MAX - 1, u128::MAX));
+        assert_eq!(None, mul_div_ceil(u128::MAX, u128::MAX, u128::MAX - 1));
+    }
+}
\ No newline at end of file
@@ -1,17 +1,27 @@
 use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::program_error::ProgramError::InvalidInstructionData;
+use solana_program::program_error::ProgramError;
+use solana_program::program_error::ProgramError::{IncorrectProgramId, InvalidAccountData, InvalidInstructionData};
 use solana_program::pubkey::Pubkey;
 use solana_program::program_pack::Pack;
-use solana_program::program::{invoke, invoke_signed};
+use solana_program::program::{get_return_data, invoke, invoke_signed};
 use solana_program::rent::Rent;
+use solana_program::system_instruction;
 use solana_program::sysvar::Sysvar;
-use crate::swap;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use crate::error::HodorError;
+use crate::math::mul_div_ceil;
+use crate::{config, swap};
 
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let module_tag = instruction_data.first().ok_or(InvalidInstructionData)?;
 
     match module_tag {
+        0 => {
+            config::processor::process(program_id, accounts, instruction_data)
+        },
         1 => {
             swap::processor::process(program_id, accounts, instruction_data)
         },
@@ -19,43 +29,183 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data:
     }
 }
 
-pub(crate) fn create_spl_token_account<'a>(
-    account: &AccountInfo<'a>, mint: &AccountInfo<'a>, owner: &AccountInfo<'a>, fee_payer: &AccountInfo<'a>, seeds: &[&[u8]],
-    program_id: &Pubkey, spl_token_program: &AccountInfo<'a>, system_program: &AccountInfo<'a>
-) -> ProgramResult {
-    let rent = Rent::get()?;
-    let (token_account, bump_seed) = Pubkey::find_program_address(&seeds, program_id);
-    // todo: should we check account.key == token_account ? Below instructions should fail if it's not true
+pub(crate) fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == spl_token::id() || *program_id == spl_token_2022::id()
+}
 
-    let create_account_instruction = solana_program::system_instruction::create_account(
-        &fee_payer.key,
-        &token_account,
-        rent.minimum_balance(spl_token::state::Account::LEN),
-        spl_token::state::Account::LEN as u64,
-        &spl_token::id(),
-    );
+// Token-2022 accounts have to fit extensions required by mint, size is asked from token program itself
+pub(crate) fn get_token_account_size<'a>(mint: &AccountInfo<'a>, token_program: &AccountInfo<'a>)
+                                         -> Result<usize, ProgramError> {
+    if *token_program.key == spl_token::id() {
+        return Ok(spl_token::state::Account::LEN);
+    }
+
+    if *token_program.key != spl_token_2022::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    let account_size_instruction = spl_token_2022::instruction::get_account_data_size(
+        token_program.key,
+        mint.key,
+        &[],
+    )?;
+
+    invoke(
+        &account_size_instruction,
+        &[
+            token_program.clone(),
+            mint.clone(),
+        ],
+    )?;
+
+    match get_return_data() {
+        Some((program_id, data)) if program_id == *token_program.key => data.as_slice()
+            .try_into()
+            .ok()
+            .map(u64::from_le_bytes)
+            .map(|size| size as usize)
+            .ok_or(InvalidAccountData),
+        _ => Err(InvalidAccountData),
+    }
+}
+
+// Unpacks both SPL token and Token-2022 accounts, extensions are ignored
+pub(crate) fn unpack_token_account(account: &AccountInfo) -> Result<spl_token_2022::state::Account, ProgramError> {
+    let data = account.try_borrow_data()?;
+    Ok(StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?.base)
+}
+
+pub(crate) fn unpack_token_mint(mint: &AccountInfo) -> Result<spl_token_2022::state::Mint, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    Ok(StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?.base)
+}
+
+// Returns amount which has to be sent, so that destination receives provided amount after mint transfer fee
+pub(crate) fn calculate_pre_fee_amount(mint: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    if *mint.owner != spl_token_2022::id() || amount == 0 {
+        return Ok(amount);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let transfer_fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => *transfer_fee_config.get_epoch_fee(Clock::get()?.epoch),
+        Err(_) => return Ok(amount),
+    };
+
+    Ok(calculate_pre_transfer_fee_amount(
+        amount,
+        u16::from(transfer_fee.transfer_fee_basis_points),
+        u64::from(transfer_fee.maximum_fee),
+    ).ok_or(HodorError::MathOverflow)?)
+}
+
+// Transfer fee is rounded up, so pre fee amount of ceil(amount * 10_000 / (10_000 - rate)) leaves at least amount
+fn calculate_pre_transfer_fee_amount(amount: u64, fee_basis_points: u16, maximum_fee: u64) -> Option<u64> {
+    if fee_basis_points == 0 || amount == 0 {
+        return Some(amount);
+    }
+
+    let max_fee_amount = amount.checked_add(maximum_fee)?;
+    let fee_basis_points = fee_basis_points as u128;
+
+    if fee_basis_points >= 10_000 {
+        return Some(max_fee_amount);
+    }
+
+    let remaining_basis_points = 10_000 - fee_basis_points;
+    let pre_fee_amount = mul_div_ceil(amount as u128, 10_000, remaining_basis_points)?;
+
+    Some(u64::try_from(pre_fee_amount).ok()?.min(max_fee_amount))
+}
+
+// Creates rent exempt account at program derived address. Anyone can transfer lamports to the address in advance,
+// create_account of system program fails for such account, so it's topped up, allocated and assigned instead
+pub(crate) fn create_pda_account<'a>(account: &AccountInfo<'a>, space: usize, owner: &Pubkey, fee_payer: &AccountInfo<'a>,
+                                     system_program: &AccountInfo<'a>, signer_seeds: &[&[u8]]) -> ProgramResult {
+    let required_lamports = Rent::get()?.minimum_balance(space);
+
+    if account.lamports() == 0 {
+        let create_account_instruction = system_instruction::create_account(
+            fee_payer.key,
+            account.key,
+            required_lamports,
+            space as u64,
+            owner,
+        );
+
+        return invoke_signed(
+            &create_account_instruction,
+            &[
+                system_program.clone(),
+                fee_payer.clone(),
+                account.clone(),
+            ],
+            &[signer_seeds],
+        );
+    }
+
+    let missing_lamports = required_lamports.saturating_sub(account.lamports());
+    if missing_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(fee_payer.key, account.key, missing_lamports),
+            &[
+                system_program.clone(),
+                fee_payer.clone(),
+                account.clone(),
+            ],
+        )?;
+    }
 
     invoke_signed(
-        &create_account_instruction,
+        &system_instruction::allocate(account.key, space as u64),
         &[
             system_program.clone(),
-            fee_payer.clone(),
             account.clone(),
         ],
-        &[&[&seeds[0], &seeds[1], &[bump_seed]]], // todo seeds explode
+        &[signer_seeds],
     )?;
 
-    let spl_initialize_instruction = spl_token::instruction::initialize_account3(
-        &spl_token_program.key,
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[
+            system_program.clone(),
+            account.clone(),
+        ],
+        &[signer_seeds],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_spl_token_account<'a>(
+    account: &AccountInfo<'a>, mint: &AccountInfo<'a>, owner: &AccountInfo<'a>, fee_payer: &AccountInfo<'a>, seeds: &[&[u8]],
+    program_id: &Pubkey, token_program: &AccountInfo<'a>, system_program: &AccountInfo<'a>
+) -> ProgramResult {
+    let (token_account, bump_seed) = Pubkey::find_program_address(seeds, program_id);
+    // todo: should we check account.key == token_account ? Below instructions should fail if it's not true
+
+    let account_size = get_token_account_size(mint, token_program)?;
+
+    create_pda_account(
+        account,
+        account_size,
+        token_program.key,
+        fee_payer,
+        system_program,
+        &[seeds[0], seeds[1], &[bump_seed]], // todo seeds explode
+    )?;
+
+    let initialize_instruction = spl_token_2022::instruction::initialize_account3(
+        token_program.key,
         &token_account,
-        &mint.key,
-        &owner.key,
+        mint.key,
+        owner.key,
     )?;
 
     invoke(
-        &spl_initialize_instruction,
+        &initialize_instruction,
         &[
-            spl_token_program.clone(),
+            token_program.clone(),
             account.clone(),
             mint.clone(),
         ],
@@ -64,27 +214,104 @@ pub(crate) fn create_spl_token_account<'a>(
     Ok(())
 }
 
-
-pub(crate) fn transfer_spl_token<'a>(source: &AccountInfo<'a>, destination: &AccountInfo<'a>, owner: &AccountInfo<'a>,
-                                     spl_token_program: &AccountInfo<'a>, amount: u64) -> ProgramResult {
-    let transfer_instruction = spl_token::instruction::transfer(
-        &spl_token_program.key,
-        &source.key,
-        &destination.key,
-        &owner.key,
-        &[&owner.key],
+// Returns amount received by destination, which is lower than sent amount for mints with transfer fee
+pub(crate) fn transfer_spl_token<'a>(source: &AccountInfo<'a>, mint: &AccountInfo<'a>, destination: &AccountInfo<'a>,
+                                     owner: &AccountInfo<'a>, token_program: &AccountInfo<'a>, amount: u64)
+                                     -> Result<u64, ProgramError> {
+    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        source.key,
+        mint.key,
+        destination.key,
+        owner.key,
+        &[owner.key],
         amount,
+        unpack_token_mint(mint)?.decimals,
     )?;
 
+    let balance_before = unpack_token_account(destination)?.amount;
+
     invoke(
         &transfer_instruction,
         &[
-            spl_token_program.clone(),
+            token_program.clone(),
             source.clone(),
+            mint.clone(),
             destination.clone(),
             owner.clone(),
         ],
     )?;
 
-    Ok(())
+    calculate_received_amount(destination, balance_before)
+}
+
+pub(crate) fn transfer_spl_token_signed<'a>(source: &AccountInfo<'a>, mint: &AccountInfo<'a>, destination: &AccountInfo<'a>,
+                                            owner: &AccountInfo<'a>, token_program: &AccountInfo<'a>, amount: u64,
+                                            signer_seeds: &[&[u8]]) -> Result<u64, ProgramError> {
+    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        source.key,
+        mint.key,
+        destination.key,
+        owner.key,
+        &[],
+        amount,
+        unpack_token_mint(mint)?.decimals,
+    )?;
+
+    let balance_before = unpack_token_account(destination)?.amount;
+
+    invoke_signed(
+        &transfer_instruction,
+        &[
+            token_program.clone(),
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            owner.clone(),
+        ],
+        &[signer_seeds],
+    )?;
+
+    calculate_received_amount(destination, balance_before)
+}
+
+fn calculate_received_amount(destination: &AccountInfo, balance_before: u64) -> Result<u64, ProgramError> {
+    Ok(unpack_token_account(destination)?.amount
+        .checked_sub(balance_before)
+        .ok_or(HodorError::MathOverflow)?)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use spl_token_2022::extension::transfer_fee::TransferFee;
+    use super::*;
+
+    #[test]
+    fn test_calculate_pre_transfer_fee_amount() {
+        assert_eq!(Some(1_000), calculate_pre_transfer_fee_amount(1_000, 0, 100));
+        assert_eq!(Some(0), calculate_pre_transfer_fee_amount(0, 100, 100));
+        // 1%: ceil(1_000 * 10_000 / 9_900) = 1_011
+        assert_eq!(Some(1_011), calculate_pre_transfer_fee_amount(1_000, 100, 100));
+        // capped by maximum fee
+        assert_eq!(Some(1_005), calculate_pre_transfer_fee_amount(1_000, 100, 5));
+        assert_eq!(Some(1_005), calculate_pre_transfer_fee_amount(1_000, 10_000, 5));
+        assert_eq!(None, calculate_pre_transfer_fee_amount(u64::MAX, 100, 1));
+
+        // destination always receives at least requested amount
+        for fee_basis_points in [1, 30, 100, 2_500, 9_999] {
+            for amount in [1, 7, 999, 1_000_000, 123_456_789] {
+                let transfer_fee = TransferFee {
+                    epoch: 0.into(),
+                    maximum_fee: u64::MAX.into(),
+                    transfer_fee_basis_points: fee_basis_points.into(),
+                };
+
+                let pre_fee_amount = calculate_pre_transfer_fee_amount(amount, fee_basis_points, u64::MAX / 2).unwrap();
+                let fee = transfer_fee.calculate_fee(pre_fee_amount).unwrap();
+                assert!(pre_fee_amount - fee >= amount);
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,500 @@
+use solana_program::program_error::ProgramError;
+use crate::fixed_point;
+use crate::math::{mul_div, mul_div_ceil, sqrt};
+use crate::swap::instruction::PoolToken;
+use crate::swap::oracle::PRICE_ONE;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Curve {
+    // x * y = k
+    ConstantProduct,
+    // Curve StableSwap invariant for pegged assets, higher amplification keeps price closer to 1:1
+    StableSwap {
+        amp: u64,
+    },
+    // Balancer style weighted invariant x^wa * y^wb = k, weight of token B is WEIGHT_BASE - weight_a
+    Weighted {
+        weight_a: u64,
+    },
+}
+
+impl Curve {
+    pub const SIZE: usize = 1 + 8;
+    pub const MIN_AMP: u64 = 1;
+    pub const MAX_AMP: u64 = 1_000_000;
+    pub const WEIGHT_BASE: u64 = 1_000_000;
+    pub const MIN_WEIGHT: u64 = 20_000;
+
+    // Newton's method iteration limit, converges in few iterations for valid pools
+    const MAX_ITERATIONS: usize = 255;
+
+    pub fn curve_type(&self) -> u8 {
+        match self {
+            Curve::ConstantProduct => 0,
+            Curve::StableSwap { .. } => 1,
+            Curve::Weighted { .. } => 2,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Curve::ConstantProduct => true,
+            Curve::StableSwap { amp } => (Curve::MIN_AMP..=Curve::MAX_AMP).contains(amp),
+            Curve::Weighted { weight_a } =>
+                (Curve::MIN_WEIGHT..=Curve::WEIGHT_BASE - Curve::MIN_WEIGHT).contains(weight_a),
+        }
+    }
+
+    // Curve math treats first provided pool token as token A, reversed curve is used when it's token B
+    pub fn reversed(&self) -> Curve {
+        match self {
+            Curve::Weighted { weight_a } => Curve::Weighted { weight_a: Curve::WEIGHT_BASE - weight_a },
+            _ => *self,
+        }
+    }
+
+    pub fn oriented(&self, first_token: PoolToken) -> Curve {
+        match first_token {
+            PoolToken::A => *self,
+            PoolToken::B => self.reversed(),
+        }
+    }
+
+    // Returns part of single token deposit which is implicitly swapped to other token
+    pub fn deposit_single_swapped_amount(&self, deposit_amount: u128) -> Option<u128> {
+        match self {
+            Curve::Weighted { weight_a } => mul_div_ceil(
+                deposit_amount,
+                (Curve::WEIGHT_BASE - weight_a) as u128,
+                Curve::WEIGHT_BASE as u128,
+            ),
+            _ => Some(deposit_amount.div_ceil(2)),
+        }
+    }
+
+    pub fn pack(&self, dst: &mut [u8; Curve::SIZE]) {
+        let parameter = match self {
+            Curve::ConstantProduct => 0,
+            Curve::StableSwap { amp } => *amp,
+            Curve::Weighted { weight_a } => *weight_a,
+        };
+
+        dst[0] = self.curve_type();
+        dst[1..].copy_from_slice(&parameter.to_le_bytes());
+    }
+
+    pub fn unpack(src: &[u8; Curve::SIZE]) -> Result<Self, ProgramError> {
+        let parameter = src[1..].try_into()
+            .map(u64::from_le_bytes)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        match src[0] {
+            0 => Ok(Curve::ConstantProduct),
+            1 => Ok(Curve::StableSwap { amp: parameter }),
+            2 => Ok(Curve::Weighted { weight_a: parameter }),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    // Returns amount of out token received for in token amount, which is already deducted of all fees
+    pub fn swap_out_amount(&self, pool_in_amount: u128, pool_out_amount: u128, in_amount: u128) -> Option<u128> {
+        match self {
+            Curve::ConstantProduct => {
+                // x * y = k
+                // (x + a)(y - b) = k
+                // b = y * a / (x + a)
+                mul_div(pool_out_amount, in_amount, pool_in_amount.checked_add(in_amount)?)
+            }
+            Curve::StableSwap { amp } => {
+                let d = compute_stable_d(*amp, pool_in_amount, pool_out_amount)?;
+                let new_pool_out_amount = compute_stable_y(*amp, pool_in_amount.checked_add(in_amount)?, d)?;
+
+                // Rounding by one in favor of pool
+                Some(pool_out_amount
+                    .saturating_sub(new_pool_out_amount)
+                    .saturating_sub(1))
+            }
+            Curve::Weighted { weight_a } => {
+                // b = y * (1 - (x / (x + a))^(wa / wb)), power is rounded up in favor of pool
+                let exponent = fixed_point::from_ratio_down(
+                    *weight_a as u128, (Curve::WEIGHT_BASE - weight_a) as u128)?;
+                let base = fixed_point::from_ratio_up(pool_in_amount, pool_in_amount.checked_add(in_amount)?)?;
+                let power = fixed_point::pow_up(base, exponent)?.min(fixed_point::ONE);
+
+                fixed_point::mul_down(pool_out_amount, fixed_point::ONE - power)
+            }
+        }
+    }
+
+    // Returns in token amount, not including fees, required to receive at least provided out token amount
+    pub fn swap_in_amount(&self, pool_in_amount: u128, pool_out_amount: u128, out_amount: u128) -> Option<u128> {
+        if out_amount >= pool_out_amount {
+            return None;
+        }
+
+        match self {
+            Curve::ConstantProduct => {
+                // a = x * b / (y - b), rounded up
+                mul_div_ceil(pool_in_amount, out_amount, pool_out_amount - out_amount)
+            }
+            Curve::StableSwap { amp } => {
+                let d = compute_stable_d(*amp, pool_in_amount, pool_out_amount)?;
+
+                // Out amount calculation subtracts one in favor of pool and solved balances can be rounded up
+                // by one, both are covered here. Invariant is symmetric in balances
+                let new_pool_out_amount = pool_out_amount
+                    .checked_sub(out_amount)?
+                    .checked_sub(2)?;
+                let new_pool_in_amount = compute_stable_y(*amp, new_pool_out_amount, d)?;
+
+                new_pool_in_amount
+                    .checked_add(1)?
+                    .checked_sub(pool_in_amount)
+            }
+            Curve::Weighted { weight_a } => {
+                // a = x * ((y / (y - b))^(wb / wa) - 1), power is rounded up in favor of pool.
+                // Out amount calculation rounds its power up by relative error margin, it's covered here
+                let out_amount = out_amount
+                    .checked_add(mul_div_ceil(pool_out_amount, 2 * fixed_point::MAX_POW_RELATIVE_ERROR, fixed_point::ONE)?)?
+                    .checked_add(1)?;
+                if out_amount >= pool_out_amount {
+                    return None;
+                }
+
+                let exponent = fixed_point::from_ratio_up(
+                    (Curve::WEIGHT_BASE - weight_a) as u128, *weight_a as u128)?;
+                let base = fixed_point::from_ratio_up(pool_out_amount, pool_out_amount - out_amount)?;
+                let power = fixed_point::pow_up(base, exponent)?;
+
+                fixed_point::mul_up(pool_in_amount, power.checked_sub(fixed_point::ONE)?)
+            }
+        }
+    }
+
+    // Returns amount of LP tokens minted for single token deposit, which is already deducted of all fees
+    pub fn deposit_single_lp_amount(&self, pool_in_amount: u128, pool_other_amount: u128, lp_supply: u128,
+                                    deposit_amount: u128) -> Option<u128> {
+        match self {
+            Curve::ConstantProduct => {
+                // lp_mint = lp_supply * (sqrt((x + a) / x) - 1)
+                let invariant_root = sqrt(
+                    pool_in_amount
+                        .checked_add(deposit_amount)?
+                        .checked_mul(pool_in_amount)?
+                );
+
+                invariant_root
+                    .checked_sub(pool_in_amount)?
+                    .checked_mul(lp_supply)?
+                    .checked_div(pool_in_amount)
+            }
+            Curve::StableSwap { amp } => {
+                // lp_mint = lp_supply * (d1 - d0) / d0
+                let d0 = compute_stable_d(*amp, pool_in_amount, pool_other_amount)?;
+                let d1 = compute_stable_d(*amp, pool_in_amount.checked_add(deposit_amount)?, pool_other_amount)?;
+
+                mul_div(d1.checked_sub(d0)?, lp_supply, d0)
+            }
+            Curve::Weighted { weight_a } => {
+                // lp_mint = lp_supply * ((1 + a / x)^wa - 1), power is rounded down in favor of pool
+                let exponent = fixed_point::from_ratio_down(*weight_a as u128, Curve::WEIGHT_BASE as u128)?;
+                let base = fixed_point::ONE.checked_add(fixed_point::from_ratio_down(deposit_amount, pool_in_amount)?)?;
+                let power = fixed_point::pow_down(base, exponent)?;
+
+                fixed_point::mul_down(lp_supply, power.saturating_sub(fixed_point::ONE))
+            }
+        }
+    }
+
+    // Returns marginal price of first token denominated in second token, as UQ64.64 fixed point number
+    pub fn spot_price(&self, pool_first_amount: u128, pool_second_amount: u128) -> Option<u128> {
+        if pool_first_amount == 0 {
+            return None;
+        }
+
+        match self {
+            Curve::ConstantProduct => {
+                // p = y / x
+                mul_div(pool_second_amount, PRICE_ONE, pool_first_amount)
+            }
+            Curve::StableSwap { amp } => {
+                // p = (Ann + D^3 / (4 * x^2 * y)) / (Ann + D^3 / (4 * x * y^2)) = (k + y) / (k + x),
+                // where k = 4 * Ann * (x * y / D)^2 / D
+                let d = compute_stable_d(*amp, pool_first_amount, pool_second_amount)?;
+                let ann = (*amp as u128).checked_mul(2)?;
+                let reduced_product = mul_div(pool_first_amount, pool_second_amount, d)?;
+                let k = mul_div(ann.checked_mul(4)?.checked_mul(reduced_product)?, reduced_product, d)?;
+
+                mul_div(k.checked_add(pool_second_amount)?, PRICE_ONE, k.checked_add(pool_first_amount)?)
+            }
+            Curve::Weighted { weight_a } => {
+                // p = (y / wb) / (x / wa)
+                mul_div(
+                    pool_second_amount.checked_mul(*weight_a as u128)?,
+                    PRICE_ONE,
+                    pool_first_amount.checked_mul((Curve::WEIGHT_BASE - weight_a) as u128)?,
+                )
+            }
+        }
+    }
+}
+
+// Solves StableSwap invariant for D with two tokens:
+// A * n^n * (x + y) + D = A * D * n^n + D^(n + 1) / (n^n * x * y)
+pub fn compute_stable_d(amp: u64, x: u128, y: u128) -> Option<u128> {
+    let sum = x.checked_add(y)?;
+    if sum == 0 {
+        return Some(0);
+    }
+
+    let ann = (amp as u128).checked_mul(2)?;
+    let mut d = sum;
+
+    for _ in 0..Curve::MAX_ITERATIONS {
+        let d_product = mul_div(d, d, x.checked_mul(2)?)?;
+        let d_product = mul_div(d_product, d, y.checked_mul(2)?)?;
+
+        let numerator = ann
+            .checked_mul(sum)?
+            .checked_add(d_product.checked_mul(2)?)?;
+        let denominator = ann
+            .checked_sub(1)?
+            .checked_mul(d)?
+            .checked_add(d_product.checked_mul(3)?)?;
+
+        let previous_d = d;
+        d = mul_div(numerator, d, denominator)?;
+
+        if d.abs_diff(previous_d) <= 1 {
+            return Some(d);
+        }
+    }
+
+    None
+}
+
+// Solves StableSwap invariant for balance of other token, given balance x and invariant D
+pub fn compute_stable_y(amp: u64, x: u128, d: u128) -> Option<u128> {
+    let ann = (amp as u128).checked_mul(2)?;
+
+    let c = mul_div(d, d, x.checked_mul(2)?)?;
+    let c = mul_div(c, d, ann.checked_mul(2)?)?;
+    let b = x.checked_add(d.checked_div(ann)?)?;
+
+    let mut y = d;
+    for _ in 0..Curve::MAX_ITERATIONS {
+        let previous_y = y;
+        // y = (y^2 + c) / (2y + b - D), square is divided separately to not overflow for large balances,
+        // both parts are rounded up so pool balance is never underestimated
+        let denominator = y
+            .checked_mul(2)?
+            .checked_add(b)?
+            .checked_sub(d)?;
+        y = mul_div_ceil(y, y, denominator)?
+            .checked_add(mul_div_ceil(c, 1, denominator)?)?;
+
+        if y.abs_diff(previous_y) <= 1 {
+            return Some(y);
+        }
+    }
+
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_pack_unpack() {
+        for curve in [Curve::ConstantProduct, Curve::StableSwap { amp: 100 }, Curve::Weighted { weight_a: 800_000 }] {
+            let mut dst = [0u8; Curve::SIZE];
+            curve.pack(&mut dst);
+            assert_eq!(curve, Curve::unpack(&dst).unwrap());
+        }
+
+        assert!(Curve::unpack(&[3, 0, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_curve_is_valid() {
+        assert!(Curve::ConstantProduct.is_valid());
+        assert!(Curve::StableSwap { amp: 1 }.is_valid());
+        assert!(Curve::StableSwap { amp: 1_000_000 }.is_valid());
+        assert!(!Curve::StableSwap { amp: 0 }.is_valid());
+        assert!(!Curve::StableSwap { amp: 1_000_001 }.is_valid());
+        assert!(Curve::Weighted { weight_a: 500_000 }.is_valid());
+        assert!(Curve::Weighted { weight_a: 20_000 }.is_valid());
+        assert!(Curve::Weighted { weight_a: 980_000 }.is_valid());
+        assert!(!Curve::Weighted { weight_a: 19_999 }.is_valid());
+        assert!(!Curve::Weighted { weight_a: 980_001 }.is_valid());
+        assert!(!Curve::Weighted { weight_a: 0 }.is_valid());
+    }
+
+    #[test]
+    fn test_compute_stable_d() {
+        assert_eq!(Some(0), compute_stable_d(100, 0, 0));
+        assert_eq!(None, compute_stable_d(100, 0, 1_000));
+
+        // balanced pool invariant equals sum of balances
+        assert_eq!(Some(2_000_000), compute_stable_d(100, 1_000_000, 1_000_000));
+        assert_eq!(Some(2 * u64::MAX as u128), compute_stable_d(1_000, u64::MAX as u128, u64::MAX as u128));
+
+        // unbalanced pool invariant is between constant product and constant sum
+        let d = compute_stable_d(100, 1_500_000, 500_000).unwrap();
+        assert!(d < 2_000_000 && d > 2 * 866_025);
+    }
+
+    #[test]
+    fn test_compute_stable_y() {
+        let d = compute_stable_d(100, 1_000_000, 1_000_000).unwrap();
+        let y = compute_stable_y(100, 1_000_000, d).unwrap();
+        assert!(y.abs_diff(1_000_000) <= 1);
+
+        let d = compute_stable_d(85, 3_000_000_000, 1_700_000_000).unwrap();
+        let y = compute_stable_y(85, 3_000_000_000, d).unwrap();
+        assert!(y.abs_diff(1_700_000_000) <= 1);
+    }
+
+    #[test]
+    fn test_stable_swap_out_amount() {
+        let constant_product = Curve::ConstantProduct;
+        let stable = Curve::StableSwap { amp: 100 };
+
+        // balanced pool trades close to 1:1, always in favor of pool
+        let out = stable.swap_out_amount(1_000_000_000, 1_000_000_000, 1_000_000).unwrap();
+        assert!(out < 1_000_000 && out > 999_900);
+        assert!(out > constant_product.swap_out_amount(1_000_000_000, 1_000_000_000, 1_000_000).unwrap());
+
+        // swapping back never returns more than initial amount
+        let back = stable.swap_out_amount(1_000_000_000 - out, 1_000_000_000 + 1_000_000, out).unwrap();
+        assert!(back < 1_000_000);
+
+        // cannot drain pool
+        let out = stable.swap_out_amount(1_000_000, 1_000_000, 1_000_000_000_000).unwrap();
+        assert!(out < 1_000_000);
+
+        assert_eq!(Some(0), stable.swap_out_amount(1_000_000, 1_000_000, 0));
+    }
+
+    #[test]
+    fn test_swap_in_amount() {
+        let curves = [
+            Curve::ConstantProduct,
+            Curve::StableSwap { amp: 100 },
+            Curve::Weighted { weight_a: 800_000 },
+            Curve::Weighted { weight_a: 20_000 },
+        ];
+        let pools: [(u128, u128); 3] = [(1_000_000, 1_000_000), (5_000_000_000, 70_000_000), (u64::MAX as u128, u64::MAX as u128)];
+
+        for curve in curves {
+            for (pool_in, pool_out) in pools {
+                for out_amount in [1, pool_out / 1_000, pool_out / 3, pool_out / 2] {
+                    // in amounts not fitting into token amount can't be swapped and aren't exact
+                    let in_amount = curve.swap_in_amount(pool_in, pool_out, out_amount).unwrap();
+                    if in_amount <= u64::MAX as u128 {
+                        assert!(curve.swap_out_amount(pool_in, pool_out, in_amount).unwrap() >= out_amount);
+                    }
+                }
+            }
+
+            assert_eq!(None, curve.swap_in_amount(1_000_000, 1_000_000, 1_000_000));
+        }
+
+        // 1_000_000 * 100_000 / 900_000 = 111_111.1...
+        assert_eq!(Some(111_112), Curve::ConstantProduct.swap_in_amount(1_000_000, 1_000_000, 100_000));
+        assert_eq!(Some(100_000), Curve::ConstantProduct.swap_out_amount(1_000_000, 1_000_000, 111_112));
+
+        // 1_000_000 * ((1_000_000 / 900_000)^0.25 - 1) = 26_690.5...
+        assert_eq!(Some(26_691), Curve::Weighted { weight_a: 800_000 }.swap_in_amount(1_000_000, 1_000_000, 100_000));
+    }
+
+    #[test]
+    fn test_deposit_single_lp_amount() {
+        let constant_product = Curve::ConstantProduct;
+        assert_eq!(Some(1_000_000), constant_product.deposit_single_lp_amount(1_000_000, 0, 1_000_000, 3_000_000));
+
+        // balanced stable pool, small deposit mints almost proportional amount of LP tokens
+        let stable = Curve::StableSwap { amp: 100 };
+        let lp_amount = stable.deposit_single_lp_amount(1_000_000_000, 1_000_000_000, 2_000_000_000, 1_000_000).unwrap();
+        assert!(lp_amount <= 1_000_000 && lp_amount > 999_000);
+    }
+
+    #[test]
+    fn test_weighted_swap_out_amount() {
+        // equal weights match constant product up to rounding in favor of pool
+        let equal_weights = Curve::Weighted { weight_a: 500_000 };
+        for (pool_in, pool_out, in_amount) in [(1_000_000, 1_000_000, 100_000), (100_000_000_000, 50_000_000_000, 750_000_000)] {
+            let constant_product_out = Curve::ConstantProduct.swap_out_amount(pool_in, pool_out, in_amount).unwrap();
+            let weighted_out = equal_weights.swap_out_amount(pool_in, pool_out, in_amount).unwrap();
+            assert!(weighted_out <= constant_product_out);
+            assert!(constant_product_out - weighted_out <= constant_product_out / 10_000_000 + 1);
+        }
+
+        // 80/20 pool, spot price of A is 4 * y / x
+        let weighted = Curve::Weighted { weight_a: 800_000 };
+        let out = weighted.swap_out_amount(1_000_000_000, 1_000_000_000, 1_000).unwrap();
+        assert!((3_999..=4_000).contains(&out));
+
+        // 1_000_000 * (1 - (1_000_000 / 1_100_000)^4) = 316_986.5...
+        assert_eq!(Some(316_986), weighted.swap_out_amount(1_000_000, 1_000_000, 100_000));
+
+        // swapping B to A uses reversed weights, 1_000_000 * (1 - (1_000_000 / 1_100_000)^0.25) = 23_545.9...
+        assert_eq!(Some(23_545), weighted.reversed().swap_out_amount(1_000_000, 1_000_000, 100_000));
+
+        // cannot drain pool
+        let out = weighted.swap_out_amount(1_000_000, 1_000_000, u64::MAX as u128).unwrap();
+        assert!(out < 1_000_000);
+
+        assert_eq!(Some(0), weighted.swap_out_amount(1_000_000, 1_000_000, 0));
+    }
+
+    #[test]
+    fn test_weighted_deposit_single() {
+        let weighted = Curve::Weighted { weight_a: 800_000 };
+        assert_eq!(Curve::Weighted { weight_a: 200_000 }, weighted.reversed());
+        assert_eq!(Curve::StableSwap { amp: 10 }, Curve::StableSwap { amp: 10 }.reversed());
+
+        // only share of deposit exceeding token weight is implicitly swapped
+        assert_eq!(Some(20_000), weighted.deposit_single_swapped_amount(100_000));
+        assert_eq!(Some(80_000), weighted.reversed().deposit_single_swapped_amount(100_000));
+        assert_eq!(Some(50_001), Curve::ConstantProduct.deposit_single_swapped_amount(100_001));
+
+        // 1_000_000 * (1.1^0.8 - 1) = 79_230.3...
+        assert_eq!(Some(79_230), weighted.deposit_single_lp_amount(1_000_000, 0, 1_000_000, 100_000));
+
+        // equal weights match constant product
+        let equal_weights = Curve::Weighted { weight_a: 500_000 };
+        assert_eq!(Some(999_999), equal_weights.deposit_single_lp_amount(1_000_000, 0, 1_000_000, 3_000_000));
+    }
+
+    #[test]
+    fn test_spot_price() {
+        assert_eq!(Some(4 * PRICE_ONE), Curve::ConstantProduct.spot_price(1_000, 4_000));
+        assert_eq!(Some(PRICE_ONE / 4), Curve::ConstantProduct.spot_price(4_000, 1_000));
+        assert_eq!(None, Curve::ConstantProduct.spot_price(0, 1_000));
+
+        // 80/20 pool with equal balances, A is worth 4 B
+        let weighted = Curve::Weighted { weight_a: 800_000 };
+        assert_eq!(Some(4 * PRICE_ONE), weighted.spot_price(1_000_000_000, 1_000_000_000));
+        assert_eq!(Some(PRICE_ONE / 4), weighted.reversed().spot_price(1_000_000_000, 1_000_000_000));
+
+        let stable = Curve::StableSwap { amp: 100 };
+        assert_eq!(Some(PRICE_ONE), stable.spot_price(1_000_000, 1_000_000));
+
+        // imbalanced stable pool price stays much closer to peg than constant product price
+        let price = stable.spot_price(1_000_000_000, 2_000_000_000).unwrap();
+        assert!(price > PRICE_ONE && price < PRICE_ONE + PRICE_ONE / 10);
+
+        // spot price matches price of small swap
+        for (x, y) in [(1_000_000_000_000, 2_000_000_000_000), (3_000_000_000_000, 1_000_000_000_000)] {
+            for curve in [Curve::ConstantProduct, stable, Curve::StableSwap { amp: 1 }, weighted] {
+                let in_amount = 1_000_000;
+                let expected_out = mul_div(curve.spot_price(x, y).unwrap(), in_amount, PRICE_ONE).unwrap();
+                let out = curve.swap_out_amount(x, y, in_amount).unwrap();
+                assert!(expected_out.abs_diff(out) <= expected_out / 10_000 + 2, "{:?}: {} vs {}", curve, expected_out, out);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,304 @@
+// Binary events logged with sol_log_data after swap pool operations, so indexers don't have to derive
+// amounts from token balance changes. Event is single data entry: event tag followed by little endian fields,
+// it's shown in transaction logs as "Program data: <base64>"
+use solana_program::log::sol_log_data;
+use solana_program::program_error::ProgramError;
+use solana_program::program_error::ProgramError::InvalidArgument;
+use solana_program::pubkey::Pubkey;
+use crate::swap::instruction::PoolToken;
+
+// Fees charged by operation, denominated in token which was swapped by pool: input token of swap and single token
+// deposit, other pool token of single token withdrawal (not the withdrawn one), loaned token of flash loan
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeAmounts {
+    pub dao: u64,
+    pub lp: u64,
+    pub creator: u64,
+}
+
+// Amounts are ones received by pool or sent from pool, token transfer fees are excluded.
+// Pool balances are reserves after operation, accrued fees aren't included
+#[derive(Debug, PartialEq)]
+pub enum SwapEvent {
+    Swap {
+        pool: Pubkey,
+        user: Pubkey,
+        in_token: PoolToken,
+        in_amount: u64,
+        out_amount: u64,
+        fees: FeeAmounts,
+        balance_a: u64,
+        balance_b: u64,
+    },
+    Deposit {
+        pool: Pubkey,
+        user: Pubkey,
+        amount_a: u64,
+        amount_b: u64,
+        lp_amount: u64,
+        fees: FeeAmounts,
+        balance_a: u64,
+        balance_b: u64,
+    },
+    Withdraw {
+        pool: Pubkey,
+        user: Pubkey,
+        lp_amount: u64,
+        amount_a: u64,
+        amount_b: u64,
+        fees: FeeAmounts,
+        balance_a: u64,
+        balance_b: u64,
+    },
+    FlashLoan {
+        pool: Pubkey,
+        // Flash loan has no signer, program receiving the loan is logged instead
+        receiver: Pubkey,
+        token: PoolToken,
+        amount: u64,
+        fees: FeeAmounts,
+        balance_a: u64,
+        balance_b: u64,
+    },
+    PauseChanged {
+        pool: Pubkey,
+        guardian: Pubkey,
+        paused: bool,
+    },
+}
+
+impl SwapEvent {
+    pub fn emit(&self) {
+        sol_log_data(&[&self.pack()]);
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        match self {
+            SwapEvent::Swap { pool, user, in_token, in_amount, out_amount, fees, balance_a, balance_b } => {
+                buffer.push(0);
+                buffer.extend_from_slice(pool.as_ref());
+                buffer.extend_from_slice(user.as_ref());
+                buffer.push(in_token.pack());
+                buffer.extend_from_slice(&in_amount.to_le_bytes());
+                buffer.extend_from_slice(&out_amount.to_le_bytes());
+                fees.pack_into(&mut buffer);
+                buffer.extend_from_slice(&balance_a.to_le_bytes());
+                buffer.extend_from_slice(&balance_b.to_le_bytes());
+            }
+            SwapEvent::Deposit { pool, user, amount_a, amount_b, lp_amount, fees, balance_a, balance_b } => {
+                buffer.push(1);
+                buffer.extend_from_slice(pool.as_ref());
+                buffer.extend_from_slice(user.as_ref());
+                buffer.extend_from_slice(&amount_a.to_le_bytes());
+                buffer.extend_from_slice(&amount_b.to_le_bytes());
+                buffer.extend_from_slice(&lp_amount.to_le_bytes());
+                fees.pack_into(&mut buffer);
+                buffer.extend_from_slice(&balance_a.to_le_bytes());
+                buffer.extend_from_slice(&balance_b.to_le_bytes());
+            }
+            SwapEvent::Withdraw { pool, user, lp_amount, amount_a, amount_b, fees, balance_a, balance_b } => {
+                buffer.push(2);
+                buffer.extend_from_slice(pool.as_ref());
+                buffer.extend_from_slice(user.as_ref());
+                buffer.extend_from_slice(&lp_amount.to_le_bytes());
+                buffer.extend_from_slice(&amount_a.to_le_bytes());
+                buffer.extend_from_slice(&amount_b.to_le_bytes());
+                fees.pack_into(&mut buffer);
+                buffer.extend_from_slice(&balance_a.to_le_bytes());
+                buffer.extend_from_slice(&balance_b.to_le_bytes());
+            }
+            SwapEvent::FlashLoan { pool, receiver, token, amount, fees, balance_a, balance_b } => {
+                buffer.push(3);
+                buffer.extend_from_slice(pool.as_ref());
+                buffer.extend_from_slice(receiver.as_ref());
+                buffer.push(token.pack());
+                buffer.extend_from_slice(&amount.to_le_bytes());
+                fees.pack_into(&mut buffer);
+                buffer.extend_from_slice(&balance_a.to_le_bytes());
+                buffer.extend_from_slice(&balance_b.to_le_bytes());
+            }
+            SwapEvent::PauseChanged { pool, guardian, paused } => {
+                buffer.push(4);
+                buffer.extend_from_slice(pool.as_ref());
+                buffer.extend_from_slice(guardian.as_ref());
+                buffer.push(*paused as u8);
+            }
+        };
+
+        buffer
+    }
+
+    // Events can only grow by appending fields, trailing data is ignored so older decoders keep working
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (tag, rest) = input.split_first().ok_or(InvalidArgument)?;
+        let mut reader = EventReader { data: rest };
+
+        Ok(match tag {
+            0 => SwapEvent::Swap {
+                pool: reader.pubkey()?,
+                user: reader.pubkey()?,
+                in_token: PoolToken::unpack(reader.u8()?).map_err(|_| InvalidArgument)?,
+                in_amount: reader.u64()?,
+                out_amount: reader.u64()?,
+                fees: reader.fees()?,
+                balance_a: reader.u64()?,
+                balance_b: reader.u64()?,
+            },
+            1 => SwapEvent::Deposit {
+                pool: reader.pubkey()?,
+                user: reader.pubkey()?,
+                amount_a: reader.u64()?,
+                amount_b: reader.u64()?,
+                lp_amount: reader.u64()?,
+                fees: reader.fees()?,
+                balance_a: reader.u64()?,
+                balance_b: reader.u64()?,
+            },
+            2 => SwapEvent::Withdraw {
+                pool: reader.pubkey()?,
+                user: reader.pubkey()?,
+                lp_amount: reader.u64()?,
+                amount_a: reader.u64()?,
+                amount_b: reader.u64()?,
+                fees: reader.fees()?,
+                balance_a: reader.u64()?,
+                balance_b: reader.u64()?,
+            },
+            3 => SwapEvent::FlashLoan {
+                pool: reader.pubkey()?,
+                receiver: reader.pubkey()?,
+                token: PoolToken::unpack(reader.u8()?).map_err(|_| InvalidArgument)?,
+                amount: reader.u64()?,
+                fees: reader.fees()?,
+                balance_a: reader.u64()?,
+                balance_b: reader.u64()?,
+            },
+            4 => SwapEvent::PauseChanged {
+                pool: reader.pubkey()?,
+                guardian: reader.pubkey()?,
+                paused: match reader.u8()? {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(InvalidArgument),
+                },
+            },
+            _ => return Err(InvalidArgument),
+        })
+    }
+}
+
+impl FeeAmounts {
+    fn pack_into(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.dao.to_le_bytes());
+        buffer.extend_from_slice(&self.lp.to_le_bytes());
+        buffer.extend_from_slice(&self.creator.to_le_bytes());
+    }
+}
+
+struct EventReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> EventReader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ProgramError> {
+        let value = self.data.get(..N)
+            .and_then(|slice| slice.try_into().ok())
+            .ok_or(InvalidArgument)?;
+        self.data = &self.data[N..];
+
+        Ok(value)
+    }
+
+    fn u8(&mut self) -> Result<u8, ProgramError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, ProgramError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::new_from_array(self.take()?))
+    }
+
+    fn fees(&mut self) -> Result<FeeAmounts, ProgramError> {
+        Ok(FeeAmounts {
+            dao: self.u64()?,
+            lp: self.u64()?,
+            creator: self.u64()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_swap_event() {
+        let fees = FeeAmounts { dao: 1, lp: 2, creator: 3 };
+
+        let events = [
+            SwapEvent::Swap {
+                pool: Pubkey::new_unique(),
+                user: Pubkey::new_unique(),
+                in_token: PoolToken::B,
+                in_amount: 1_000,
+                out_amount: 900,
+                fees,
+                balance_a: 5_000,
+                balance_b: 6_000,
+            },
+            SwapEvent::Deposit {
+                pool: Pubkey::new_unique(),
+                user: Pubkey::new_unique(),
+                amount_a: 100,
+                amount_b: 200,
+                lp_amount: 150,
+                fees: FeeAmounts::default(),
+                balance_a: u64::MAX,
+                balance_b: 0,
+            },
+            SwapEvent::Withdraw {
+                pool: Pubkey::new_unique(),
+                user: Pubkey::new_unique(),
+                lp_amount: 150,
+                amount_a: 0,
+                amount_b: 400,
+                fees,
+                balance_a: 1,
+                balance_b: 2,
+            },
+            SwapEvent::FlashLoan {
+                pool: Pubkey::new_unique(),
+                receiver: Pubkey::new_unique(),
+                token: PoolToken::A,
+                amount: 10_000,
+                fees,
+                balance_a: 3,
+                balance_b: 4,
+            },
+            SwapEvent::PauseChanged {
+                pool: Pubkey::new_unique(),
+                guardian: Pubkey::new_unique(),
+                paused: true,
+            },
+        ];
+
+        for event in events {
+            let data = event.pack();
+            assert_eq!(event, SwapEvent::unpack(&data).unwrap());
+            assert!(SwapEvent::unpack(&data[..data.len() - 1]).is_err());
+
+            // appended fields are ignored
+            let mut extended_data = data.clone();
+            extended_data.push(0);
+            assert_eq!(event, SwapEvent::unpack(&extended_data).unwrap());
+        }
+
+        assert!(SwapEvent::unpack(&[]).is_err());
+        assert!(SwapEvent::unpack(&[5]).is_err());
+    }
+}
\ No newline at end of file
@@ -1,5 +1,31 @@
 use solana_program::program_error::ProgramError;
 use solana_program::program_error::ProgramError::InvalidInstructionData;
+use solana_program::pubkey::Pubkey;
+use crate::math::{mul_div, mul_div_ceil, sqrt};
+use crate::swap::curve::Curve;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PoolToken {
+    A,
+    B,
+}
+
+impl PoolToken {
+    pub(crate) fn pack(&self) -> u8 {
+        match self {
+            PoolToken::A => 0,
+            PoolToken::B => 1,
+        }
+    }
+
+    pub(crate) fn unpack(value: u8) -> Result<Self, ProgramError> {
+        match value {
+            0 => Ok(PoolToken::A),
+            1 => Ok(PoolToken::B),
+            _ => Err(InvalidInstructionData),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub enum SwapInstruction {
@@ -12,12 +38,23 @@ pub enum SwapInstruction {
     // 4. [] Token B mint
     // 5. [writeable] Token B pool account
     // 6. [writeable] LP mint
-    // 7. [] SPL token program
-    // 8. [] System program
+    // 7. [writeable] Locked LP token account - PDA
+    // 8. [] SPL token program, owner of LP mint
+    // 9. [] System program
+    // 10. [] Token A program, SPL token or Token-2022
+    // 11. [] Token B program, SPL token or Token-2022
+    // 12. [] Hodor config account - PDA
+    //
+    // LP and creator fee rates have to be within bounds set in config.
+    // Token-2022 mints with non-transferable or permanent delegate extension are rejected.
+    // Canonical pool has to use seed from SwapPool::canonical_seed and token A mint lower than token B mint,
+    // its address is derived from seed and bump, so only one canonical pool exists per mints, LP fee and curve type
     CreatePool {
         seed: [u8; 32],
         lp_fee_rate: u32,
         creator_fee_rate: u32,
+        curve: Curve,
+        canonical: bool,
     },
 
     // 1-1
@@ -28,8 +65,13 @@ pub enum SwapInstruction {
     // 3. [writeable] Destination input token account
     // 4. [writeable] Source output token account
     // 5. [writeable] Destination output token account
-    // 6. [] SPL token program
-    // todo: add hodor config account - read dao fee rate from it
+    // 6. [] Input token mint
+    // 7. [] Input token program
+    // 8. [] Output token mint
+    // 9. [] Output token program
+    // 10. [] Hodor config account - PDA
+    //
+    // Swap is priced on amount received by pool, min out amount is checked on amount received by destination
     Swap {
         in_amount: u64,
         min_out_amount: u64,
@@ -46,6 +88,13 @@ pub enum SwapInstruction {
     // 6. [writeable] LP mint
     // 7. [writeable] Destination LP token account
     // 8. [] SPL token program
+    // 9. [writeable] Locked LP token account - PDA
+    // 10. [] Token A mint
+    // 11. [] Token A program
+    // 12. [] Token B mint
+    // 13. [] Token B program
+    //
+    // Min amounts are checked on sent amounts, LP tokens are minted for amounts received by pool
     Deposit {
         // todo: document properties
         min_a: u64,
@@ -65,14 +114,268 @@ pub enum SwapInstruction {
     // 6. [writeable] LP mint
     // 7. [writeable] Source LP token account
     // 8. [] SPL token program
+    // 9. [] Token A mint
+    // 10. [] Token A program
+    // 11. [] Token B mint
+    // 12. [] Token B program
     Withdraw {
         lp_amount: u64,
         min_a: u64,
         min_b: u64,
     },
 
-    // 1-4 ChangeCreatorWithdrawAuthority
-    // 1-5 WithdrawCreatorFee
+    // 1-4
+    // Change creator fee withdraw authority
+    // 0. [signer] Current creator fee withdraw authority
+    // 1. [writeable] Swap pool state account - PDA
+    ChangeCreatorWithdrawAuthority {
+        new_authority: Pubkey,
+    },
+
+    // 1-5
+    // Withdraw accrued creator fees
+    // 0. [signer] Creator fee withdraw authority
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [writeable] Pool token A account
+    // 3. [writeable] Destination token A account
+    // 4. [writeable] Pool token B account
+    // 5. [writeable] Destination token B account
+    // 6. [] Token A mint
+    // 7. [] Token A program
+    // 8. [] Token B mint
+    // 9. [] Token B program
+    WithdrawCreatorFee {
+        amount_a: u64,
+        amount_b: u64,
+    },
+
+    // 1-6
+    // Collect accrued DAO fees into DAO vault token accounts, permissionless
+    // 0. [writeable] Swap pool state account - PDA
+    // 1. [writeable] Pool token A account
+    // 2. [writeable] DAO vault token A account
+    // 3. [writeable] Pool token B account
+    // 4. [writeable] DAO vault token B account
+    // 5. [] Token A mint
+    // 6. [] Token A program
+    // 7. [] Token B mint
+    // 8. [] Token B program
+    // 9. [] Hodor config account - PDA
+    CollectDaoFees,
+
+    // 1-7
+    // Deposit single token into pool, part of deposit is implicitly swapped and charged with swap fees
+    // 0. [signer] Fee payer, token account owner
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [writeable] Source token account
+    // 3. [writeable] Destination pool token account of deposited token
+    // 4. [writeable] LP mint
+    // 5. [writeable] Destination LP token account
+    // 6. [] SPL token program
+    // 7. [] Hodor config account - PDA
+    // 8. [] Deposited token mint
+    // 9. [] Deposited token program
+    DepositSingle {
+        token: PoolToken,
+        amount_in: u64,
+        min_lp_out: u64,
+    },
+
+    // 1-8
+    // Withdraw single token from pool, other token share is implicitly swapped and charged with swap fees
+    // 0. [signer] Fee payer, LP token account owner
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [writeable] Source pool token account of withdrawn token
+    // 3. [writeable] Destination token account
+    // 4. [writeable] LP mint
+    // 5. [writeable] Source LP token account
+    // 6. [] SPL token program
+    // 7. [] Hodor config account - PDA
+    // 8. [] Withdrawn token mint
+    // 9. [] Withdrawn token program
+    WithdrawSingle {
+        lp_amount: u64,
+        token: PoolToken,
+        min_out: u64,
+    },
+
+    // 1-9
+    // Swap tokens receiving exact output amount
+    // 0. [signer] Fee payer, token accounts owner
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [writeable] Source input token account
+    // 3. [writeable] Destination input token account
+    // 4. [writeable] Source output token account
+    // 5. [writeable] Destination output token account
+    // 6. [] Input token mint
+    // 7. [] Input token program
+    // 8. [] Output token mint
+    // 9. [] Output token program
+    // 10. [] Hodor config account - PDA
+    //
+    // Pool receives exact in amount, input transfer fee is added to sent amount and counts against max in amount.
+    // Output transfer fee is deducted from out amount
+    SwapExactOut {
+        out_amount: u64,
+        max_in_amount: u64,
+    },
+
+    // 1-10
+    // Swap tokens through route of swap pools, intermediate amounts are transferred directly between pools
+    // 0. [signer] Fee payer, token accounts owner
+    // 1. [writeable] Source input token account
+    // 2. [writeable] Destination output token account
+    // 3. [] Input token mint
+    // 4. [] Input token program
+    // 5. [] Hodor config account - PDA
+    // For every hop in route order:
+    // 6 + 5 * i. [writeable] Swap pool state account - PDA
+    // 7 + 5 * i. [writeable] Pool input token account
+    // 8 + 5 * i. [writeable] Pool output token account
+    // 9 + 5 * i. [] Output token mint
+    // 10 + 5 * i. [] Output token program
+    SwapRoute {
+        in_amount: u64,
+        min_final_out: u64,
+    },
+
+    // 1-11
+    // Borrow pool reserves, which have to be repaid with fee to pool token account within receiver program call.
+    // Receiver program is invoked with remaining accounts and data: amount (u64 LE), fee (u64 LE)
+    // 0. [writeable] Swap pool state account - PDA
+    // 1. [writeable] Pool token account
+    // 2. [writeable] Destination token account
+    // 3. [] Token mint
+    // 4. [] Token program
+    // 5. [] Hodor config account - PDA
+    // 6. [] Receiver program
+    // 7+. Accounts passed to receiver program
+    FlashLoan {
+        token: PoolToken,
+        amount: u64,
+    },
+
+    // 1-12
+    // Add untracked surplus of pool token accounts, like direct donations, to pool reserves, permissionless
+    // 0. [writeable] Swap pool state account - PDA
+    // 1. [] Pool token A account
+    // 2. [] Pool token B account
+    Sync,
+
+    // 1-13
+    // Transfer untracked surplus of pool token accounts to provided token accounts, permissionless.
+    // Pools of legacy layout are rejected, their untracked tokens are DAO fees recorded by MigratePool
+    // 0. [] Swap pool state account - PDA
+    // 1. [writeable] Pool token A account
+    // 2. [writeable] Destination token A account
+    // 3. [writeable] Pool token B account
+    // 4. [writeable] Destination token B account
+    // 5. [] Token A mint
+    // 6. [] Token A program
+    // 7. [] Token B mint
+    // 8. [] Token B program
+    Skim,
+
+    // 1-14
+    // Upgrade pool state account of older layout version to current one, permissionless.
+    // State account is reallocated, missing locked LP token account of legacy pools is created.
+    // Tokens held by pool token accounts above tracked reserves are DAO fees accrued by legacy pool,
+    // they are added to DAO fee balances
+    // 0. [signer, writeable] Fee payer, pays rent of grown state account and locked LP token account
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [] Pool token A account
+    // 3. [] Pool token B account
+    // 4. [] LP mint
+    // 5. [writeable] Locked LP token account - PDA
+    // 6. [] SPL token program
+    // 7. [] System program
+    MigratePool,
+
+    // 1-15
+    // Propose new LP and creator fee rates, replaces pending proposal and restarts its delay.
+    // Fees are changed by creator fee withdraw authority, or by config admin for pools without creator fee,
+    // whose creator fee stays disabled. LP fee of canonical pool is part of its address and can't be changed
+    // 0. [signer, writeable] Creator fee withdraw authority or config admin, pays rent of proposal account
+    // 1. [] Swap pool state account - PDA
+    // 2. [writeable] Fee change proposal account - PDA
+    // 3. [] Hodor config account - PDA
+    // 4. [] System program
+    ProposeFeeChange {
+        lp_fee_rate: u32,
+        creator_fee_rate: u32,
+    },
+
+    // 1-16
+    // Apply proposed fee rates after fee change delay has passed, proposal account is closed
+    // 0. [signer, writeable] Creator fee withdraw authority or config admin, receives rent of proposal account
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [writeable] Fee change proposal account - PDA
+    // 3. [] Hodor config account - PDA
+    ApplyFeeChange,
+
+    // 1-17
+    // Pause swap pool in emergency. Swaps, deposits, single token withdrawals, flash loans and sync are refused
+    // while pool is paused, withdrawals keep working so LPs can always exit
+    // 0. [signer] Guardian set in config
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [] Hodor config account - PDA
+    Pause,
+
+    // 1-18
+    // Unpause swap pool
+    // 0. [signer] Guardian set in config
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [] Hodor config account - PDA
+    Unpause,
+
+    // 1-19
+    // Quote swap of provided amount received by pool, returns SwapQuote through return data.
+    // Read only, can be called through CPI or simulated, fails in the same cases as swap would
+    // 0. [] Swap pool state account - PDA
+    // 1. [] Hodor config account - PDA
+    Quote {
+        in_token: PoolToken,
+        in_amount: u64,
+    },
+
+    // 1-20
+    // Quote deposit of up to provided amounts, returns DepositQuote through return data
+    // 0. [] Swap pool state account - PDA
+    // 1. [] LP mint
+    QuoteDeposit {
+        max_a: u64,
+        max_b: u64,
+    },
+
+    // 1-21
+    // Quote withdrawal of provided LP amount, returns WithdrawQuote through return data
+    // 0. [] Swap pool state account - PDA
+    // 1. [] LP mint
+    QuoteWithdraw {
+        lp_amount: u64,
+    },
+
+    // 1-22
+    // Close drained swap pool and reclaim rent of its accounts. DAO and creator fees have to be collected and
+    // all LP tokens burned, except locked minimum liquidity which nobody can burn, so LP supply equal to locked
+    // LP amount is accepted. Locked LP tokens are burned by the pool, tokens backing them and untracked surplus
+    // are sent to DAO vault token accounts. LP mint can't be closed by SPL token program, its rent stays locked
+    // and its address stays taken, so closed canonical pool can't be created again by anyone, its creator included
+    // 0. [signer, writeable] Pool creator, receives rent. Config admin for pools created before creator was recorded
+    // 1. [writeable] Swap pool state account - PDA
+    // 2. [writeable] Pool token A account
+    // 3. [writeable] DAO vault token A account
+    // 4. [writeable] Pool token B account
+    // 5. [writeable] DAO vault token B account
+    // 6. [writeable] LP mint
+    // 7. [writeable] Locked LP token account - PDA
+    // 8. [] SPL token program
+    // 9. [] Token A mint
+    // 10. [] Token A program
+    // 11. [] Token B mint
+    // 12. [] Token B program
+    // 13. [] Hodor config account - PDA
+    ClosePool,
 }
 
 impl SwapInstruction {
@@ -83,11 +386,16 @@ impl SwapInstruction {
         buffer.push(SwapInstruction::MODULE_TAG);
 
         match self {
-            SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate } => {
+            SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate, curve, canonical } => {
                 buffer.push(0);
                 buffer.extend_from_slice(seed);
                 buffer.extend_from_slice(&lp_fee_rate.to_le_bytes());
                 buffer.extend_from_slice(&creator_fee_rate.to_le_bytes());
+
+                let mut curve_buffer = [0u8; Curve::SIZE];
+                curve.pack(&mut curve_buffer);
+                buffer.extend_from_slice(&curve_buffer);
+                buffer.push(*canonical as u8);
             }
             SwapInstruction::Swap { in_amount, min_out_amount } => {
                 buffer.push(1);
@@ -107,6 +415,85 @@ impl SwapInstruction {
                 buffer.extend_from_slice(&min_a.to_le_bytes());
                 buffer.extend_from_slice(&min_b.to_le_bytes())
             }
+            SwapInstruction::ChangeCreatorWithdrawAuthority { new_authority } => {
+                buffer.push(4);
+                buffer.extend_from_slice(new_authority.as_ref());
+            }
+            SwapInstruction::WithdrawCreatorFee { amount_a, amount_b } => {
+                buffer.push(5);
+                buffer.extend_from_slice(&amount_a.to_le_bytes());
+                buffer.extend_from_slice(&amount_b.to_le_bytes());
+            }
+            SwapInstruction::CollectDaoFees => {
+                buffer.push(6);
+            }
+            SwapInstruction::DepositSingle { token, amount_in, min_lp_out } => {
+                buffer.push(7);
+                buffer.push(token.pack());
+                buffer.extend_from_slice(&amount_in.to_le_bytes());
+                buffer.extend_from_slice(&min_lp_out.to_le_bytes());
+            }
+            SwapInstruction::WithdrawSingle { lp_amount, token, min_out } => {
+                buffer.push(8);
+                buffer.extend_from_slice(&lp_amount.to_le_bytes());
+                buffer.push(token.pack());
+                buffer.extend_from_slice(&min_out.to_le_bytes());
+            }
+            SwapInstruction::SwapExactOut { out_amount, max_in_amount } => {
+                buffer.push(9);
+                buffer.extend_from_slice(&out_amount.to_le_bytes());
+                buffer.extend_from_slice(&max_in_amount.to_le_bytes());
+            }
+            SwapInstruction::SwapRoute { in_amount, min_final_out } => {
+                buffer.push(10);
+                buffer.extend_from_slice(&in_amount.to_le_bytes());
+                buffer.extend_from_slice(&min_final_out.to_le_bytes());
+            }
+            SwapInstruction::FlashLoan { token, amount } => {
+                buffer.push(11);
+                buffer.push(token.pack());
+                buffer.extend_from_slice(&amount.to_le_bytes());
+            }
+            SwapInstruction::Sync => {
+                buffer.push(12);
+            }
+            SwapInstruction::Skim => {
+                buffer.push(13);
+            }
+            SwapInstruction::MigratePool => {
+                buffer.push(14);
+            }
+            SwapInstruction::ProposeFeeChange { lp_fee_rate, creator_fee_rate } => {
+                buffer.push(15);
+                buffer.extend_from_slice(&lp_fee_rate.to_le_bytes());
+                buffer.extend_from_slice(&creator_fee_rate.to_le_bytes());
+            }
+            SwapInstruction::ApplyFeeChange => {
+                buffer.push(16);
+            }
+            SwapInstruction::Pause => {
+                buffer.push(17);
+            }
+            SwapInstruction::Unpause => {
+                buffer.push(18);
+            }
+            SwapInstruction::Quote { in_token, in_amount } => {
+                buffer.push(19);
+                buffer.push(in_token.pack());
+                buffer.extend_from_slice(&in_amount.to_le_bytes());
+            }
+            SwapInstruction::QuoteDeposit { max_a, max_b } => {
+                buffer.push(20);
+                buffer.extend_from_slice(&max_a.to_le_bytes());
+                buffer.extend_from_slice(&max_b.to_le_bytes());
+            }
+            SwapInstruction::QuoteWithdraw { lp_amount } => {
+                buffer.push(21);
+                buffer.extend_from_slice(&lp_amount.to_le_bytes());
+            }
+            SwapInstruction::ClosePool => {
+                buffer.push(22);
+            }
         };
 
         buffer
@@ -137,7 +524,18 @@ impl SwapInstruction {
                     .map(u32::from_le_bytes)
                     .ok_or(InvalidInstructionData)?;
 
-                Ok(SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate })
+                let curve = rest.get(40..40 + Curve::SIZE)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstructionData)
+                    .and_then(|curve| Curve::unpack(curve).map_err(|_| InvalidInstructionData))?;
+
+                let canonical = match rest.get(40 + Curve::SIZE).ok_or(InvalidInstructionData)? {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(InvalidInstructionData),
+                };
+
+                Ok(SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate, curve, canonical })
             }
             1 => {
                 let in_amount = rest.get(..8)
@@ -193,54 +591,203 @@ impl SwapInstruction {
 
                 Ok(SwapInstruction::Withdraw { lp_amount, min_a, min_b })
             }
+            4 => {
+                let new_authority = rest.get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::ChangeCreatorWithdrawAuthority { new_authority })
+            }
+            5 => {
+                let amount_a = rest.get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let amount_b = rest.get(8..16)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::WithdrawCreatorFee { amount_a, amount_b })
+            }
+            6 => Ok(SwapInstruction::CollectDaoFees),
+            7 => {
+                let token = rest.first()
+                    .ok_or(InvalidInstructionData)
+                    .and_then(|value| PoolToken::unpack(*value))?;
+
+                let amount_in = rest.get(1..9)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let min_lp_out = rest.get(9..17)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::DepositSingle { token, amount_in, min_lp_out })
+            }
+            8 => {
+                let lp_amount = rest.get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let token = rest.get(8)
+                    .ok_or(InvalidInstructionData)
+                    .and_then(|value| PoolToken::unpack(*value))?;
+
+                let min_out = rest.get(9..17)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::WithdrawSingle { lp_amount, token, min_out })
+            }
+            9 => {
+                let out_amount = rest.get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let max_in_amount = rest.get(8..16)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::SwapExactOut { out_amount, max_in_amount })
+            }
+            10 => {
+                let in_amount = rest.get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let min_final_out = rest.get(8..16)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::SwapRoute { in_amount, min_final_out })
+            }
+            11 => {
+                let token = rest.first()
+                    .ok_or(InvalidInstructionData)
+                    .and_then(|value| PoolToken::unpack(*value))?;
+
+                let amount = rest.get(1..9)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::FlashLoan { token, amount })
+            }
+            12 => Ok(SwapInstruction::Sync),
+            13 => Ok(SwapInstruction::Skim),
+            14 => Ok(SwapInstruction::MigratePool),
+            15 => {
+                let lp_fee_rate = rest.get(..4)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let creator_fee_rate = rest.get(4..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::ProposeFeeChange { lp_fee_rate, creator_fee_rate })
+            }
+            16 => Ok(SwapInstruction::ApplyFeeChange),
+            17 => Ok(SwapInstruction::Pause),
+            18 => Ok(SwapInstruction::Unpause),
+            19 => {
+                let in_token = rest.first()
+                    .ok_or(InvalidInstructionData)
+                    .and_then(|value| PoolToken::unpack(*value))?;
+
+                let in_amount = rest.get(1..9)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::Quote { in_token, in_amount })
+            }
+            20 => {
+                let max_a = rest.get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                let max_b = rest.get(8..16)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::QuoteDeposit { max_a, max_b })
+            }
+            21 => {
+                let lp_amount = rest.get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstructionData)?;
+
+                Ok(SwapInstruction::QuoteWithdraw { lp_amount })
+            }
+            22 => Ok(SwapInstruction::ClosePool),
             _ => Err(InvalidInstructionData)
         }
     }
 }
 
 
+// LP tokens minted on the first deposit to the locked LP account, they can never be withdrawn,
+// so the pool can't be emptied and LP price can't be inflated by rounding on tiny supply
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+// Returns token amounts taken from the depositor and LP amount minted to the depositor,
+// on the first deposit additional MINIMUM_LIQUIDITY is minted to the locked LP account
 pub fn calculate_deposit_amounts(pool_a_amount: u64, pool_b_amount: u64, lp_supply: u64,
                                  deposit_max_a: u64, deposit_max_b: u64) -> Option<(u64, u64, u64)> {
     if lp_supply == 0 {
-        // Deposit to empty pool
-        return Some((deposit_max_a, deposit_max_b, 10_000_000_000 as u64));
-    }
-
-    let pool_ratio = (pool_a_amount as u128)
-        .checked_mul(u64::MAX as u128)?
-        .checked_div(pool_b_amount as u128)?;
-
-    let deposit_ratio = (deposit_max_a as u128)
-        .checked_mul(u64::MAX as u128)?
-        .checked_div(deposit_max_b as u128)?;
-
-    let (deposit_a, deposit_b) = if deposit_ratio >= pool_ratio {
-        let deposit_a: u64 = (deposit_max_b as u128)
-            .checked_mul(pool_ratio)?
-            .checked_div(u64::MAX as u128)?
+        // Deposit to empty pool, initial LP supply is geometric mean of deposited amounts
+        let initial_lp_supply: u64 = sqrt((deposit_max_a as u128).checked_mul(deposit_max_b as u128)?)
             .try_into().ok()?;
+        if initial_lp_supply <= MINIMUM_LIQUIDITY {
+            return None;
+        }
 
-        (deposit_a, deposit_max_b)
-    } else {
-        let deposit_b: u64 = (deposit_max_a as u128)
-            .checked_mul(u64::MAX as u128)?
-            .checked_div(pool_ratio)?
-            .try_into().ok()?;
+        return Some((deposit_max_a, deposit_max_b, initial_lp_supply - MINIMUM_LIQUIDITY));
+    }
 
-        (deposit_max_a, deposit_b)
-    };
+    // LP amount is rounded down for both tokens, deposited amounts are rounded up,
+    // so depositor can never receive more than proportional share of the pool
+    let lp_mint_amount = mul_div(deposit_max_a as u128, lp_supply as u128, pool_a_amount as u128)?
+        .min(mul_div(deposit_max_b as u128, lp_supply as u128, pool_b_amount as u128)?);
 
-    let lp_mint_amount = (deposit_a as u128)
-        .checked_mul(u64::MAX as u128)?
-        .checked_div(pool_a_amount as u128)?
-        .checked_mul(lp_supply as u128)?
-        .checked_div(u64::MAX as u128)?
-        .try_into().ok()?;
+    let deposit_a = mul_div_ceil(lp_mint_amount, pool_a_amount as u128, lp_supply as u128)?;
+    let deposit_b = mul_div_ceil(lp_mint_amount, pool_b_amount as u128, lp_supply as u128)?;
 
-    Some((deposit_a, deposit_b, lp_mint_amount))
+    Some((deposit_a.try_into().ok()?, deposit_b.try_into().ok()?, lp_mint_amount.try_into().ok()?))
 }
 
 const FEE_RATE_BASE_DIVIDER: u128 = 100_000_000;
+const MAX_SWAP_IN_ITERATIONS: usize = 32;
+
+pub fn is_total_fee_rate_valid(dao_fee_rate: u32, lp_fee_rate: u32, creator_fee_rate: u32) -> bool {
+    (dao_fee_rate as u128) + (lp_fee_rate as u128) + (creator_fee_rate as u128) < FEE_RATE_BASE_DIVIDER
+}
+
+// Fee rates charged by pool operation, in units of FEE_RATE_BASE_DIVIDER
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeRates {
+    pub dao: u32,
+    pub lp: u32,
+    pub creator: u32,
+}
 
 fn calculate_fee_amount(amount: u128, fee_rate: u32) -> Option<u128> {
     Some(if fee_rate == 0 {
@@ -252,13 +799,24 @@ fn calculate_fee_amount(amount: u128, fee_rate: u32) -> Option<u128> {
     })
 }
 
-pub fn calculate_swap_amounts(pool_balance_in_token: u64, pool_balance_out_token: u64, swap_in_amount: u64,
-                              dao_fee_rate: u32, lp_fee_rate: u32, creator_fee_rate: u32) -> Option<(u64, u64, u64, u64)> {
+// Returns (dao fee, lp fee, creator fee) charged on flash loan, every fee is rounded up
+// so that small loans can't be taken for free
+pub fn calculate_flash_loan_fees(amount: u64, fee_rates: FeeRates) -> Option<(u64, u64, u64)> {
+    let fee = |fee_rate: u32| -> Option<u64> {
+        mul_div_ceil(amount as u128, fee_rate as u128, FEE_RATE_BASE_DIVIDER)?.try_into().ok()
+    };
+
+    Some((fee(fee_rates.dao)?, fee(fee_rates.lp)?, fee(fee_rates.creator)?))
+}
+
+// Curve has to be oriented with input token as first token
+pub fn calculate_swap_amounts(curve: &Curve, pool_balance_in_token: u64, pool_balance_out_token: u64, swap_in_amount: u64,
+                              fee_rates: FeeRates) -> Option<(u64, u64, u64, u64)> {
     let swap_in_amount = swap_in_amount as u128;
 
-    let dao_fee_amount = calculate_fee_amount(swap_in_amount, dao_fee_rate)?;
-    let lp_fee_amount = calculate_fee_amount(swap_in_amount, lp_fee_rate)?;
-    let creator_fee_amount = calculate_fee_amount(swap_in_amount, creator_fee_rate)?;
+    let dao_fee_amount = calculate_fee_amount(swap_in_amount, fee_rates.dao)?;
+    let lp_fee_amount = calculate_fee_amount(swap_in_amount, fee_rates.lp)?;
+    let creator_fee_amount = calculate_fee_amount(swap_in_amount, fee_rates.creator)?;
 
     let pool_balance_in_token_after_fees = (pool_balance_in_token as u128)
         .checked_add(lp_fee_amount)?;
@@ -267,15 +825,11 @@ pub fn calculate_swap_amounts(pool_balance_in_token: u64, pool_balance_out_token
         .checked_sub(lp_fee_amount)?
         .checked_sub(creator_fee_amount)?;
 
-    // x * y = k
-    // (x + a)(y - b) = k
-    // b = y * a / (x + a)
-    let swap_out_amount = (pool_balance_out_token as u128)
-        .checked_mul(swap_in_amount_after_fees)?
-        .checked_div(
-            pool_balance_in_token_after_fees
-                .checked_add(swap_in_amount_after_fees)?
-        )?;
+    let swap_out_amount = curve.swap_out_amount(
+        pool_balance_in_token_after_fees,
+        pool_balance_out_token as u128,
+        swap_in_amount_after_fees,
+    )?;
 
     Some((
         swap_out_amount.try_into().ok()?,
@@ -285,6 +839,85 @@ pub fn calculate_swap_amounts(pool_balance_in_token: u64, pool_balance_out_token
     ))
 }
 
+// Returns (in amount, dao fee, lp fee, creator fee) for swap receiving at least provided out amount,
+// inverse of calculate_swap_amounts rounded in favor of pool. Curve has to be oriented with input token as first token
+pub fn calculate_swap_in_amount(curve: &Curve, pool_balance_in_token: u64, pool_balance_out_token: u64,
+                                swap_out_amount: u64, fee_rates: FeeRates) -> Option<(u64, u64, u64, u64)> {
+    if !is_total_fee_rate_valid(fee_rates.dao, fee_rates.lp, fee_rates.creator) {
+        return None;
+    }
+
+    let total_fee_rate = fee_rates.dao as u128 + fee_rates.lp as u128 + fee_rates.creator as u128;
+
+    // LP fee stays in pool and is part of in token balance used by curve, it depends on in amount
+    // so it's estimated iteratively, estimate only grows and stabilizes after few iterations
+    let mut lp_fee_estimate = 0;
+    for _ in 0..MAX_SWAP_IN_ITERATIONS {
+        let swap_in_amount_after_fees = curve.swap_in_amount(
+            (pool_balance_in_token as u128).checked_add(lp_fee_estimate)?,
+            pool_balance_out_token as u128,
+            swap_out_amount as u128,
+        )?;
+
+        // Fees are rounded down, so charging them from grossed up amount always leaves enough to swap
+        let swap_in_amount = mul_div_ceil(
+            swap_in_amount_after_fees,
+            FEE_RATE_BASE_DIVIDER,
+            FEE_RATE_BASE_DIVIDER - total_fee_rate,
+        )?;
+
+        let lp_fee_amount = calculate_fee_amount(swap_in_amount, fee_rates.lp)?;
+        if lp_fee_amount <= lp_fee_estimate {
+            return Some((
+                swap_in_amount.try_into().ok()?,
+                calculate_fee_amount(swap_in_amount, fee_rates.dao)?.try_into().ok()?,
+                lp_fee_amount.try_into().ok()?,
+                calculate_fee_amount(swap_in_amount, fee_rates.creator)?.try_into().ok()?,
+            ));
+        }
+
+        lp_fee_estimate = lp_fee_amount;
+    }
+
+    None
+}
+
+// Returns (LP mint amount, dao fee, lp fee, creator fee)
+// Half of deposit is treated as implicitly swapped to other token and charged with swap fees,
+// remaining amount increases pool invariant, LP tokens are minted proportionally to the invariant growth.
+// Curve has to be oriented with deposited token as first token
+pub fn calculate_deposit_single_amounts(curve: &Curve, pool_in_amount: u64, pool_other_amount: u64, lp_supply: u64,
+                                        deposit_amount: u64, fee_rates: FeeRates) -> Option<(u64, u64, u64, u64)> {
+    if pool_in_amount == 0 || lp_supply == 0 {
+        return None;
+    }
+
+    let swapped_amount = curve.deposit_single_swapped_amount(deposit_amount as u128)?;
+
+    let dao_fee_amount = calculate_fee_amount(swapped_amount, fee_rates.dao)?;
+    let lp_fee_amount = calculate_fee_amount(swapped_amount, fee_rates.lp)?;
+    let creator_fee_amount = calculate_fee_amount(swapped_amount, fee_rates.creator)?;
+
+    let deposit_amount_after_fees = (deposit_amount as u128)
+        .checked_sub(dao_fee_amount)?
+        .checked_sub(lp_fee_amount)?
+        .checked_sub(creator_fee_amount)?;
+
+    let lp_mint_amount = curve.deposit_single_lp_amount(
+        pool_in_amount as u128,
+        pool_other_amount as u128,
+        lp_supply as u128,
+        deposit_amount_after_fees,
+    )?;
+
+    Some((
+        lp_mint_amount.try_into().ok()?,
+        dao_fee_amount.try_into().ok()?,
+        lp_fee_amount.try_into().ok()?,
+        creator_fee_amount.try_into().ok()?
+    ))
+}
+
 pub fn calculate_withdraw_amounts(pool_a_amount: u64, pool_b_amount: u64, lp_supply: u64,
                                   withdraw_lp_amount: u64) -> Option<(u64, u64)> {
     if withdraw_lp_amount == lp_supply {
@@ -308,10 +941,37 @@ pub fn calculate_withdraw_amounts(pool_a_amount: u64, pool_b_amount: u64, lp_sup
     Some((withdraw_a_amount, withdraw_b_amount))
 }
 
+// Returns (out amount, dao fee, lp fee, creator fee), fees are denominated in other pool token
+// Proportional share of other token is swapped to out token against pool state after withdrawal.
+// Curve has to be oriented with withdrawn token as first token
+pub fn calculate_withdraw_single_amounts(curve: &Curve, pool_out_amount: u64, pool_other_amount: u64, lp_supply: u64,
+                                         withdraw_lp_amount: u64, fee_rates: FeeRates) -> Option<(u64, u64, u64, u64)> {
+    if withdraw_lp_amount >= lp_supply {
+        return None;
+    }
+
+    let (withdraw_out_amount, withdraw_other_amount) = calculate_withdraw_amounts(
+        pool_out_amount, pool_other_amount, lp_supply, withdraw_lp_amount)?;
+
+    let (swap_out_amount, dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_swap_amounts(
+        &curve.reversed(),
+        pool_other_amount.checked_sub(withdraw_other_amount)?,
+        pool_out_amount.checked_sub(withdraw_out_amount)?,
+        withdraw_other_amount,
+        fee_rates,
+    )?;
+
+    Some((
+        withdraw_out_amount.checked_add(swap_out_amount)?,
+        dao_fee_amount,
+        lp_fee_amount,
+        creator_fee_amount
+    ))
+}
+
 
 #[cfg(test)]
 mod tests {
-    use solana_program::pubkey::Pubkey;
     use super::*;
 
     #[test]
@@ -320,12 +980,16 @@ mod tests {
             seed: Pubkey::new_unique().to_bytes(),
             lp_fee_rate: 5,
             creator_fee_rate: 60,
+            curve: Curve::StableSwap { amp: 100 },
+            canonical: true,
         };
         assert_eq!(create_instruction, SwapInstruction::unpack(&create_instruction.pack()).unwrap());
         assert_ne!(create_instruction, SwapInstruction::unpack(&SwapInstruction::CreatePool {
             seed: Default::default(),
             lp_fee_rate: 0,
             creator_fee_rate: 0,
+            curve: Curve::ConstantProduct,
+            canonical: false,
         }.pack()).unwrap());
 
 
@@ -363,13 +1027,139 @@ mod tests {
             min_a: 1,
             min_b: 1,
         }.pack()).unwrap());
+
+        let change_authority_instruction = SwapInstruction::ChangeCreatorWithdrawAuthority {
+            new_authority: Pubkey::new_unique(),
+        };
+        assert_eq!(change_authority_instruction, SwapInstruction::unpack(&change_authority_instruction.pack()).unwrap());
+        assert_ne!(change_authority_instruction, SwapInstruction::unpack(&SwapInstruction::ChangeCreatorWithdrawAuthority {
+            new_authority: Pubkey::new_unique(),
+        }.pack()).unwrap());
+
+        let withdraw_creator_fee_instruction = SwapInstruction::WithdrawCreatorFee {
+            amount_a: 1,
+            amount_b: 2,
+        };
+        assert_eq!(withdraw_creator_fee_instruction, SwapInstruction::unpack(&withdraw_creator_fee_instruction.pack()).unwrap());
+        assert_ne!(withdraw_creator_fee_instruction, SwapInstruction::unpack(&SwapInstruction::WithdrawCreatorFee {
+            amount_a: 2,
+            amount_b: 1,
+        }.pack()).unwrap());
+
+        let collect_dao_fees_instruction = SwapInstruction::CollectDaoFees;
+        assert_eq!(collect_dao_fees_instruction, SwapInstruction::unpack(&collect_dao_fees_instruction.pack()).unwrap());
+
+        let deposit_single_instruction = SwapInstruction::DepositSingle {
+            token: PoolToken::B,
+            amount_in: 1,
+            min_lp_out: 2,
+        };
+        assert_eq!(deposit_single_instruction, SwapInstruction::unpack(&deposit_single_instruction.pack()).unwrap());
+        assert_ne!(deposit_single_instruction, SwapInstruction::unpack(&SwapInstruction::DepositSingle {
+            token: PoolToken::A,
+            amount_in: 1,
+            min_lp_out: 2,
+        }.pack()).unwrap());
+
+        let mut invalid_token = deposit_single_instruction.pack();
+        invalid_token[2] = 2;
+        assert!(SwapInstruction::unpack(&invalid_token).is_err());
+
+        let withdraw_single_instruction = SwapInstruction::WithdrawSingle {
+            lp_amount: 1,
+            token: PoolToken::A,
+            min_out: 2,
+        };
+        assert_eq!(withdraw_single_instruction, SwapInstruction::unpack(&withdraw_single_instruction.pack()).unwrap());
+        assert_ne!(withdraw_single_instruction, SwapInstruction::unpack(&SwapInstruction::WithdrawSingle {
+            lp_amount: 1,
+            token: PoolToken::B,
+            min_out: 2,
+        }.pack()).unwrap());
+
+        let swap_exact_out_instruction = SwapInstruction::SwapExactOut { out_amount: 1, max_in_amount: 2 };
+        assert_eq!(swap_exact_out_instruction, SwapInstruction::unpack(&swap_exact_out_instruction.pack()).unwrap());
+        assert_ne!(swap_exact_out_instruction, SwapInstruction::unpack(&SwapInstruction::Swap {
+            in_amount: 1,
+            min_out_amount: 2,
+        }.pack()).unwrap());
+
+        let swap_route_instruction = SwapInstruction::SwapRoute { in_amount: 1, min_final_out: 2 };
+        assert_eq!(swap_route_instruction, SwapInstruction::unpack(&swap_route_instruction.pack()).unwrap());
+        assert_ne!(swap_route_instruction, SwapInstruction::unpack(&SwapInstruction::SwapRoute {
+            in_amount: 2,
+            min_final_out: 1,
+        }.pack()).unwrap());
+
+        let flash_loan_instruction = SwapInstruction::FlashLoan { token: PoolToken::B, amount: 1 };
+        assert_eq!(flash_loan_instruction, SwapInstruction::unpack(&flash_loan_instruction.pack()).unwrap());
+        assert_ne!(flash_loan_instruction, SwapInstruction::unpack(&SwapInstruction::FlashLoan {
+            token: PoolToken::A,
+            amount: 1,
+        }.pack()).unwrap());
+
+        assert_eq!(SwapInstruction::Sync, SwapInstruction::unpack(&SwapInstruction::Sync.pack()).unwrap());
+        assert_eq!(SwapInstruction::Skim, SwapInstruction::unpack(&SwapInstruction::Skim.pack()).unwrap());
+        assert_eq!(SwapInstruction::MigratePool, SwapInstruction::unpack(&SwapInstruction::MigratePool.pack()).unwrap());
+
+        let propose_fee_change_instruction = SwapInstruction::ProposeFeeChange { lp_fee_rate: 1, creator_fee_rate: 2 };
+        assert_eq!(propose_fee_change_instruction,
+                   SwapInstruction::unpack(&propose_fee_change_instruction.pack()).unwrap());
+        assert!(SwapInstruction::unpack(&propose_fee_change_instruction.pack()[..8]).is_err());
+        assert_eq!(SwapInstruction::ApplyFeeChange, SwapInstruction::unpack(&SwapInstruction::ApplyFeeChange.pack()).unwrap());
+        assert_eq!(SwapInstruction::Pause, SwapInstruction::unpack(&SwapInstruction::Pause.pack()).unwrap());
+        assert_eq!(SwapInstruction::Unpause, SwapInstruction::unpack(&SwapInstruction::Unpause.pack()).unwrap());
+
+        let quote_instruction = SwapInstruction::Quote { in_token: PoolToken::B, in_amount: 1_000 };
+        assert_eq!(quote_instruction, SwapInstruction::unpack(&quote_instruction.pack()).unwrap());
+        assert!(SwapInstruction::unpack(&quote_instruction.pack()[..10]).is_err());
+
+        let quote_deposit_instruction = SwapInstruction::QuoteDeposit { max_a: 1, max_b: u64::MAX };
+        assert_eq!(quote_deposit_instruction, SwapInstruction::unpack(&quote_deposit_instruction.pack()).unwrap());
+
+        let quote_withdraw_instruction = SwapInstruction::QuoteWithdraw { lp_amount: 5 };
+        assert_eq!(quote_withdraw_instruction, SwapInstruction::unpack(&quote_withdraw_instruction.pack()).unwrap());
+
+        assert_eq!(SwapInstruction::ClosePool, SwapInstruction::unpack(&SwapInstruction::ClosePool.pack()).unwrap());
+    }
+
+    #[test]
+    fn test_calculate_flash_loan_fees() {
+        // 0.1% DAO, 0.3% LP, 0.05% creator
+        assert_eq!(
+            Some((1_000, 3_000, 500)),
+            calculate_flash_loan_fees(1_000_000, FeeRates { dao: 100_000, lp: 300_000, creator: 50_000 })
+        );
+
+        // rounded up
+        let fee_rates = FeeRates { dao: 100_000, lp: 300_000, creator: 0 };
+        assert_eq!(Some((1, 1, 0)), calculate_flash_loan_fees(1, fee_rates));
+        assert_eq!(Some((0, 0, 0)), calculate_flash_loan_fees(1_000_000, FeeRates::default()));
+        assert_eq!(Some((0, 0, 0)), calculate_flash_loan_fees(0, fee_rates));
+
+        let fee_rates = FeeRates { dao: 33_000_000, lp: 33_000_000, creator: 33_000_000 };
+        let (dao_fee, lp_fee, creator_fee) = calculate_flash_loan_fees(u64::MAX, fee_rates).unwrap();
+        assert!(dao_fee as u128 + lp_fee as u128 + creator_fee as u128 <= u64::MAX as u128);
     }
 
 
     #[test]
     fn test_calculate_deposit_amounts() {
+        // initial LP supply is geometric mean, minimum liquidity is locked
         assert_eq!(
-            Some((69, 420, 10_000_000_000)),
+            Some((1_000_000, 4_000_000, 2_000_000 - MINIMUM_LIQUIDITY)),
+            calculate_deposit_amounts(0, 0, 0, 1_000_000, 4_000_000)
+        );
+        assert_eq!(
+            Some((1_001, 1_001, 1)),
+            calculate_deposit_amounts(0, 0, 0, 1_001, 1_001)
+        );
+        assert_eq!(
+            None,
+            calculate_deposit_amounts(0, 0, 0, 1_000, 1_000)
+        );
+        assert_eq!(
+            None,
             calculate_deposit_amounts(0, 0, 0, 69, 420)
         );
 
@@ -385,13 +1175,66 @@ mod tests {
             Some((100, 100, 10_000)),
             calculate_deposit_amounts(100, 100, 10_000, 100, 110)
         );
+        assert_eq!(
+            Some((2, 6, 2)),
+            calculate_deposit_amounts(1_000, 3_000, 1_000, 2, 7)
+        );
 
+        // input u64::MAX
+        assert_eq!(
+            Some((u64::MAX, u64::MAX, u64::MAX - MINIMUM_LIQUIDITY)),
+            calculate_deposit_amounts(0, 0, 0, u64::MAX, u64::MAX)
+        );
+        assert_eq!(
+            Some((u64::MAX, u64::MAX, u64::MAX)),
+            calculate_deposit_amounts(u64::MAX, u64::MAX, u64::MAX, u64::MAX, u64::MAX)
+        );
+        assert_eq!(
+            None,
+            calculate_deposit_amounts(0, 0, 1_000, 100, 100)
+        );
+    }
 
-        // todo: add more unit tests
-
-        // todo: test input u64::MAX
+    #[test]
+    fn test_deposit_rounding_can_not_print_money() {
+        // dust deposit gets no LP instead of rounded up share
+        assert_eq!(
+            Some((0, 0, 0)),
+            calculate_deposit_amounts(1_000, 3_000, 1_000, 1, 1)
+        );
+        // tiny deposit pays rounded up amounts
+        assert_eq!(
+            Some((1, 1, 1)),
+            calculate_deposit_amounts(3, 7, 10, 1, 1)
+        );
 
-        // todo: test for rounding error- printing money
+        let pools = [
+            (1_000, 1_000, 1_000),
+            (1_001, 3_000, 1_732),
+            (7, 1_000_000_007, 1_000),
+            (1_000_000_007, 13, 2_000_000),
+            (123_456_789, 987_654_321, 349_192_813),
+            (u64::MAX / 3, u64::MAX / 7, u64::MAX / 5),
+        ];
+        let deposits = [(1, 1), (1, 1_000), (999, 3), (12_345, 67_890), (1_000_000_007, 999_999_937), (u64::MAX / 9, u64::MAX / 11)];
+
+        for (pool_a, pool_b, lp_supply) in pools {
+            for (max_a, max_b) in deposits {
+                let (deposit_a, deposit_b, lp_amount) = calculate_deposit_amounts(
+                    pool_a, pool_b, lp_supply, max_a, max_b).unwrap();
+
+                assert!(deposit_a <= max_a && deposit_b <= max_b);
+
+                // deposited amounts are at least proportional share of minted LP
+                assert!(deposit_a as u128 * lp_supply as u128 >= lp_amount as u128 * pool_a as u128);
+                assert!(deposit_b as u128 * lp_supply as u128 >= lp_amount as u128 * pool_b as u128);
+
+                // withdrawing minted LP right away never returns more than deposited
+                let (withdraw_a, withdraw_b) = calculate_withdraw_amounts(
+                    pool_a + deposit_a, pool_b + deposit_b, lp_supply + lp_amount, lp_amount).unwrap();
+                assert!(withdraw_a <= deposit_a && withdraw_b <= deposit_b);
+            }
+        }
     }
 
     #[test]
@@ -399,46 +1242,211 @@ mod tests {
         // 1% for every fee type
         assert_eq!(
             Some((88_342, 1_000, 1_000, 1_000)),
-            calculate_swap_amounts(1_000_000, 1_000_000, 100_000,
-                                   1_000_000, 1_000_000, 1_000_000)
+            calculate_swap_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 100_000,
+                                   FeeRates { dao: 1_000_000, lp: 1_000_000, creator: 1_000_000 })
         );
 
         // 90.99% fee
         assert_eq!(
             Some((8920, 90000, 990, 0)),
-            calculate_swap_amounts(1_000_000, 1_000_000, 100_000,
-                                   90_000_000, 990_000, 0)
+            calculate_swap_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 100_000,
+                                   FeeRates { dao: 90_000_000, lp: 990_000, creator: 0 })
         );
 
         // 90.99% fee
         assert_eq!(
             Some((8198, 990, 90000, 0)),
-            calculate_swap_amounts(1_000_000, 1_000_000, 100_000,
-                                   990_000, 90_000_000, 0)
+            calculate_swap_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 100_000,
+                                   FeeRates { dao: 990_000, lp: 90_000_000, creator: 0 })
         );
 
         // over 100% total fee
         assert_eq!(
             None,
-            calculate_swap_amounts(1_000_000, 1_000_000, 100_000,
-                                   50_000_000, 50_000_000, 1_000_000)
+            calculate_swap_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 100_000,
+                                   FeeRates { dao: 50_000_000, lp: 50_000_000, creator: 1_000_000 })
         );
+    }
 
-        // todo: add more unit tests
+    #[test]
+    fn test_is_total_fee_rate_valid() {
+        assert!(is_total_fee_rate_valid(0, 0, 0));
+        assert!(is_total_fee_rate_valid(50_000, 300_000, 100_000));
+        assert!(is_total_fee_rate_valid(0, 99_999_999, 0));
+        assert!(!is_total_fee_rate_valid(0, 100_000_000, 0));
+        assert!(!is_total_fee_rate_valid(50_000_000, 50_000_000, 1_000_000));
+        assert!(!is_total_fee_rate_valid(u32::MAX, u32::MAX, u32::MAX));
     }
 
     #[test]
     fn test_calculate_swap_amounts_without_fees() {
-        assert_eq!(Some((0, 0, 0, 0)), calculate_swap_amounts(1, 100, 0, 0, 0, 0));
-        assert_eq!(Some((0, 0, 0, 0)), calculate_swap_amounts(100, 10, 11, 0, 0, 0));
-        assert_eq!(Some((1, 0, 0, 0)), calculate_swap_amounts(100_000_000, 100, 1_011_000, 0, 0, 0));
-        assert_eq!(Some((4, 0, 0, 0)), calculate_swap_amounts(100, 100, 5, 0, 0, 0));
-        assert_eq!(Some((49_950_049, 0, 0, 0)), calculate_swap_amounts(100_000_000_000, 50_000_000_000, 100_000_000, 0, 0, 0));
-        assert_eq!(Some((372_208_436, 0, 0, 0)), calculate_swap_amounts(100_000_000_000, 50_000_000_000, 750_000_000, 0, 0, 0));
-        assert_eq!(Some((3_333_333, 0, 0, 0)), calculate_swap_amounts(10_000_000, 10_000_000, 5_000_000, 0, 0, 0));
-        assert_eq!(Some((6_666_666, 0, 0, 0)), calculate_swap_amounts(10_000_000, 10_000_000, 20_000_000, 0, 0, 0));
-        assert_eq!(Some((8_000_000, 0, 0, 0)), calculate_swap_amounts(10_000_000, 10_000_000, 40_000_000, 0, 0, 0));
-        assert_eq!(Some((12_990_906, 0, 0, 0)), calculate_swap_amounts(70_000_000, 13_000_000, 100_000_000_000, 0, 0, 0));
+        assert_eq!(Some((0, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 1, 100, 0, FeeRates::default()));
+        assert_eq!(Some((0, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 100, 10, 11, FeeRates::default()));
+        assert_eq!(Some((1, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 100_000_000, 100, 1_011_000, FeeRates::default()));
+        assert_eq!(Some((4, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 100, 100, 5, FeeRates::default()));
+        assert_eq!(Some((49_950_049, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 100_000_000_000, 50_000_000_000, 100_000_000, FeeRates::default()));
+        assert_eq!(Some((372_208_436, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 100_000_000_000, 50_000_000_000, 750_000_000, FeeRates::default()));
+        assert_eq!(Some((3_333_333, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 10_000_000, 10_000_000, 5_000_000, FeeRates::default()));
+        assert_eq!(Some((6_666_666, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 10_000_000, 10_000_000, 20_000_000, FeeRates::default()));
+        assert_eq!(Some((8_000_000, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 10_000_000, 10_000_000, 40_000_000, FeeRates::default()));
+        assert_eq!(Some((12_990_906, 0, 0, 0)), calculate_swap_amounts(&Curve::ConstantProduct, 70_000_000, 13_000_000, 100_000_000_000, FeeRates::default()));
+    }
+
+    #[test]
+    fn test_calculate_swap_in_amount() {
+        // 1_000_000 * 100_000 / 900_000 = 111_111.1...
+        assert_eq!(
+            Some((111_112, 0, 0, 0)),
+            calculate_swap_in_amount(&Curve::ConstantProduct, 1_000_000, 1_000_000, 100_000, FeeRates::default())
+        );
+
+        // whole pool can't be bought
+        assert_eq!(None, calculate_swap_in_amount(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000_000, FeeRates::default()));
+
+        // over 100% total fee
+        let fee_rates = FeeRates { dao: 50_000_000, lp: 50_000_000, creator: 0 };
+        assert_eq!(None, calculate_swap_in_amount(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000, fee_rates));
+
+        // LP fee stays in pool and limits max out amount: out = y * a / (x + 10a) < y / 10
+        let fee_rates = FeeRates { dao: 0, lp: 90_000_000, creator: 0 };
+        assert_eq!(None, calculate_swap_in_amount(&Curve::ConstantProduct, 1_000_000, 1_000_000, 100_000, fee_rates));
+        assert!(calculate_swap_in_amount(&Curve::ConstantProduct, 1_000_000, 1_000_000, 50_000, fee_rates).is_some());
+
+        // in amount is never lower than needed for exact out amount and never much higher
+        let curves = [Curve::ConstantProduct, Curve::StableSwap { amp: 85 }, Curve::Weighted { weight_a: 200_000 }];
+        let fee_rates = [
+            FeeRates::default(),
+            FeeRates { dao: 1_000_000, lp: 1_000_000, creator: 1_000_000 },
+            FeeRates { dao: 50_000, lp: 300_000, creator: 0 },
+            FeeRates { dao: 0, lp: 5_000_000, creator: 10_000_000 },
+        ];
+        for curve in curves {
+            for fee_rates in fee_rates {
+                for out_amount in [1, 1_000, 3_333_333, 30_000_000] {
+                    let (in_amount, dao_fee, lp_fee, creator_fee) = calculate_swap_in_amount(
+                        &curve, 70_000_000, 100_000_000, out_amount, fee_rates).unwrap();
+                    let (swap_out_amount, swap_dao_fee, swap_lp_fee, swap_creator_fee) = calculate_swap_amounts(
+                        &curve, 70_000_000, 100_000_000, in_amount, fee_rates).unwrap();
+
+                    assert_eq!((dao_fee, lp_fee, creator_fee), (swap_dao_fee, swap_lp_fee, swap_creator_fee));
+                    assert!(swap_out_amount >= out_amount);
+
+                    // dust amounts are dominated by rounding, each of three fees is rounded down
+                    // and single unit after fees is worth more before fees
+                    if out_amount >= 1_000_000 {
+                        let total_fee_rate = (fee_rates.dao + fee_rates.lp + fee_rates.creator) as u128;
+                        let rounding = 4 * (FEE_RATE_BASE_DIVIDER / (FEE_RATE_BASE_DIVIDER - total_fee_rate) + 1) as u64;
+                        let (lower_out_amount, _, _, _) = calculate_swap_amounts(
+                            &curve, 70_000_000, 100_000_000, in_amount - in_amount / 100_000 - rounding, fee_rates).unwrap();
+                        assert!(lower_out_amount < out_amount);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_stable_swap_amounts() {
+        let stable = Curve::StableSwap { amp: 100 };
+
+        // balanced stable pool gives far better execution than constant product
+        let (stable_out, _, _, _) = calculate_swap_amounts(
+            &stable, 1_000_000_000, 1_000_000_000, 10_000_000, FeeRates::default()).unwrap();
+        let (constant_product_out, _, _, _) = calculate_swap_amounts(
+            &Curve::ConstantProduct, 1_000_000_000, 1_000_000_000, 10_000_000, FeeRates::default()).unwrap();
+        assert!(stable_out < 10_000_000 && stable_out > 9_990_000);
+        assert!(stable_out > constant_product_out);
+
+        // fees are charged the same way for every curve
+        let (out, dao_fee, lp_fee, creator_fee) = calculate_swap_amounts(
+            &stable, 1_000_000_000, 1_000_000_000, 10_000_000,
+            FeeRates { dao: 1_000_000, lp: 1_000_000, creator: 1_000_000 }).unwrap();
+        assert_eq!((100_000, 100_000, 100_000), (dao_fee, lp_fee, creator_fee));
+        assert!(out < 9_700_000 && out > 9_690_000);
+
+        // single sided deposit and withdrawal dispatch on curve
+        let (lp_amount, _, _, _) = calculate_deposit_single_amounts(
+            &stable, 1_000_000_000, 1_000_000_000, 2_000_000_000, 10_000_000, FeeRates::default()).unwrap();
+        assert!(lp_amount <= 10_000_000 && lp_amount > 9_990_000);
+
+        let (out_amount, _, _, _) = calculate_withdraw_single_amounts(
+            &stable, 1_000_000_000, 1_000_000_000, 2_000_000_000, 20_000_000, FeeRates::default()).unwrap();
+        assert!(out_amount <= 20_000_000 && out_amount > 19_980_000);
+    }
+
+    #[test]
+    fn test_calculate_weighted_amounts() {
+        let weighted = Curve::Weighted { weight_a: 800_000 };
+
+        // 80/20 pool, spot price of A is (wa / wb) * (y / x) = 16 B
+        // 4_000_000_000 * (1 - (1_000_000_000 / 1_001_000_000)^4) = 15_960_079.8...
+        assert_eq!(
+            Some((15_960_079, 0, 0, 0)),
+            calculate_swap_amounts(&weighted, 1_000_000_000, 4_000_000_000, 1_000_000, FeeRates::default())
+        );
+
+        // 1_000_000_000 * (1 - (4_000_000_000 / 4_016_000_000)^0.25) = 997_507.4...
+        assert_eq!(
+            Some((997_507, 0, 0, 0)),
+            calculate_swap_amounts(&weighted.oriented(PoolToken::B), 4_000_000_000, 1_000_000_000, 16_000_000, FeeRates::default())
+        );
+
+        // single deposit of token A only swaps 20% of deposit, fees are charged on swapped part
+        let (lp_amount, dao_fee, lp_fee, creator_fee) = calculate_deposit_single_amounts(
+            &weighted, 1_000_000_000, 4_000_000_000, 1_000_000_000, 1_000_000,
+            FeeRates { dao: 1_000_000, lp: 1_000_000, creator: 1_000_000 }).unwrap();
+        assert_eq!((2_000, 2_000, 2_000), (dao_fee, lp_fee, creator_fee));
+        // 1_000_000_000 * ((1 + 994_000 / 1_000_000_000)^0.8 - 1) = 795_120.9...
+        assert_eq!(795_120, lp_amount);
+
+        // 4_000_000 of token B withdrawn plus 1_000_000 of token A swapped against remaining pool
+        let (out_amount, _, _, _) = calculate_withdraw_single_amounts(
+            &weighted.oriented(PoolToken::B), 4_000_000_000, 1_000_000_000, 1_000_000_000, 1_000_000, FeeRates::default()).unwrap();
+        assert!(out_amount <= 19_960_039 && out_amount > 19_960_000);
+    }
+
+    #[test]
+    fn test_calculate_deposit_single_amounts() {
+        // empty pool
+        assert_eq!(None, calculate_deposit_single_amounts(&Curve::ConstantProduct, 0, 0, 0, 1_000, FeeRates::default()));
+        assert_eq!(None, calculate_deposit_single_amounts(&Curve::ConstantProduct, 1_000, 1_000, 0, 1_000, FeeRates::default()));
+
+        // sqrt(4) - 1 = 1 -> doubling LP supply
+        assert_eq!(
+            Some((1_000_000, 0, 0, 0)),
+            calculate_deposit_single_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000_000, 3_000_000, FeeRates::default())
+        );
+
+        assert_eq!(
+            Some((48_808, 0, 0, 0)),
+            calculate_deposit_single_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000_000, 100_000, FeeRates::default())
+        );
+
+        // 1% for every fee type, charged on half of deposit
+        assert_eq!(
+            Some((48_093, 500, 500, 500)),
+            calculate_deposit_single_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000_000, 100_000,
+                                             FeeRates { dao: 1_000_000, lp: 1_000_000, creator: 1_000_000 })
+        );
+
+        // rounding in favor of pool
+        assert_eq!(
+            Some((0, 0, 0, 0)),
+            calculate_deposit_single_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000, 1, FeeRates::default())
+        );
+
+        // single deposit is never better than two sided deposit
+        let (lp_amount, _, _, _) = calculate_deposit_single_amounts(
+            &Curve::ConstantProduct, 10_000_000, 10_000_000, 5_000_000, 2_000_000, FeeRates::default()).unwrap();
+        let (_, _, two_sided_lp_amount) = calculate_deposit_amounts(
+            10_000_000, 10_000_000, 5_000_000, 1_000_000, 1_000_000).unwrap();
+        assert!(lp_amount <= two_sided_lp_amount);
+
+        // overflow
+        assert_eq!(
+            None,
+            calculate_deposit_single_amounts(&Curve::ConstantProduct, u64::MAX, u64::MAX, 1_000_000, u64::MAX, FeeRates::default())
+        );
     }
 
     #[test]
@@ -491,4 +1499,31 @@ mod tests {
         // todo: tests with rounding errors
         // todo: tests with overflow
     }
+
+    #[test]
+    fn test_calculate_withdraw_single_amounts() {
+        // withdrawing whole pool liquidity as single token isn't possible
+        assert_eq!(None, calculate_withdraw_single_amounts(&Curve::ConstantProduct, 1_000, 1_000, 100, 100, FeeRates::default()));
+        assert_eq!(None, calculate_withdraw_single_amounts(&Curve::ConstantProduct, 1_000, 1_000, 100, 101, FeeRates::default()));
+
+        // 10% share: 100_000 + swap of 100_000 against (900_000, 900_000)
+        assert_eq!(
+            Some((190_000, 0, 0, 0)),
+            calculate_withdraw_single_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000_000, 100_001, FeeRates::default())
+        );
+
+        // 1% for every fee type, charged on swapped other token share
+        assert_eq!(
+            Some((187_474, 1_000, 1_000, 1_000)),
+            calculate_withdraw_single_amounts(&Curve::ConstantProduct, 1_000_000, 1_000_000, 1_000_000, 100_001,
+                                              FeeRates { dao: 1_000_000, lp: 1_000_000, creator: 1_000_000 })
+        );
+
+        // single withdrawal is never better than withdrawal of both tokens and swap
+        let (out_amount, _, _, _) = calculate_withdraw_single_amounts(
+            &Curve::ConstantProduct, 5_000_000, 20_000_000, 1_000_000, 250_000, FeeRates::default()).unwrap();
+        let (withdraw_out, withdraw_other) = calculate_withdraw_amounts(
+            5_000_000, 20_000_000, 1_000_000, 250_000).unwrap();
+        assert!(out_amount <= withdraw_out + withdraw_other / 4);
+    }
 }
\ No newline at end of file
@@ -1,3 +1,7 @@
+pub mod curve;
+pub mod event;
 pub mod instruction;
+pub mod oracle;
 pub mod processor;
+pub mod quote;
 pub mod state;
\ No newline at end of file
@@ -0,0 +1,154 @@
+// Uniswap v2 style price oracle. Pool accumulates spot prices multiplied by seconds elapsed since
+// last update, before balances are changed by swap, deposit or withdraw. Accumulators are allowed
+// to overflow, TWAP is computed from difference of two observations so wrapping cancels out
+use crate::swap::state::SwapPool;
+
+// Prices are UQ64.64 fixed point numbers
+pub const PRICE_ONE: u128 = 1 << 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceObservation {
+    pub timestamp: i64,
+    // Cumulative price of token A denominated in token B
+    pub price_a_cumulative: u128,
+    // Cumulative price of token B denominated in token A
+    pub price_b_cumulative: u128,
+}
+
+// Has to be called before pool balances are changed
+pub fn update_cumulative_prices(swap_pool: &mut SwapPool, timestamp: i64) {
+    let observation = observe(swap_pool, timestamp);
+
+    swap_pool.price_a_cumulative = observation.price_a_cumulative;
+    swap_pool.price_b_cumulative = observation.price_b_cumulative;
+    swap_pool.last_update_timestamp = swap_pool.last_update_timestamp.max(timestamp);
+}
+
+// Returns cumulative prices as they would be at provided timestamp, so current observation
+// can be read without pool being updated
+pub fn observe(swap_pool: &SwapPool, timestamp: i64) -> PriceObservation {
+    let mut observation = PriceObservation {
+        timestamp: swap_pool.last_update_timestamp.max(timestamp),
+        price_a_cumulative: swap_pool.price_a_cumulative,
+        price_b_cumulative: swap_pool.price_b_cumulative,
+    };
+
+    let elapsed = timestamp.saturating_sub(swap_pool.last_update_timestamp);
+    if elapsed <= 0 || swap_pool.balance_a == 0 || swap_pool.balance_b == 0 {
+        return observation;
+    }
+
+    // Curve of loaded pool is always valid
+    let curve = match swap_pool.curve() {
+        Ok(curve) => curve,
+        Err(_) => return observation,
+    };
+
+    let balance_a = swap_pool.balance_a as u128;
+    let balance_b = swap_pool.balance_b as u128;
+
+    // Extreme balances can make price unrepresentable, such period is skipped instead of failing pool operation
+    if let Some(price_a) = curve.spot_price(balance_a, balance_b) {
+        observation.price_a_cumulative = observation.price_a_cumulative
+            .wrapping_add(price_a.wrapping_mul(elapsed as u128));
+    }
+
+    if let Some(price_b) = curve.reversed().spot_price(balance_b, balance_a) {
+        observation.price_b_cumulative = observation.price_b_cumulative
+            .wrapping_add(price_b.wrapping_mul(elapsed as u128));
+    }
+
+    observation
+}
+
+// Returns time weighted average prices of token A and token B between two observations, as UQ64.64
+pub fn compute_twap(older: &PriceObservation, newer: &PriceObservation) -> Option<(u128, u128)> {
+    let elapsed = newer.timestamp.checked_sub(older.timestamp)?;
+    if elapsed <= 0 {
+        return None;
+    }
+
+    Some((
+        newer.price_a_cumulative.wrapping_sub(older.price_a_cumulative) / elapsed as u128,
+        newer.price_b_cumulative.wrapping_sub(older.price_b_cumulative) / elapsed as u128,
+    ))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use solana_program::pubkey::Pubkey;
+    use crate::swap::curve::Curve;
+    use super::*;
+
+    fn pool(balance_a: u64, balance_b: u64, curve: Curve) -> SwapPool {
+        let mut swap_pool = SwapPool::default();
+        swap_pool.seed = Pubkey::new_unique().to_bytes();
+        swap_pool.token_account_a = Pubkey::new_unique();
+        swap_pool.token_account_b = Pubkey::new_unique();
+        swap_pool.balance_a = balance_a;
+        swap_pool.balance_b = balance_b;
+        swap_pool.lp_mint = Pubkey::new_unique();
+        swap_pool.last_update_timestamp = 1_000;
+        swap_pool.creator = Pubkey::new_unique();
+        swap_pool.set_curve(&curve);
+
+        swap_pool
+    }
+
+    #[test]
+    fn test_update_cumulative_prices() {
+        let mut swap_pool = pool(1_000, 2_000, Curve::ConstantProduct);
+
+        update_cumulative_prices(&mut swap_pool, 1_010);
+        assert_eq!(20 * PRICE_ONE, { swap_pool.price_a_cumulative });
+        assert_eq!(5 * PRICE_ONE, { swap_pool.price_b_cumulative });
+        assert_eq!(1_010, { swap_pool.last_update_timestamp });
+
+        // same timestamp and clock going backwards doesn't accumulate
+        update_cumulative_prices(&mut swap_pool, 1_010);
+        update_cumulative_prices(&mut swap_pool, 1_005);
+        assert_eq!(20 * PRICE_ONE, { swap_pool.price_a_cumulative });
+        assert_eq!(1_010, { swap_pool.last_update_timestamp });
+
+        // empty pool only moves timestamp
+        let mut empty_pool = pool(0, 0, Curve::ConstantProduct);
+        update_cumulative_prices(&mut empty_pool, 2_000);
+        assert_eq!((0, 0, 2_000), (empty_pool.price_a_cumulative, empty_pool.price_b_cumulative,
+                                   empty_pool.last_update_timestamp));
+    }
+
+    #[test]
+    fn test_compute_twap() {
+        let mut swap_pool = pool(1_000, 1_000, Curve::ConstantProduct);
+        let start = observe(&swap_pool, 1_000);
+
+        // price 1 for 30 seconds, then price 4 for 10 seconds
+        update_cumulative_prices(&mut swap_pool, 1_030);
+        swap_pool.balance_a = 500;
+        swap_pool.balance_b = 2_000;
+        let end = observe(&swap_pool, 1_040);
+
+        assert_eq!(Some((7 * PRICE_ONE / 4, 13 * PRICE_ONE / 16)), compute_twap(&start, &end));
+        assert_eq!(None, compute_twap(&end, &start));
+        assert_eq!(None, compute_twap(&end, &end));
+
+        // observation doesn't modify pool
+        assert_eq!(1_030, { swap_pool.last_update_timestamp });
+    }
+
+    #[test]
+    fn test_twap_accumulator_wrapping() {
+        let mut swap_pool = pool(1_000, 3_000, Curve::ConstantProduct);
+        swap_pool.price_a_cumulative = u128::MAX - PRICE_ONE;
+        swap_pool.price_b_cumulative = u128::MAX;
+        let start = observe(&swap_pool, 1_000);
+
+        update_cumulative_prices(&mut swap_pool, 1_100);
+        assert!(swap_pool.price_a_cumulative < start.price_a_cumulative);
+
+        let (price_a, price_b) = compute_twap(&start, &observe(&swap_pool, 1_100)).unwrap();
+        assert_eq!(3 * PRICE_ONE, price_a);
+        assert_eq!(PRICE_ONE / 3, price_b);
+    }
+}
\ No newline at end of file
@@ -1,22 +1,38 @@
 use solana_program::account_info::{AccountInfo, next_account_info};
+use solana_program::clock::Clock;
 use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::msg;
-use solana_program::program::{invoke, invoke_signed};
+use solana_program::program::{invoke, invoke_signed, set_return_data};
 use solana_program::pubkey::Pubkey;
 use solana_program::program_pack::Pack;
 use solana_program::rent::Rent;
 use solana_program::sysvar::Sysvar;
 use spl_token::state::Mint;
-use solana_program::program_error::ProgramError::{IllegalOwner, InvalidAccountData, InvalidInstructionData, MissingRequiredSignature};
-use crate::swap::state::{CreatorFee, SwapPool};
-use crate::swap::instruction::{calculate_deposit_amounts, calculate_swap_amounts, calculate_withdraw_amounts, SwapInstruction};
-use crate::processor::{create_spl_token_account, transfer_spl_token};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use solana_program::program_error::ProgramError;
+use solana_program::program_error::ProgramError::{IllegalOwner, IncorrectProgramId, InvalidAccountData, InvalidSeeds,
+                                                  MissingRequiredSignature, NotEnoughAccountKeys};
+use crate::swap::curve::Curve;
+use crate::swap::event::{FeeAmounts, SwapEvent};
+use crate::swap::oracle::update_cumulative_prices;
+use crate::swap::quote::{DepositQuote, SwapQuote, WithdrawQuote};
+use crate::swap::state::{CreatorFee, FeeChangeProposal, SwapPool};
+use crate::swap::instruction::{calculate_deposit_amounts, calculate_deposit_single_amounts, calculate_flash_loan_fees,
+                                calculate_swap_amounts,
+                                calculate_swap_in_amount, calculate_withdraw_amounts, calculate_withdraw_single_amounts,
+                                is_total_fee_rate_valid, FeeRates, PoolToken, SwapInstruction, MINIMUM_LIQUIDITY};
+use crate::config::processor::load_config;
+use crate::config::state::HodorConfig;
+use crate::error::HodorError;
+use crate::processor::{calculate_pre_fee_amount, create_pda_account, create_spl_token_account, is_token_program,
+                       transfer_spl_token, transfer_spl_token_signed, unpack_token_account};
 
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     match SwapInstruction::unpack(instruction_data)? {
-        SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate } => {
+        SwapInstruction::CreatePool { seed, lp_fee_rate, creator_fee_rate, curve, canonical } => {
             msg!("Swap:CreatePool");
-            process_create_pool(program_id, accounts, seed, lp_fee_rate, creator_fee_rate)
+            process_create_pool(program_id, accounts, seed, lp_fee_rate, creator_fee_rate, curve, canonical)
         }
         SwapInstruction::Swap { in_amount, min_out_amount } => {
             msg!("Swap:Swap");
@@ -30,11 +46,87 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data:
             msg!("Swap:Withdraw");
             process_wthdraw(program_id, accounts, lp_amount, min_a, min_b)
         }
+        SwapInstruction::ChangeCreatorWithdrawAuthority { new_authority } => {
+            msg!("Swap:ChangeCreatorWithdrawAuthority");
+            process_change_creator_withdraw_authority(program_id, accounts, new_authority)
+        }
+        SwapInstruction::WithdrawCreatorFee { amount_a, amount_b } => {
+            msg!("Swap:WithdrawCreatorFee");
+            process_withdraw_creator_fee(program_id, accounts, amount_a, amount_b)
+        }
+        SwapInstruction::CollectDaoFees => {
+            msg!("Swap:CollectDaoFees");
+            process_collect_dao_fees(program_id, accounts)
+        }
+        SwapInstruction::DepositSingle { token, amount_in, min_lp_out } => {
+            msg!("Swap:DepositSingle");
+            process_deposit_single(program_id, accounts, token, amount_in, min_lp_out)
+        }
+        SwapInstruction::WithdrawSingle { lp_amount, token, min_out } => {
+            msg!("Swap:WithdrawSingle");
+            process_withdraw_single(program_id, accounts, lp_amount, token, min_out)
+        }
+        SwapInstruction::SwapExactOut { out_amount, max_in_amount } => {
+            msg!("Swap:SwapExactOut");
+            process_swap_exact_out(program_id, accounts, out_amount, max_in_amount)
+        }
+        SwapInstruction::SwapRoute { in_amount, min_final_out } => {
+            msg!("Swap:SwapRoute");
+            process_swap_route(program_id, accounts, in_amount, min_final_out)
+        }
+        SwapInstruction::FlashLoan { token, amount } => {
+            msg!("Swap:FlashLoan");
+            process_flash_loan(program_id, accounts, token, amount)
+        }
+        SwapInstruction::Sync => {
+            msg!("Swap:Sync");
+            process_sync(program_id, accounts)
+        }
+        SwapInstruction::Skim => {
+            msg!("Swap:Skim");
+            process_skim(program_id, accounts)
+        }
+        SwapInstruction::MigratePool => {
+            msg!("Swap:MigratePool");
+            process_migrate_pool(program_id, accounts)
+        }
+        SwapInstruction::ProposeFeeChange { lp_fee_rate, creator_fee_rate } => {
+            msg!("Swap:ProposeFeeChange");
+            process_propose_fee_change(program_id, accounts, lp_fee_rate, creator_fee_rate)
+        }
+        SwapInstruction::ApplyFeeChange => {
+            msg!("Swap:ApplyFeeChange");
+            process_apply_fee_change(program_id, accounts)
+        }
+        SwapInstruction::Pause => {
+            msg!("Swap:Pause");
+            process_set_paused(program_id, accounts, true)
+        }
+        SwapInstruction::Unpause => {
+            msg!("Swap:Unpause");
+            process_set_paused(program_id, accounts, false)
+        }
+        SwapInstruction::Quote { in_token, in_amount } => {
+            msg!("Swap:Quote");
+            process_quote(program_id, accounts, in_token, in_amount)
+        }
+        SwapInstruction::QuoteDeposit { max_a, max_b } => {
+            msg!("Swap:QuoteDeposit");
+            process_quote_deposit(program_id, accounts, max_a, max_b)
+        }
+        SwapInstruction::QuoteWithdraw { lp_amount } => {
+            msg!("Swap:QuoteWithdraw");
+            process_quote_withdraw(program_id, accounts, lp_amount)
+        }
+        SwapInstruction::ClosePool => {
+            msg!("Swap:ClosePool");
+            process_close_pool(program_id, accounts)
+        }
     }
 }
 
 fn process_create_pool(program_id: &Pubkey, accounts: &[AccountInfo], seed: [u8; 32],
-                       lp_fee_rate: u32, creator_fee_rate: u32) -> ProgramResult {
+                       lp_fee_rate: u32, creator_fee_rate: u32, curve: Curve, canonical: bool) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
     let fee_payer_info = next_account_info(accounts_iter)?;
@@ -44,20 +136,60 @@ fn process_create_pool(program_id: &Pubkey, accounts: &[AccountInfo], seed: [u8;
     let token_b_mint_info = next_account_info(accounts_iter)?;
     let token_b_account_info = next_account_info(accounts_iter)?;
     let lp_mint_info = next_account_info(accounts_iter)?;
+    let locked_lp_info = next_account_info(accounts_iter)?;
 
     let spl_token_program = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
-
-    let rent = Rent::get()?;
+    let token_a_program = next_account_info(accounts_iter)?;
+    let token_b_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
 
     if !fee_payer_info.is_signer {
         return Err(MissingRequiredSignature);
     }
 
+    let config = load_config(program_id, config_info)?;
+    if !config.is_pool_fee_valid(lp_fee_rate, creator_fee_rate)
+        || !is_total_fee_rate_valid(config.dao_fee_rate, lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
     if token_a_mint_info.key == token_b_mint_info.key {
         return Err(InvalidAccountData);
     }
 
+    // LP mint is always created by SPL token program, pool tokens may be Token-2022 mints
+    if *spl_token_program.key != spl_token::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    check_token_program(token_a_mint_info, token_a_program)?;
+    check_token_program(token_b_mint_info, token_b_program)?;
+    check_mint_extensions(token_a_mint_info)?;
+    check_mint_extensions(token_b_mint_info)?;
+
+    if !curve.is_valid() {
+        return Err(HodorError::InvalidCurve.into());
+    }
+
+    // Canonical pool address is fully determined by sorted mints, LP fee rate and curve type,
+    // so second canonical pool for the same parameters fails on state account creation
+    let canonical_bump = if canonical {
+        if token_a_mint_info.key > token_b_mint_info.key {
+            return Err(InvalidAccountData);
+        }
+
+        let (canonical_address, bump, canonical_seed) = SwapPool::find_canonical_address(
+            token_a_mint_info.key, token_b_mint_info.key, lp_fee_rate, curve.curve_type(), program_id);
+        if seed != canonical_seed || canonical_address != *swap_state_info.key {
+            return Err(InvalidSeeds);
+        }
+
+        Some(bump)
+    } else {
+        None
+    };
+
     let seeds_a = [swap_state_info.key.as_ref(), b"A"];
     create_spl_token_account(
         token_a_account_info,
@@ -66,7 +198,7 @@ fn process_create_pool(program_id: &Pubkey, accounts: &[AccountInfo], seed: [u8;
         fee_payer_info,
         &seeds_a,
         program_id,
-        spl_token_program,
+        token_a_program,
         system_program,
     )?;
 
@@ -78,7 +210,7 @@ fn process_create_pool(program_id: &Pubkey, accounts: &[AccountInfo], seed: [u8;
         fee_payer_info,
         &seeds_b,
         program_id,
-        spl_token_program,
+        token_b_program,
         system_program,
     )?;
 
@@ -86,27 +218,19 @@ fn process_create_pool(program_id: &Pubkey, accounts: &[AccountInfo], seed: [u8;
     let seeds_mint = [swap_state_info.key.as_ref(), b"LP"];
     let (lp_mint_account, bump_seed) = Pubkey::find_program_address(&seeds_mint, program_id);
 
-    let create_mint_account_instruction = solana_program::system_instruction::create_account(
-        &fee_payer_info.key,
-        &lp_mint_account,
-        rent.minimum_balance(spl_token::state::Mint::LEN),
-        spl_token::state::Mint::LEN as u64,
+    create_pda_account(
+        lp_mint_info,
+        spl_token::state::Mint::LEN,
         &spl_token::id(),
-    );
-    invoke_signed(
-        &create_mint_account_instruction,
-        &[
-            system_program.clone(),
-            fee_payer_info.clone(),
-            lp_mint_info.clone(),
-        ],
-        &[&[&seeds_mint[0], &seeds_mint[1], &[bump_seed]]], // todo seeds explode
+        fee_payer_info,
+        system_program,
+        &[seeds_mint[0], seeds_mint[1], &[bump_seed]], // todo seeds explode
     )?;
 
     let initialize_mint_instruction = spl_token::instruction::initialize_mint2(
-        &spl_token_program.key,
+        spl_token_program.key,
         &lp_mint_account,
-        &swap_state_info.key,
+        swap_state_info.key,
         None,
         6,
     )?;
@@ -119,49 +243,47 @@ fn process_create_pool(program_id: &Pubkey, accounts: &[AccountInfo], seed: [u8;
         ],
     )?;
 
-    let (creator_fee, state_size) = {
-        if creator_fee_rate > 0 {
-            (Some(CreatorFee {
-                rate: creator_fee_rate,
-                balance_a: 0,
-                balance_b: 0,
-                withdraw_authority: fee_payer_info.key.clone(),
-            }), SwapPool::WITH_CREATOR_FEE_SIZE)
-        } else {
-            (None, SwapPool::BASE_SIZE)
-        }
-    };
-
-
-    let create_state_account_instruction = solana_program::system_instruction::create_account(
-        &fee_payer_info.key,
-        &swap_state_info.key,
-        rent.minimum_balance(state_size),
-        state_size as u64,
-        &program_id,
-    );
+    // Minimum liquidity minted on first deposit is locked here forever, pool never transfers from it
+    let seeds_locked_lp = [swap_state_info.key.as_ref(), SwapPool::LOCKED_LP_SEED];
+    create_spl_token_account(
+        locked_lp_info,
+        lp_mint_info,
+        swap_state_info,
+        fee_payer_info,
+        &seeds_locked_lp,
+        program_id,
+        spl_token_program,
+        system_program,
+    )?;
 
-    // todo: test making sure it fails if account exists or if seed is incorrect
-    invoke_signed(
-        &create_state_account_instruction,
-        &[
-            system_program.clone(),
-            fee_payer_info.clone(),
-            swap_state_info.clone(),
-        ],
-        &[&[&seed]],
+    let mut swap_pool_state = SwapPool::default();
+    swap_pool_state.seed = seed;
+    swap_pool_state.token_account_a = *token_a_account_info.key;
+    swap_pool_state.token_account_b = *token_b_account_info.key;
+    swap_pool_state.lp_mint = lp_mint_account;
+    swap_pool_state.lp_fee_rate = lp_fee_rate;
+    swap_pool_state.last_update_timestamp = Clock::get()?.unix_timestamp;
+    swap_pool_state.creator = *fee_payer_info.key;
+    swap_pool_state.set_curve(&curve);
+    swap_pool_state.set_canonical_bump(canonical_bump);
+    if creator_fee_rate > 0 {
+        swap_pool_state.set_creator_fee(Some(CreatorFee {
+            rate: creator_fee_rate,
+            balance_a: 0,
+            balance_b: 0,
+            withdraw_authority: *fee_payer_info.key,
+        }));
+    }
+    create_pda_account(
+        swap_state_info,
+        SwapPool::LEN,
+        program_id,
+        fee_payer_info,
+        system_program,
+        swap_pool_state.signer_seeds().as_slice(),
     )?;
 
-    SwapPool {
-        seed: seed,
-        token_account_a: *token_a_account_info.key,
-        token_account_b: *token_b_account_info.key,
-        balance_a: 0,
-        balance_b: 0,
-        lp_mint: lp_mint_account,
-        lp_fee_rate,
-        creator_fee: creator_fee,
-    }.pack(&mut swap_state_info.try_borrow_mut_data()?)?;
+    swap_pool_state.pack(&mut swap_state_info.try_borrow_mut_data()?)?;
 
     Ok(())
 }
@@ -180,6 +302,11 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], min_a: u64, ma
     let destination_lp_info = next_account_info(accounts_iter)?;
 
     let spl_token_program = next_account_info(accounts_iter)?;
+    let locked_lp_info = next_account_info(accounts_iter)?;
+    let token_a_mint_info = next_account_info(accounts_iter)?;
+    let token_a_program = next_account_info(accounts_iter)?;
+    let token_b_mint_info = next_account_info(accounts_iter)?;
+    let token_b_program = next_account_info(accounts_iter)?;
 
     if !owner_info.is_signer {
         return Err(MissingRequiredSignature);
@@ -189,43 +316,65 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], min_a: u64, ma
         return Err(IllegalOwner);
     }
 
-    let mut swap_pool_state = SwapPool::unpack(&swap_pool_state_info.try_borrow_data()?)?;
+    if *spl_token_program.key != spl_token::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    let state_data = swap_pool_state_info.try_borrow_data()?;
+    let swap_pool_state = SwapPool::load_active(&state_data)?;
     if swap_pool_state.token_account_a != *destination_a_info.key
         || swap_pool_state.token_account_b != *destination_b_info.key
         || swap_pool_state.lp_mint != *lp_mint_info.key {
         return Err(InvalidAccountData);
     }
 
+    check_token_program(token_a_mint_info, token_a_program)?;
+    check_token_program(token_b_mint_info, token_b_program)?;
+
     let lp_mint_state = Mint::unpack(&lp_mint_info.try_borrow_data()?)?;
 
-    let (token_a_transfer_amount, token_b_transfer_amount, lp_mint_amount) = calculate_deposit_amounts(
+    let (token_a_transfer_amount, token_b_transfer_amount, _) = calculate_deposit_amounts(
         swap_pool_state.balance_a,
         swap_pool_state.balance_b,
         lp_mint_state.supply,
         max_a,
-        max_b).ok_or(InvalidInstructionData)?;
+        max_b).ok_or(HodorError::MathOverflow)?;
 
     if token_a_transfer_amount < min_a || token_b_transfer_amount < min_b {
-        // todo: add custom error message
-        return Err(InvalidInstructionData);
+        return Err(HodorError::SlippageExceeded.into());
     }
 
-    transfer_spl_token(
+    let received_a_amount = transfer_spl_token(
         source_a_info,
+        token_a_mint_info,
         destination_a_info,
         owner_info,
-        spl_token_program,
+        token_a_program,
         token_a_transfer_amount,
     )?;
 
-    transfer_spl_token(
+    let received_b_amount = transfer_spl_token(
         source_b_info,
+        token_b_mint_info,
         destination_b_info,
         owner_info,
-        spl_token_program,
+        token_b_program,
         token_b_transfer_amount,
     )?;
 
+    // LP amount is based on amounts which pool actually received, so transfer fees are paid by depositor.
+    // Rounding remainder of received amounts stays in pool
+    let (_, _, lp_mint_amount) = calculate_deposit_amounts(
+        swap_pool_state.balance_a,
+        swap_pool_state.balance_b,
+        lp_mint_state.supply,
+        received_a_amount,
+        received_b_amount).ok_or(HodorError::MathOverflow)?;
+
+    if lp_mint_amount == 0 {
+        return Err(HodorError::ZeroOutputAmount.into());
+    }
+
     let mint_instruction = spl_token::instruction::mint_to(
         spl_token_program.key,
         &swap_pool_state.lp_mint,
@@ -243,17 +392,58 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], min_a: u64, ma
             destination_lp_info.clone(),
             swap_pool_state_info.clone(),
         ],
-        &[&[&swap_pool_state.seed]],
+        &[swap_pool_state.signer_seeds().as_slice()],
     )?;
 
+    if lp_mint_state.supply == 0 {
+        let (locked_lp_account, _) = SwapPool::find_locked_lp_address(swap_pool_state_info.key, program_id);
+        if locked_lp_account != *locked_lp_info.key {
+            return Err(InvalidAccountData);
+        }
+
+        let mint_locked_instruction = spl_token::instruction::mint_to(
+            spl_token_program.key,
+            &swap_pool_state.lp_mint,
+            locked_lp_info.key,
+            swap_pool_state_info.key,
+            &[],
+            MINIMUM_LIQUIDITY,
+        )?;
+
+        invoke_signed(
+            &mint_locked_instruction,
+            &[
+                spl_token_program.clone(),
+                lp_mint_info.clone(),
+                locked_lp_info.clone(),
+                swap_pool_state_info.clone(),
+            ],
+            &[swap_pool_state.signer_seeds().as_slice()],
+        )?;
+    }
 
+    // Pool state account is passed to invoked token program, so it's borrowed mutably only after invokes
+    drop(state_data);
+    let mut state_data = swap_pool_state_info.try_borrow_mut_data()?;
+    let swap_pool_state = SwapPool::load_active_mut(&mut state_data)?;
+    update_cumulative_prices(swap_pool_state, Clock::get()?.unix_timestamp);
     swap_pool_state.balance_a = swap_pool_state.balance_a
-        .checked_add(token_a_transfer_amount)
-        .ok_or(InvalidInstructionData)?;
+        .checked_add(received_a_amount)
+        .ok_or(HodorError::MathOverflow)?;
     swap_pool_state.balance_b = swap_pool_state.balance_b
-        .checked_add(token_b_transfer_amount)
-        .ok_or(InvalidInstructionData)?;
-    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+        .checked_add(received_b_amount)
+        .ok_or(HodorError::MathOverflow)?;
+
+    SwapEvent::Deposit {
+        pool: *swap_pool_state_info.key,
+        user: *owner_info.key,
+        amount_a: received_a_amount,
+        amount_b: received_b_amount,
+        lp_amount: lp_mint_amount,
+        fees: FeeAmounts::default(),
+        balance_a: swap_pool_state.balance_a,
+        balance_b: swap_pool_state.balance_b,
+    }.emit();
 
     Ok(())
 }
@@ -267,7 +457,11 @@ fn process_swap(program_id: &Pubkey, accounts: &[AccountInfo], in_amount: u64, m
     let output_source_info = next_account_info(accounts_iter)?;
     let output_destination_info = next_account_info(accounts_iter)?;
 
-    let spl_token_program = next_account_info(accounts_iter)?;
+    let input_mint_info = next_account_info(accounts_iter)?;
+    let input_token_program = next_account_info(accounts_iter)?;
+    let output_mint_info = next_account_info(accounts_iter)?;
+    let output_token_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
 
     if !owner_info.is_signer {
         return Err(MissingRequiredSignature);
@@ -278,10 +472,15 @@ fn process_swap(program_id: &Pubkey, accounts: &[AccountInfo], in_amount: u64, m
     }
 
     if in_amount == 0 {
-        return Err(InvalidInstructionData);
+        return Err(HodorError::ZeroAmount.into());
     }
 
-    let mut swap_pool_state = SwapPool::unpack(&swap_pool_state_info.try_borrow_data()?)?;
+    check_token_program(input_mint_info, input_token_program)?;
+    check_token_program(output_mint_info, output_token_program)?;
+
+    let config = load_config(program_id, config_info)?;
+    let state_data = swap_pool_state_info.try_borrow_data()?;
+    let swap_pool_state = SwapPool::load_active(&state_data)?;
 
     // todo: this conditions need to be unit tested
     let is_a_to_b = {
@@ -296,86 +495,365 @@ fn process_swap(program_id: &Pubkey, accounts: &[AccountInfo], in_amount: u64, m
         }
     };
 
-    let (pool_balance_in_token, pool_balance_out_token) = if is_a_to_b {
-        (swap_pool_state.balance_a, swap_pool_state.balance_b)
+    let (pool_balance_in_token, pool_balance_out_token, in_token) = if is_a_to_b {
+        (swap_pool_state.balance_a, swap_pool_state.balance_b, PoolToken::A)
     } else {
-        (swap_pool_state.balance_b, swap_pool_state.balance_a)
+        (swap_pool_state.balance_b, swap_pool_state.balance_a, PoolToken::B)
     };
 
-    let (out_amount, dao_fee_amount, _lp_fee_amount, creator_fee_amount) = calculate_swap_amounts(
+    let creator_fee_rate = swap_pool_state.creator_fee_rate();
+
+    if !is_total_fee_rate_valid(config.dao_fee_rate, swap_pool_state.lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    // Swap is priced on amount received by pool, which is lower than in amount for mints with transfer fee
+    let received_in_amount = transfer_spl_token(
+        input_source_info,
+        input_mint_info,
+        input_destination_info,
+        owner_info,
+        input_token_program,
+        in_amount,
+    )?;
+
+    let (out_amount, dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_swap_amounts(
+        &swap_pool_state.curve()?.oriented(in_token),
         pool_balance_in_token,
         pool_balance_out_token,
-        in_amount,
-        50_000, // hardcoded 0.05%, todo: read from dao controlled config account
-        swap_pool_state.lp_fee_rate,
-        swap_pool_state.creator_fee.as_ref()
-            .map_or(0, |cf| cf.rate),
-    ).ok_or(InvalidInstructionData)?;
+        received_in_amount,
+        FeeRates { dao: config.dao_fee_rate, lp: swap_pool_state.lp_fee_rate, creator: creator_fee_rate },
+    ).ok_or(HodorError::MathOverflow)?;
+
+    if out_amount == 0 {
+        return Err(HodorError::ZeroOutputAmount.into());
+    }
+
+    let received_out_amount = transfer_spl_token_signed(
+        output_source_info,
+        output_mint_info,
+        output_destination_info,
+        swap_pool_state_info,
+        output_token_program,
+        out_amount,
+        &swap_pool_state.signer_seeds(),
+    )?;
+
+    if received_out_amount < min_out_amount {
+        return Err(HodorError::SlippageExceeded.into());
+    }
+
+    // Borrowed mutably only after invokes, as in deposit
+    drop(state_data);
+    let mut state_data = swap_pool_state_info.try_borrow_mut_data()?;
+    let swap_pool_state = SwapPool::load_active_mut(&mut state_data)?;
+    update_swap_balances(swap_pool_state, is_a_to_b, received_in_amount, out_amount, dao_fee_amount,
+                         creator_fee_amount)?;
+
+    SwapEvent::Swap {
+        pool: *swap_pool_state_info.key,
+        user: *owner_info.key,
+        in_token,
+        in_amount: received_in_amount,
+        out_amount,
+        fees: FeeAmounts { dao: dao_fee_amount, lp: lp_fee_amount, creator: creator_fee_amount },
+        balance_a: swap_pool_state.balance_a,
+        balance_b: swap_pool_state.balance_b,
+    }.emit();
+
+    Ok(())
+}
+
+fn process_swap_exact_out(program_id: &Pubkey, accounts: &[AccountInfo], out_amount: u64, max_in_amount: u64)
+                          -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let input_source_info = next_account_info(accounts_iter)?;
+    let input_destination_info = next_account_info(accounts_iter)?;
+    let output_source_info = next_account_info(accounts_iter)?;
+    let output_destination_info = next_account_info(accounts_iter)?;
+
+    let input_mint_info = next_account_info(accounts_iter)?;
+    let input_token_program = next_account_info(accounts_iter)?;
+    let output_mint_info = next_account_info(accounts_iter)?;
+    let output_token_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if !owner_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    if out_amount == 0 {
+        return Err(HodorError::ZeroAmount.into());
+    }
+
+    check_token_program(input_mint_info, input_token_program)?;
+    check_token_program(output_mint_info, output_token_program)?;
+
+    let config = load_config(program_id, config_info)?;
+    let mut swap_pool_state = SwapPool::unpack_active(&swap_pool_state_info.try_borrow_data()?)?;
+
+    let is_a_to_b = {
+        if *input_destination_info.key == swap_pool_state.token_account_a
+            && *output_source_info.key == swap_pool_state.token_account_b {
+            true
+        } else if *input_destination_info.key == swap_pool_state.token_account_b
+            && *output_source_info.key == swap_pool_state.token_account_a {
+            false
+        } else {
+            return Err(InvalidAccountData);
+        }
+    };
+
+    let (pool_balance_in_token, pool_balance_out_token, in_token) = if is_a_to_b {
+        (swap_pool_state.balance_a, swap_pool_state.balance_b, PoolToken::A)
+    } else {
+        (swap_pool_state.balance_b, swap_pool_state.balance_a, PoolToken::B)
+    };
+
+    if out_amount >= pool_balance_out_token {
+        return Err(HodorError::InsufficientLiquidity.into());
+    }
+
+    let creator_fee_rate = swap_pool_state.creator_fee_rate();
+
+    if !is_total_fee_rate_valid(config.dao_fee_rate, swap_pool_state.lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    let (in_amount, dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_swap_in_amount(
+        &swap_pool_state.curve()?.oriented(in_token),
+        pool_balance_in_token,
+        pool_balance_out_token,
+        out_amount,
+        FeeRates { dao: config.dao_fee_rate, lp: swap_pool_state.lp_fee_rate, creator: creator_fee_rate },
+    ).ok_or(HodorError::MathOverflow)?;
+
+    // Pool has to receive in amount, input transfer fee is paid on top of it.
+    // Out amount is what pool sends, output transfer fee is deducted from it
+    let send_amount = calculate_pre_fee_amount(input_mint_info, in_amount)?;
 
-    if out_amount < min_out_amount || out_amount == 0 {
-        // todo: add custom error message
-        return Err(InvalidInstructionData);
+    if send_amount > max_in_amount {
+        return Err(HodorError::SlippageExceeded.into());
     }
 
-    transfer_spl_token(
+    let received_in_amount = transfer_spl_token(
         input_source_info,
+        input_mint_info,
         input_destination_info,
         owner_info,
-        spl_token_program,
-        in_amount,
+        input_token_program,
+        send_amount,
     )?;
 
-    let withdraw_transfer_instruction = spl_token::instruction::transfer(
-        spl_token_program.key,
-        output_source_info.key,
-        output_destination_info.key,
-        swap_pool_state_info.key,
-        &[],
+    if received_in_amount < in_amount {
+        return Err(HodorError::SlippageExceeded.into());
+    }
+
+    transfer_spl_token_signed(
+        output_source_info,
+        output_mint_info,
+        output_destination_info,
+        swap_pool_state_info,
+        output_token_program,
         out_amount,
+        &swap_pool_state.signer_seeds(),
     )?;
-    invoke_signed(
-        &withdraw_transfer_instruction,
-        &[
-            spl_token_program.clone(),
-            output_source_info.clone(),
-            output_destination_info.clone(),
-            swap_pool_state_info.clone(),
-        ],
-        &[&[&swap_pool_state.seed]],
+
+    update_swap_balances(&mut swap_pool_state, is_a_to_b, received_in_amount, out_amount, dao_fee_amount,
+                         creator_fee_amount)?;
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    SwapEvent::Swap {
+        pool: *swap_pool_state_info.key,
+        user: *owner_info.key,
+        in_token,
+        in_amount: received_in_amount,
+        out_amount,
+        fees: FeeAmounts { dao: dao_fee_amount, lp: lp_fee_amount, creator: creator_fee_amount },
+        balance_a: swap_pool_state.balance_a,
+        balance_b: swap_pool_state.balance_b,
+    }.emit();
+
+    Ok(())
+}
+
+// Pool state, pool input and output token accounts, output mint and output token program of every route hop
+const ACCOUNTS_PER_HOP: usize = 5;
+
+fn process_swap_route(program_id: &Pubkey, accounts: &[AccountInfo], in_amount: u64, min_final_out: u64)
+                      -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner_info = next_account_info(accounts_iter)?;
+    let source_info = next_account_info(accounts_iter)?;
+    let destination_info = next_account_info(accounts_iter)?;
+
+    let input_mint_info = next_account_info(accounts_iter)?;
+    let input_token_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    let hop_accounts = accounts_iter.as_slice();
+    if hop_accounts.is_empty() || !hop_accounts.len().is_multiple_of(ACCOUNTS_PER_HOP) {
+        return Err(NotEnoughAccountKeys);
+    }
+
+    if !owner_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if in_amount == 0 {
+        return Err(HodorError::ZeroAmount.into());
+    }
+
+    check_token_program(input_mint_info, input_token_program)?;
+
+    let config = load_config(program_id, config_info)?;
+
+    let mut hop_in_amount = transfer_spl_token(
+        source_info,
+        input_mint_info,
+        &hop_accounts[1],
+        owner_info,
+        input_token_program,
+        in_amount,
     )?;
 
+    let hops: Vec<&[AccountInfo]> = hop_accounts.chunks_exact(ACCOUNTS_PER_HOP).collect();
+    for (index, hop) in hops.iter().enumerate() {
+        let (swap_pool_state_info, pool_input_info, pool_output_info) = (&hop[0], &hop[1], &hop[2]);
+        let (output_mint_info, output_token_program) = (&hop[3], &hop[4]);
+
+        if swap_pool_state_info.owner != program_id {
+            return Err(IllegalOwner);
+        }
+
+        check_token_program(output_mint_info, output_token_program)?;
+
+        let mut swap_pool_state = SwapPool::unpack_active(&swap_pool_state_info.try_borrow_data()?)?;
+
+        let is_a_to_b = {
+            if *pool_input_info.key == swap_pool_state.token_account_a
+                && *pool_output_info.key == swap_pool_state.token_account_b {
+                true
+            } else if *pool_input_info.key == swap_pool_state.token_account_b
+                && *pool_output_info.key == swap_pool_state.token_account_a {
+                false
+            } else {
+                return Err(InvalidAccountData);
+            }
+        };
+
+        let (pool_balance_in_token, pool_balance_out_token, in_token) = if is_a_to_b {
+            (swap_pool_state.balance_a, swap_pool_state.balance_b, PoolToken::A)
+        } else {
+            (swap_pool_state.balance_b, swap_pool_state.balance_a, PoolToken::B)
+        };
+
+        let creator_fee_rate = swap_pool_state.creator_fee_rate();
+
+        if !is_total_fee_rate_valid(config.dao_fee_rate, swap_pool_state.lp_fee_rate, creator_fee_rate) {
+            return Err(HodorError::InvalidFeeRate.into());
+        }
+
+        let (out_amount, dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_swap_amounts(
+            &swap_pool_state.curve()?.oriented(in_token),
+            pool_balance_in_token,
+            pool_balance_out_token,
+            hop_in_amount,
+            FeeRates { dao: config.dao_fee_rate, lp: swap_pool_state.lp_fee_rate, creator: creator_fee_rate },
+        ).ok_or(HodorError::MathOverflow)?;
+
+        if out_amount == 0 {
+            return Err(HodorError::ZeroOutputAmount.into());
+        }
+
+        // Output of intermediate hop goes directly into input token account of next pool
+        let out_destination_info = hops.get(index + 1)
+            .map_or(destination_info, |next_hop| &next_hop[1]);
+
+        let received_out_amount = transfer_spl_token_signed(
+            pool_output_info,
+            output_mint_info,
+            out_destination_info,
+            swap_pool_state_info,
+            output_token_program,
+            out_amount,
+            &swap_pool_state.signer_seeds(),
+        )?;
+
+        update_swap_balances(&mut swap_pool_state, is_a_to_b, hop_in_amount, out_amount, dao_fee_amount,
+                             creator_fee_amount)?;
+        swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+        SwapEvent::Swap {
+            pool: *swap_pool_state_info.key,
+            user: *owner_info.key,
+            in_token,
+            in_amount: hop_in_amount,
+            out_amount,
+            fees: FeeAmounts { dao: dao_fee_amount, lp: lp_fee_amount, creator: creator_fee_amount },
+            balance_a: swap_pool_state.balance_a,
+            balance_b: swap_pool_state.balance_b,
+        }.emit();
+
+        // Next hop is priced on amount received after transfer fee of intermediate token
+        hop_in_amount = received_out_amount;
+    }
+
+    if hop_in_amount < min_final_out {
+        return Err(HodorError::SlippageExceeded.into());
+    }
+
+    Ok(())
+}
+
+// In amount without DAO and creator fees is added to pool balance, LP fee stays in pool
+fn update_swap_balances(swap_pool_state: &mut SwapPool, is_a_to_b: bool, in_amount: u64, out_amount: u64,
+                        dao_fee_amount: u64, creator_fee_amount: u64) -> ProgramResult {
+    update_cumulative_prices(swap_pool_state, Clock::get()?.unix_timestamp);
+
     let pool_deposit_amount = in_amount
         .checked_sub(dao_fee_amount)
-        .ok_or(InvalidInstructionData)?
+        .ok_or(HodorError::MathOverflow)?
         .checked_sub(creator_fee_amount)
-        .ok_or(InvalidInstructionData)?;
+        .ok_or(HodorError::MathOverflow)?;
 
     if is_a_to_b {
         swap_pool_state.balance_a = swap_pool_state.balance_a
             .checked_add(pool_deposit_amount)
-            .ok_or(InvalidInstructionData)?;
+            .ok_or(HodorError::MathOverflow)?;
         swap_pool_state.balance_b = swap_pool_state.balance_b
             .checked_sub(out_amount)
-            .ok_or(InvalidInstructionData)?;
+            .ok_or(HodorError::MathOverflow)?;
+        swap_pool_state.dao_fee_balance_a = swap_pool_state.dao_fee_balance_a
+            .checked_add(dao_fee_amount)
+            .ok_or(HodorError::MathOverflow)?;
     } else {
         swap_pool_state.balance_b = swap_pool_state.balance_b
             .checked_add(pool_deposit_amount)
-            .ok_or(InvalidInstructionData)?;
+            .ok_or(HodorError::MathOverflow)?;
         swap_pool_state.balance_a = swap_pool_state.balance_a
             .checked_sub(out_amount)
-            .ok_or(InvalidInstructionData)?;
+            .ok_or(HodorError::MathOverflow)?;
+        swap_pool_state.dao_fee_balance_b = swap_pool_state.dao_fee_balance_b
+            .checked_add(dao_fee_amount)
+            .ok_or(HodorError::MathOverflow)?;
     }
 
-    if let Some(creator_fee) = &mut swap_pool_state.creator_fee {
+    if let Some(creator_fee) = swap_pool_state.creator_fee_mut() {
         if is_a_to_b {
             creator_fee.balance_a = creator_fee.balance_a.checked_add(creator_fee_amount)
-                .ok_or(InvalidInstructionData)?;
+                .ok_or(HodorError::MathOverflow)?;
         } else {
             creator_fee.balance_b = creator_fee.balance_b.checked_add(creator_fee_amount)
-                .ok_or(InvalidInstructionData)?;
+                .ok_or(HodorError::MathOverflow)?;
         }
     }
-    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
 
     Ok(())
 }
@@ -392,6 +870,10 @@ fn process_wthdraw(program_id: &Pubkey, accounts: &[AccountInfo], lp_amount: u64
     let source_lp_info = next_account_info(accounts_iter)?;
 
     let spl_token_program = next_account_info(accounts_iter)?;
+    let token_a_mint_info = next_account_info(accounts_iter)?;
+    let token_a_program = next_account_info(accounts_iter)?;
+    let token_b_mint_info = next_account_info(accounts_iter)?;
+    let token_b_program = next_account_info(accounts_iter)?;
 
     if !owner_info.is_signer {
         return Err(MissingRequiredSignature);
@@ -401,7 +883,15 @@ fn process_wthdraw(program_id: &Pubkey, accounts: &[AccountInfo], lp_amount: u64
         return Err(IllegalOwner);
     }
 
-    let mut swap_pool_state = SwapPool::unpack(&swap_pool_state_info.try_borrow_data()?)?;
+    if *spl_token_program.key != spl_token::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    check_token_program(token_a_mint_info, token_a_program)?;
+    check_token_program(token_b_mint_info, token_b_program)?;
+
+    let state_data = swap_pool_state_info.try_borrow_data()?;
+    let swap_pool_state = SwapPool::load_unlocked(&state_data)?;
     if swap_pool_state.token_account_a != *source_a_info.key
         || swap_pool_state.token_account_b != *source_b_info.key
         || swap_pool_state.lp_mint != *lp_mint_info.key {
@@ -415,11 +905,10 @@ fn process_wthdraw(program_id: &Pubkey, accounts: &[AccountInfo], lp_amount: u64
         swap_pool_state.balance_b,
         lp_mint_state.supply,
         lp_amount,
-    ).ok_or(InvalidInstructionData)?;
+    ).ok_or(HodorError::MathOverflow)?;
 
     if withdraw_a_amount < min_a || withdraw_b_amount < min_b {
-        // todo: add custom error message
-        return Err(InvalidInstructionData);
+        return Err(HodorError::SlippageExceeded.into());
     }
 
     // todo: test burning of more than provided account have
@@ -442,51 +931,1364 @@ fn process_wthdraw(program_id: &Pubkey, accounts: &[AccountInfo], lp_amount: u64
         ],
     )?;
 
-    let transfer_a_instruction = spl_token::instruction::transfer(
-        spl_token_program.key,
-        &swap_pool_state.token_account_a,
-        destination_a_info.key,
-        swap_pool_state_info.key,
-        &[],
+    transfer_spl_token_signed(
+        source_a_info,
+        token_a_mint_info,
+        destination_a_info,
+        swap_pool_state_info,
+        token_a_program,
         withdraw_a_amount,
-    )?;
-    invoke_signed(
-        &transfer_a_instruction,
-        &[
-            spl_token_program.clone(),
-            source_a_info.clone(),
-            destination_a_info.clone(),
-            swap_pool_state_info.clone(),
-        ],
-        &[&[&swap_pool_state.seed]],
+        &swap_pool_state.signer_seeds(),
     )?;
 
-    let transfer_b_instruction = spl_token::instruction::transfer(
-        spl_token_program.key,
-        &swap_pool_state.token_account_b,
-        destination_b_info.key,
-        swap_pool_state_info.key,
-        &[],
+    transfer_spl_token_signed(
+        source_b_info,
+        token_b_mint_info,
+        destination_b_info,
+        swap_pool_state_info,
+        token_b_program,
         withdraw_b_amount,
-    )?;
-    invoke_signed(
-        &transfer_b_instruction,
-        &[
-            spl_token_program.clone(),
-            source_b_info.clone(),
-            destination_b_info.clone(),
-            swap_pool_state_info.clone(),
-        ],
-        &[&[&swap_pool_state.seed]],
+        &swap_pool_state.signer_seeds(),
     )?;
 
+    // Borrowed mutably only after invokes, as in deposit
+    drop(state_data);
+    let mut state_data = swap_pool_state_info.try_borrow_mut_data()?;
+    let swap_pool_state = SwapPool::load_unlocked_mut(&mut state_data)?;
+    update_cumulative_prices(swap_pool_state, Clock::get()?.unix_timestamp);
     swap_pool_state.balance_a = swap_pool_state.balance_a
         .checked_sub(withdraw_a_amount)
-        .ok_or(InvalidInstructionData)?;
+        .ok_or(HodorError::MathOverflow)?;
     swap_pool_state.balance_b = swap_pool_state.balance_b
         .checked_sub(withdraw_b_amount)
-        .ok_or(InvalidInstructionData)?;
-    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+        .ok_or(HodorError::MathOverflow)?;
+
+    SwapEvent::Withdraw {
+        pool: *swap_pool_state_info.key,
+        user: *owner_info.key,
+        lp_amount,
+        amount_a: withdraw_a_amount,
+        amount_b: withdraw_b_amount,
+        fees: FeeAmounts::default(),
+        balance_a: swap_pool_state.balance_a,
+        balance_b: swap_pool_state.balance_b,
+    }.emit();
+
+    Ok(())
+}
+
+fn process_change_creator_withdraw_authority(program_id: &Pubkey, accounts: &[AccountInfo], new_authority: Pubkey)
+                                             -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+
+    if !authority_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    let mut swap_pool_data = swap_pool_state_info.try_borrow_mut_data()?;
+    let swap_pool_state = SwapPool::load_unlocked_mut(&mut swap_pool_data)?;
+    let creator_fee = swap_pool_state.creator_fee_mut()
+        .ok_or(HodorError::CreatorFeeNotEnabled)?;
+
+    if creator_fee.withdraw_authority != *authority_info.key {
+        return Err(HodorError::InvalidAuthority.into());
+    }
+
+    creator_fee.withdraw_authority = new_authority;
+
+    Ok(())
+}
+
+fn process_withdraw_creator_fee(program_id: &Pubkey, accounts: &[AccountInfo], amount_a: u64, amount_b: u64)
+                                -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let source_a_info = next_account_info(accounts_iter)?;
+    let destination_a_info = next_account_info(accounts_iter)?;
+    let source_b_info = next_account_info(accounts_iter)?;
+    let destination_b_info = next_account_info(accounts_iter)?;
+
+    let token_a_mint_info = next_account_info(accounts_iter)?;
+    let token_a_program = next_account_info(accounts_iter)?;
+    let token_b_mint_info = next_account_info(accounts_iter)?;
+    let token_b_program = next_account_info(accounts_iter)?;
+
+    if !authority_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    check_token_program(token_a_mint_info, token_a_program)?;
+    check_token_program(token_b_mint_info, token_b_program)?;
+
+    let mut swap_pool_state = SwapPool::unpack_unlocked(&swap_pool_state_info.try_borrow_data()?)?;
+    if swap_pool_state.token_account_a != *source_a_info.key
+        || swap_pool_state.token_account_b != *source_b_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let creator_fee = swap_pool_state.creator_fee_mut()
+        .ok_or(HodorError::CreatorFeeNotEnabled)?;
+
+    if creator_fee.withdraw_authority != *authority_info.key {
+        return Err(HodorError::InvalidAuthority.into());
+    }
+
+    creator_fee.balance_a = creator_fee.balance_a
+        .checked_sub(amount_a)
+        .ok_or(HodorError::InsufficientFeeBalance)?;
+    creator_fee.balance_b = creator_fee.balance_b
+        .checked_sub(amount_b)
+        .ok_or(HodorError::InsufficientFeeBalance)?;
+
+    if amount_a > 0 {
+        transfer_spl_token_signed(
+            source_a_info,
+            token_a_mint_info,
+            destination_a_info,
+            swap_pool_state_info,
+            token_a_program,
+            amount_a,
+            &swap_pool_state.signer_seeds(),
+        )?;
+    }
+
+    if amount_b > 0 {
+        transfer_spl_token_signed(
+            source_b_info,
+            token_b_mint_info,
+            destination_b_info,
+            swap_pool_state_info,
+            token_b_program,
+            amount_b,
+            &swap_pool_state.signer_seeds(),
+        )?;
+    }
+
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
+
+fn process_collect_dao_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let source_a_info = next_account_info(accounts_iter)?;
+    let vault_a_info = next_account_info(accounts_iter)?;
+    let source_b_info = next_account_info(accounts_iter)?;
+    let vault_b_info = next_account_info(accounts_iter)?;
+
+    let token_a_mint_info = next_account_info(accounts_iter)?;
+    let token_a_program = next_account_info(accounts_iter)?;
+    let token_b_mint_info = next_account_info(accounts_iter)?;
+    let token_b_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    check_token_program(token_a_mint_info, token_a_program)?;
+    check_token_program(token_b_mint_info, token_b_program)?;
+
+    let config = load_config(program_id, config_info)?;
+    let mut swap_pool_state = SwapPool::unpack_unlocked(&swap_pool_state_info.try_borrow_data()?)?;
+    if swap_pool_state.token_account_a != *source_a_info.key
+        || swap_pool_state.token_account_b != *source_b_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    if unpack_token_account(vault_a_info)?.owner != config.dao_fee_vault_owner
+        || unpack_token_account(vault_b_info)?.owner != config.dao_fee_vault_owner {
+        return Err(HodorError::InvalidDaoFeeVault.into());
+    }
+
+    let amount_a = swap_pool_state.dao_fee_balance_a;
+    let amount_b = swap_pool_state.dao_fee_balance_b;
+
+    if amount_a > 0 {
+        transfer_spl_token_signed(
+            source_a_info,
+            token_a_mint_info,
+            vault_a_info,
+            swap_pool_state_info,
+            token_a_program,
+            amount_a,
+            &swap_pool_state.signer_seeds(),
+        )?;
+    }
+
+    if amount_b > 0 {
+        transfer_spl_token_signed(
+            source_b_info,
+            token_b_mint_info,
+            vault_b_info,
+            swap_pool_state_info,
+            token_b_program,
+            amount_b,
+            &swap_pool_state.signer_seeds(),
+        )?;
+    }
+
+    swap_pool_state.dao_fee_balance_a = 0;
+    swap_pool_state.dao_fee_balance_b = 0;
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
+
+fn process_deposit_single(program_id: &Pubkey, accounts: &[AccountInfo], token: PoolToken, amount_in: u64,
+                          min_lp_out: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let source_info = next_account_info(accounts_iter)?;
+    let destination_info = next_account_info(accounts_iter)?;
+    let lp_mint_info = next_account_info(accounts_iter)?;
+    let destination_lp_info = next_account_info(accounts_iter)?;
+
+    let spl_token_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+    let token_mint_info = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !owner_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    if *spl_token_program.key != spl_token::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    if amount_in == 0 {
+        return Err(HodorError::ZeroAmount.into());
+    }
+
+    check_token_program(token_mint_info, token_program)?;
+
+    let config = load_config(program_id, config_info)?;
+    let mut swap_pool_state = SwapPool::unpack_active(&swap_pool_state_info.try_borrow_data()?)?;
+
+    let (pool_token_account, pool_in_amount, pool_other_amount) = match token {
+        PoolToken::A => (swap_pool_state.token_account_a, swap_pool_state.balance_a, swap_pool_state.balance_b),
+        PoolToken::B => (swap_pool_state.token_account_b, swap_pool_state.balance_b, swap_pool_state.balance_a),
+    };
+
+    if pool_token_account != *destination_info.key || swap_pool_state.lp_mint != *lp_mint_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let creator_fee_rate = swap_pool_state.creator_fee_rate();
+    if !is_total_fee_rate_valid(config.dao_fee_rate, swap_pool_state.lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    let lp_mint_state = Mint::unpack(&lp_mint_info.try_borrow_data()?)?;
+    if lp_mint_state.supply == 0 {
+        return Err(HodorError::EmptyPool.into());
+    }
+
+    let received_amount = transfer_spl_token(
+        source_info,
+        token_mint_info,
+        destination_info,
+        owner_info,
+        token_program,
+        amount_in,
+    )?;
+
+    let (lp_mint_amount, dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_deposit_single_amounts(
+        &swap_pool_state.curve()?.oriented(token),
+        pool_in_amount,
+        pool_other_amount,
+        lp_mint_state.supply,
+        received_amount,
+        FeeRates { dao: config.dao_fee_rate, lp: swap_pool_state.lp_fee_rate, creator: creator_fee_rate },
+    ).ok_or(HodorError::MathOverflow)?;
+
+    if lp_mint_amount == 0 {
+        return Err(HodorError::ZeroOutputAmount.into());
+    }
+
+    if lp_mint_amount < min_lp_out {
+        return Err(HodorError::SlippageExceeded.into());
+    }
+
+    let mint_instruction = spl_token::instruction::mint_to(
+        spl_token_program.key,
+        &swap_pool_state.lp_mint,
+        destination_lp_info.key,
+        swap_pool_state_info.key,
+        &[],
+        lp_mint_amount,
+    )?;
+
+    invoke_signed(
+        &mint_instruction,
+        &[
+            spl_token_program.clone(),
+            lp_mint_info.clone(),
+            destination_lp_info.clone(),
+            swap_pool_state_info.clone(),
+        ],
+        &[swap_pool_state.signer_seeds().as_slice()],
+    )?;
+
+    update_cumulative_prices(&mut swap_pool_state, Clock::get()?.unix_timestamp);
+
+    let pool_deposit_amount = received_amount
+        .checked_sub(dao_fee_amount)
+        .ok_or(HodorError::MathOverflow)?
+        .checked_sub(creator_fee_amount)
+        .ok_or(HodorError::MathOverflow)?;
+
+    match token {
+        PoolToken::A => {
+            swap_pool_state.balance_a = swap_pool_state.balance_a
+                .checked_add(pool_deposit_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.dao_fee_balance_a = swap_pool_state.dao_fee_balance_a
+                .checked_add(dao_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+        }
+        PoolToken::B => {
+            swap_pool_state.balance_b = swap_pool_state.balance_b
+                .checked_add(pool_deposit_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.dao_fee_balance_b = swap_pool_state.dao_fee_balance_b
+                .checked_add(dao_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+        }
+    }
+
+    if let Some(creator_fee) = swap_pool_state.creator_fee_mut() {
+        match token {
+            PoolToken::A => {
+                creator_fee.balance_a = creator_fee.balance_a.checked_add(creator_fee_amount)
+                    .ok_or(HodorError::MathOverflow)?;
+            }
+            PoolToken::B => {
+                creator_fee.balance_b = creator_fee.balance_b.checked_add(creator_fee_amount)
+                    .ok_or(HodorError::MathOverflow)?;
+            }
+        }
+    }
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    let (amount_a, amount_b) = match token {
+        PoolToken::A => (received_amount, 0),
+        PoolToken::B => (0, received_amount),
+    };
+
+    SwapEvent::Deposit {
+        pool: *swap_pool_state_info.key,
+        user: *owner_info.key,
+        amount_a,
+        amount_b,
+        lp_amount: lp_mint_amount,
+        fees: FeeAmounts { dao: dao_fee_amount, lp: lp_fee_amount, creator: creator_fee_amount },
+        balance_a: swap_pool_state.balance_a,
+        balance_b: swap_pool_state.balance_b,
+    }.emit();
+
+    Ok(())
+}
+
+fn process_withdraw_single(program_id: &Pubkey, accounts: &[AccountInfo], lp_amount: u64, token: PoolToken,
+                           min_out: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let source_info = next_account_info(accounts_iter)?;
+    let destination_info = next_account_info(accounts_iter)?;
+    let lp_mint_info = next_account_info(accounts_iter)?;
+    let source_lp_info = next_account_info(accounts_iter)?;
+
+    let spl_token_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+    let token_mint_info = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !owner_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    if *spl_token_program.key != spl_token::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    if lp_amount == 0 {
+        return Err(HodorError::ZeroAmount.into());
+    }
+
+    check_token_program(token_mint_info, token_program)?;
+
+    let config = load_config(program_id, config_info)?;
+    let mut swap_pool_state = SwapPool::unpack_active(&swap_pool_state_info.try_borrow_data()?)?;
+
+    let (pool_token_account, pool_out_amount, pool_other_amount) = match token {
+        PoolToken::A => (swap_pool_state.token_account_a, swap_pool_state.balance_a, swap_pool_state.balance_b),
+        PoolToken::B => (swap_pool_state.token_account_b, swap_pool_state.balance_b, swap_pool_state.balance_a),
+    };
+
+    if pool_token_account != *source_info.key || swap_pool_state.lp_mint != *lp_mint_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let creator_fee_rate = swap_pool_state.creator_fee_rate();
+    if !is_total_fee_rate_valid(config.dao_fee_rate, swap_pool_state.lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    let lp_mint_state = Mint::unpack(&lp_mint_info.try_borrow_data()?)?;
+    if lp_amount >= lp_mint_state.supply {
+        return Err(HodorError::InsufficientLiquidity.into());
+    }
+
+    let (out_amount, dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_withdraw_single_amounts(
+        &swap_pool_state.curve()?.oriented(token),
+        pool_out_amount,
+        pool_other_amount,
+        lp_mint_state.supply,
+        lp_amount,
+        FeeRates { dao: config.dao_fee_rate, lp: swap_pool_state.lp_fee_rate, creator: creator_fee_rate },
+    ).ok_or(HodorError::MathOverflow)?;
+
+    if out_amount == 0 {
+        return Err(HodorError::ZeroOutputAmount.into());
+    }
+
+    if out_amount < min_out {
+        return Err(HodorError::SlippageExceeded.into());
+    }
+
+    let burn_instruction = spl_token::instruction::burn(
+        spl_token_program.key,
+        source_lp_info.key,
+        &swap_pool_state.lp_mint,
+        owner_info.key,
+        &[owner_info.key],
+        lp_amount,
+    )?;
+
+    invoke(
+        &burn_instruction,
+        &[
+            spl_token_program.clone(),
+            source_lp_info.clone(),
+            lp_mint_info.clone(),
+            owner_info.clone(),
+        ],
+    )?;
+
+    transfer_spl_token_signed(
+        source_info,
+        token_mint_info,
+        destination_info,
+        swap_pool_state_info,
+        token_program,
+        out_amount,
+        &swap_pool_state.signer_seeds(),
+    )?;
+
+    // Other token share stays in pool, except of dao & creator fees charged on implicit swap
+    let other_fees_amount = dao_fee_amount
+        .checked_add(creator_fee_amount)
+        .ok_or(HodorError::MathOverflow)?;
+
+    update_cumulative_prices(&mut swap_pool_state, Clock::get()?.unix_timestamp);
+
+    match token {
+        PoolToken::A => {
+            swap_pool_state.balance_a = swap_pool_state.balance_a
+                .checked_sub(out_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.balance_b = swap_pool_state.balance_b
+                .checked_sub(other_fees_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.dao_fee_balance_b = swap_pool_state.dao_fee_balance_b
+                .checked_add(dao_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+        }
+        PoolToken::B => {
+            swap_pool_state.balance_b = swap_pool_state.balance_b
+                .checked_sub(out_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.balance_a = swap_pool_state.balance_a
+                .checked_sub(other_fees_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.dao_fee_balance_a = swap_pool_state.dao_fee_balance_a
+                .checked_add(dao_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+        }
+    }
+
+    if let Some(creator_fee) = swap_pool_state.creator_fee_mut() {
+        match token {
+            PoolToken::A => {
+                creator_fee.balance_b = creator_fee.balance_b.checked_add(creator_fee_amount)
+                    .ok_or(HodorError::MathOverflow)?;
+            }
+            PoolToken::B => {
+                creator_fee.balance_a = creator_fee.balance_a.checked_add(creator_fee_amount)
+                    .ok_or(HodorError::MathOverflow)?;
+            }
+        }
+    }
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    let (amount_a, amount_b) = match token {
+        PoolToken::A => (out_amount, 0),
+        PoolToken::B => (0, out_amount),
+    };
+
+    SwapEvent::Withdraw {
+        pool: *swap_pool_state_info.key,
+        user: *owner_info.key,
+        lp_amount,
+        amount_a,
+        amount_b,
+        fees: FeeAmounts { dao: dao_fee_amount, lp: lp_fee_amount, creator: creator_fee_amount },
+        balance_a: swap_pool_state.balance_a,
+        balance_b: swap_pool_state.balance_b,
+    }.emit();
+
+    Ok(())
+}
+
+fn process_flash_loan(program_id: &Pubkey, accounts: &[AccountInfo], token: PoolToken, amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let pool_token_info = next_account_info(accounts_iter)?;
+    let destination_info = next_account_info(accounts_iter)?;
+
+    let token_mint_info = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+    let receiver_program_info = next_account_info(accounts_iter)?;
+    let receiver_accounts = accounts_iter.as_slice();
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    check_token_program(token_mint_info, token_program)?;
+
+    if receiver_program_info.key == program_id {
+        return Err(IncorrectProgramId);
+    }
+
+    if amount == 0 {
+        return Err(HodorError::ZeroAmount.into());
+    }
+
+    let config = load_config(program_id, config_info)?;
+    let mut swap_pool_state = SwapPool::unpack_active(&swap_pool_state_info.try_borrow_data()?)?;
+
+    let (pool_token_account, pool_balance) = match token {
+        PoolToken::A => (swap_pool_state.token_account_a, swap_pool_state.balance_a),
+        PoolToken::B => (swap_pool_state.token_account_b, swap_pool_state.balance_b),
+    };
+
+    if pool_token_account != *pool_token_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    // Only LP reserves can be borrowed, accrued fees stay in pool token account
+    if amount > pool_balance {
+        return Err(HodorError::InsufficientLiquidity.into());
+    }
+
+    let creator_fee_rate = swap_pool_state.creator_fee_rate();
+
+    if !is_total_fee_rate_valid(config.dao_fee_rate, swap_pool_state.lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    let (dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_flash_loan_fees(
+        amount,
+        FeeRates { dao: config.dao_fee_rate, lp: swap_pool_state.lp_fee_rate, creator: creator_fee_rate },
+    ).ok_or(HodorError::MathOverflow)?;
+
+    let fee_amount = dao_fee_amount
+        .checked_add(lp_fee_amount)
+        .and_then(|fee| fee.checked_add(creator_fee_amount))
+        .ok_or(HodorError::MathOverflow)?;
+
+    let required_balance = unpack_token_account(pool_token_info)?.amount
+        .checked_add(fee_amount)
+        .ok_or(HodorError::MathOverflow)?;
+
+    // Pool is locked before control is passed to receiver program, so it can't be reentered until repaid
+    swap_pool_state.set_locked(true);
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    transfer_spl_token_signed(
+        pool_token_info,
+        token_mint_info,
+        destination_info,
+        swap_pool_state_info,
+        token_program,
+        amount,
+        &swap_pool_state.signer_seeds(),
+    )?;
+
+    let mut receiver_data = Vec::with_capacity(16);
+    receiver_data.extend_from_slice(&amount.to_le_bytes());
+    receiver_data.extend_from_slice(&fee_amount.to_le_bytes());
+
+    let receiver_instruction = Instruction {
+        program_id: *receiver_program_info.key,
+        accounts: receiver_accounts.iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: receiver_data,
+    };
+
+    let mut receiver_account_infos = receiver_accounts.to_vec();
+    receiver_account_infos.push(receiver_program_info.clone());
+    invoke(&receiver_instruction, &receiver_account_infos)?;
+
+    // Repayment is checked on pool balance, so transfer fee of repayment is paid by borrower
+    if unpack_token_account(pool_token_info)?.amount < required_balance {
+        return Err(HodorError::FlashLoanNotRepaid.into());
+    }
+
+    update_cumulative_prices(&mut swap_pool_state, Clock::get()?.unix_timestamp);
+
+    match token {
+        PoolToken::A => {
+            swap_pool_state.balance_a = swap_pool_state.balance_a
+                .checked_add(lp_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.dao_fee_balance_a = swap_pool_state.dao_fee_balance_a
+                .checked_add(dao_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+        }
+        PoolToken::B => {
+            swap_pool_state.balance_b = swap_pool_state.balance_b
+                .checked_add(lp_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+            swap_pool_state.dao_fee_balance_b = swap_pool_state.dao_fee_balance_b
+                .checked_add(dao_fee_amount)
+                .ok_or(HodorError::MathOverflow)?;
+        }
+    }
+
+    if let Some(creator_fee) = swap_pool_state.creator_fee_mut() {
+        match token {
+            PoolToken::A => {
+                creator_fee.balance_a = creator_fee.balance_a.checked_add(creator_fee_amount)
+                    .ok_or(HodorError::MathOverflow)?;
+            }
+            PoolToken::B => {
+                creator_fee.balance_b = creator_fee.balance_b.checked_add(creator_fee_amount)
+                    .ok_or(HodorError::MathOverflow)?;
+            }
+        }
+    }
+
+    swap_pool_state.set_locked(false);
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    SwapEvent::FlashLoan {
+        pool: *swap_pool_state_info.key,
+        receiver: *receiver_program_info.key,
+        token,
+        amount,
+        fees: FeeAmounts { dao: dao_fee_amount, lp: lp_fee_amount, creator: creator_fee_amount },
+        balance_a: swap_pool_state.balance_a,
+        balance_b: swap_pool_state.balance_b,
+    }.emit();
+
+    Ok(())
+}
+
+fn process_sync(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let pool_token_a_info = next_account_info(accounts_iter)?;
+    let pool_token_b_info = next_account_info(accounts_iter)?;
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    let mut swap_pool_state = SwapPool::unpack_active(&swap_pool_state_info.try_borrow_data()?)?;
+    if swap_pool_state.token_account_a != *pool_token_a_info.key
+        || swap_pool_state.token_account_b != *pool_token_b_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let (surplus_a, surplus_b) = calculate_surplus_amounts(&swap_pool_state, pool_token_a_info, pool_token_b_info)?;
+
+    update_cumulative_prices(&mut swap_pool_state, Clock::get()?.unix_timestamp);
+
+    swap_pool_state.balance_a = swap_pool_state.balance_a
+        .checked_add(surplus_a)
+        .ok_or(HodorError::MathOverflow)?;
+    swap_pool_state.balance_b = swap_pool_state.balance_b
+        .checked_add(surplus_b)
+        .ok_or(HodorError::MathOverflow)?;
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
+
+fn process_skim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let pool_token_a_info = next_account_info(accounts_iter)?;
+    let destination_a_info = next_account_info(accounts_iter)?;
+    let pool_token_b_info = next_account_info(accounts_iter)?;
+    let destination_b_info = next_account_info(accounts_iter)?;
+
+    let token_a_mint_info = next_account_info(accounts_iter)?;
+    let token_a_program = next_account_info(accounts_iter)?;
+    let token_b_mint_info = next_account_info(accounts_iter)?;
+    let token_b_program = next_account_info(accounts_iter)?;
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    check_token_program(token_a_mint_info, token_a_program)?;
+    check_token_program(token_b_mint_info, token_b_program)?;
+
+    let swap_pool_state = SwapPool::unpack_unlocked(&swap_pool_state_info.try_borrow_data()?)?;
+    if swap_pool_state.token_account_a != *pool_token_a_info.key
+        || swap_pool_state.token_account_b != *pool_token_b_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let (surplus_a, surplus_b) = calculate_surplus_amounts(&swap_pool_state, pool_token_a_info, pool_token_b_info)?;
+
+    if surplus_a > 0 {
+        transfer_spl_token_signed(
+            pool_token_a_info,
+            token_a_mint_info,
+            destination_a_info,
+            swap_pool_state_info,
+            token_a_program,
+            surplus_a,
+            &swap_pool_state.signer_seeds(),
+        )?;
+    }
+
+    if surplus_b > 0 {
+        transfer_spl_token_signed(
+            pool_token_b_info,
+            token_b_mint_info,
+            destination_b_info,
+            swap_pool_state_info,
+            token_b_program,
+            surplus_b,
+            &swap_pool_state.signer_seeds(),
+        )?;
+    }
+
+    Ok(())
+}
+
+// Rewrites pool state of older layout into current one, creating locked LP account if missing.
+// Legacy pools didn't track DAO fees, tokens held above reserves are accrued fees and become DAO fee balances.
+// It's done in the same instruction as layout change, Skim rejects outdated pools so fees can't be skimmed before
+fn process_migrate_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let fee_payer_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let pool_token_a_info = next_account_info(accounts_iter)?;
+    let pool_token_b_info = next_account_info(accounts_iter)?;
+    let lp_mint_info = next_account_info(accounts_iter)?;
+    let locked_lp_info = next_account_info(accounts_iter)?;
+    let spl_token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !fee_payer_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    if *spl_token_program.key != spl_token::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    let mut swap_pool_state = match SwapPool::unpack(&swap_pool_state_info.try_borrow_data()?) {
+        Ok(_) => {
+            msg!("Pool is already in current layout");
+            return Ok(());
+        }
+        Err(error) if error == HodorError::OutdatedPoolVersion.into() => {
+            SwapPool::unpack_outdated(&swap_pool_state_info.try_borrow_data()?)?
+        }
+        Err(error) => return Err(error),
+    };
+
+    if swap_pool_state.lp_mint != *lp_mint_info.key
+        || swap_pool_state.token_account_a != *pool_token_a_info.key
+        || swap_pool_state.token_account_b != *pool_token_b_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let (untracked_a, untracked_b) = calculate_surplus_amounts(&swap_pool_state, pool_token_a_info,
+                                                               pool_token_b_info)?;
+    swap_pool_state.dao_fee_balance_a = swap_pool_state.dao_fee_balance_a.checked_add(untracked_a)
+        .ok_or(HodorError::MathOverflow)?;
+    swap_pool_state.dao_fee_balance_b = swap_pool_state.dao_fee_balance_b.checked_add(untracked_b)
+        .ok_or(HodorError::MathOverflow)?;
+
+    // Legacy pools have no oracle, it starts accumulating from migration as if pool was just created
+    if swap_pool_state.last_update_timestamp == 0 {
+        swap_pool_state.last_update_timestamp = Clock::get()?.unix_timestamp;
+    }
+
+    let (locked_lp_account, _) = SwapPool::find_locked_lp_address(swap_pool_state_info.key, program_id);
+    if locked_lp_account != *locked_lp_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    if locked_lp_info.data_is_empty() {
+        let seeds_locked_lp = [swap_pool_state_info.key.as_ref(), SwapPool::LOCKED_LP_SEED];
+        create_spl_token_account(
+            locked_lp_info,
+            lp_mint_info,
+            swap_pool_state_info,
+            fee_payer_info,
+            &seeds_locked_lp,
+            program_id,
+            spl_token_program,
+            system_program,
+        )?;
+    }
+
+    let state_size = SwapPool::LEN;
+    let rent_shortage = Rent::get()?.minimum_balance(state_size)
+        .saturating_sub(swap_pool_state_info.lamports());
+    if rent_shortage > 0 {
+        let transfer_instruction = solana_program::system_instruction::transfer(
+            fee_payer_info.key,
+            swap_pool_state_info.key,
+            rent_shortage,
+        );
+
+        invoke(
+            &transfer_instruction,
+            &[
+                system_program.clone(),
+                fee_payer_info.clone(),
+                swap_pool_state_info.clone(),
+            ],
+        )?;
+    }
+
+    swap_pool_state_info.realloc(state_size, false)?;
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
+
+fn process_propose_fee_change(program_id: &Pubkey, accounts: &[AccountInfo], lp_fee_rate: u32, creator_fee_rate: u32)
+                              -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let proposal_info = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !authority_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    let swap_pool_state = SwapPool::unpack(&swap_pool_state_info.try_borrow_data()?)?;
+    let config = load_config(program_id, config_info)?;
+    check_fee_change_authority(&swap_pool_state, &config, authority_info)?;
+
+    // Creator fee can't be enabled for pool created without it, nobody could withdraw it
+    if swap_pool_state.creator_fee().is_none() && creator_fee_rate != 0 {
+        return Err(HodorError::CreatorFeeNotEnabled.into());
+    }
+
+    if swap_pool_state.canonical_bump().is_some() && lp_fee_rate != swap_pool_state.lp_fee_rate {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    if !config.is_pool_fee_valid(lp_fee_rate, creator_fee_rate)
+        || !is_total_fee_rate_valid(config.dao_fee_rate, lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    let (proposal_account, bump_seed) = FeeChangeProposal::find_address(swap_pool_state_info.key, program_id);
+    if proposal_account != *proposal_info.key {
+        return Err(InvalidSeeds);
+    }
+
+    if proposal_info.data_is_empty() {
+        create_pda_account(
+            proposal_info,
+            FeeChangeProposal::SIZE,
+            program_id,
+            authority_info,
+            system_program,
+            &[swap_pool_state_info.key.as_ref(), FeeChangeProposal::SEED, &[bump_seed]],
+        )?;
+    } else if proposal_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    FeeChangeProposal {
+        swap_pool: *swap_pool_state_info.key,
+        lp_fee_rate,
+        creator_fee_rate,
+        apply_after: Clock::get()?.unix_timestamp
+            .checked_add(config.fee_change_delay)
+            .ok_or(HodorError::MathOverflow)?,
+    }.pack(&mut proposal_info.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
+
+fn process_apply_fee_change(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let proposal_info = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if !authority_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id || proposal_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    let mut swap_pool_state = SwapPool::unpack_unlocked(&swap_pool_state_info.try_borrow_data()?)?;
+    let proposal = FeeChangeProposal::unpack(&proposal_info.try_borrow_data()?)?;
+    if proposal.swap_pool != *swap_pool_state_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    if Clock::get()?.unix_timestamp < proposal.apply_after {
+        return Err(HodorError::FeeChangeNotReady.into());
+    }
+
+    // Bounds could have been lowered by DAO while proposal was pending
+    let config = load_config(program_id, config_info)?;
+    if !config.is_pool_fee_valid(proposal.lp_fee_rate, proposal.creator_fee_rate)
+        || !is_total_fee_rate_valid(config.dao_fee_rate, proposal.lp_fee_rate, proposal.creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    check_fee_change_authority(&swap_pool_state, &config, authority_info)?;
+
+    match swap_pool_state.creator_fee_mut() {
+        Some(creator_fee) => creator_fee.rate = proposal.creator_fee_rate,
+        None if proposal.creator_fee_rate != 0 => return Err(HodorError::CreatorFeeNotEnabled.into()),
+        None => {}
+    }
+    swap_pool_state.lp_fee_rate = proposal.lp_fee_rate;
+    swap_pool_state.pack(&mut swap_pool_state_info.try_borrow_mut_data()?)?;
+
+    // Closing proposal account, its rent goes back to authority
+    let proposal_lamports = proposal_info.lamports();
+    **authority_info.try_borrow_mut_lamports()? = authority_info.lamports()
+        .checked_add(proposal_lamports)
+        .ok_or(HodorError::MathOverflow)?;
+    **proposal_info.try_borrow_mut_lamports()? = 0;
+    proposal_info.try_borrow_mut_data()?.fill(0);
+
+    Ok(())
+}
+
+fn process_set_paused(program_id: &Pubkey, accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let guardian_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if !guardian_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    let config = load_config(program_id, config_info)?;
+    if config.guardian != *guardian_info.key {
+        return Err(HodorError::InvalidAuthority.into());
+    }
+
+    SwapPool::load_unlocked_mut(&mut swap_pool_state_info.try_borrow_mut_data()?)?.set_paused(paused);
+
+    SwapEvent::PauseChanged {
+        pool: *swap_pool_state_info.key,
+        guardian: *guardian_info.key,
+        paused,
+    }.emit();
+
+    Ok(())
+}
+
+fn process_quote(program_id: &Pubkey, accounts: &[AccountInfo], in_token: PoolToken, in_amount: u64)
+                 -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    if in_amount == 0 {
+        return Err(HodorError::ZeroAmount.into());
+    }
+
+    let config = load_config(program_id, config_info)?;
+    let swap_pool_data = swap_pool_state_info.try_borrow_data()?;
+    let swap_pool_state = SwapPool::load_active(&swap_pool_data)?;
+
+    let (pool_balance_in_token, pool_balance_out_token) = match in_token {
+        PoolToken::A => (swap_pool_state.balance_a, swap_pool_state.balance_b),
+        PoolToken::B => (swap_pool_state.balance_b, swap_pool_state.balance_a),
+    };
+
+    let creator_fee_rate = swap_pool_state.creator_fee_rate();
+
+    if !is_total_fee_rate_valid(config.dao_fee_rate, swap_pool_state.lp_fee_rate, creator_fee_rate) {
+        return Err(HodorError::InvalidFeeRate.into());
+    }
+
+    let (out_amount, dao_fee_amount, lp_fee_amount, creator_fee_amount) = calculate_swap_amounts(
+        &swap_pool_state.curve()?.oriented(in_token),
+        pool_balance_in_token,
+        pool_balance_out_token,
+        in_amount,
+        FeeRates { dao: config.dao_fee_rate, lp: swap_pool_state.lp_fee_rate, creator: creator_fee_rate },
+    ).ok_or(HodorError::MathOverflow)?;
+
+    if out_amount == 0 {
+        return Err(HodorError::ZeroOutputAmount.into());
+    }
+
+    let quote = SwapQuote {
+        out_amount,
+        fees: FeeAmounts { dao: dao_fee_amount, lp: lp_fee_amount, creator: creator_fee_amount },
+    };
+    set_return_data(&quote.pack());
+
+    Ok(())
+}
+
+fn process_quote_deposit(program_id: &Pubkey, accounts: &[AccountInfo], max_a: u64, max_b: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let lp_mint_info = next_account_info(accounts_iter)?;
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    let swap_pool_data = swap_pool_state_info.try_borrow_data()?;
+    let swap_pool_state = SwapPool::load_active(&swap_pool_data)?;
+    if swap_pool_state.lp_mint != *lp_mint_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let lp_mint_state = Mint::unpack(&lp_mint_info.try_borrow_data()?)?;
+
+    let (amount_a, amount_b, _) = calculate_deposit_amounts(
+        swap_pool_state.balance_a,
+        swap_pool_state.balance_b,
+        lp_mint_state.supply,
+        max_a,
+        max_b).ok_or(HodorError::MathOverflow)?;
+
+    // Same as deposit, LP amount is calculated from amounts taken from depositor
+    let (_, _, lp_amount) = calculate_deposit_amounts(
+        swap_pool_state.balance_a,
+        swap_pool_state.balance_b,
+        lp_mint_state.supply,
+        amount_a,
+        amount_b).ok_or(HodorError::MathOverflow)?;
+
+    if lp_amount == 0 {
+        return Err(HodorError::ZeroOutputAmount.into());
+    }
+
+    set_return_data(&DepositQuote { amount_a, amount_b, lp_amount }.pack());
+
+    Ok(())
+}
+
+fn process_quote_withdraw(program_id: &Pubkey, accounts: &[AccountInfo], lp_amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let lp_mint_info = next_account_info(accounts_iter)?;
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    // Withdrawals keep working while pool is paused
+    let swap_pool_data = swap_pool_state_info.try_borrow_data()?;
+    let swap_pool_state = SwapPool::load_unlocked(&swap_pool_data)?;
+    if swap_pool_state.lp_mint != *lp_mint_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let lp_mint_state = Mint::unpack(&lp_mint_info.try_borrow_data()?)?;
+    if lp_amount > lp_mint_state.supply {
+        return Err(HodorError::InsufficientLiquidity.into());
+    }
+
+    let (amount_a, amount_b) = calculate_withdraw_amounts(
+        swap_pool_state.balance_a,
+        swap_pool_state.balance_b,
+        lp_mint_state.supply,
+        lp_amount,
+    ).ok_or(HodorError::MathOverflow)?;
+
+    set_return_data(&WithdrawQuote { amount_a, amount_b }.pack());
+
+    Ok(())
+}
+
+fn process_close_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let creator_info = next_account_info(accounts_iter)?;
+    let swap_pool_state_info = next_account_info(accounts_iter)?;
+    let pool_token_a_info = next_account_info(accounts_iter)?;
+    let vault_a_info = next_account_info(accounts_iter)?;
+    let pool_token_b_info = next_account_info(accounts_iter)?;
+    let vault_b_info = next_account_info(accounts_iter)?;
+    let lp_mint_info = next_account_info(accounts_iter)?;
+    let locked_lp_info = next_account_info(accounts_iter)?;
+
+    let spl_token_program = next_account_info(accounts_iter)?;
+    let token_a_mint_info = next_account_info(accounts_iter)?;
+    let token_a_program = next_account_info(accounts_iter)?;
+    let token_b_mint_info = next_account_info(accounts_iter)?;
+    let token_b_program = next_account_info(accounts_iter)?;
+    let config_info = next_account_info(accounts_iter)?;
+
+    if !creator_info.is_signer {
+        return Err(MissingRequiredSignature);
+    }
+
+    if swap_pool_state_info.owner != program_id {
+        return Err(IllegalOwner);
+    }
+
+    if *spl_token_program.key != spl_token::id() {
+        return Err(IncorrectProgramId);
+    }
+
+    check_token_program(token_a_mint_info, token_a_program)?;
+    check_token_program(token_b_mint_info, token_b_program)?;
+
+    let swap_pool_state = SwapPool::unpack_unlocked(&swap_pool_state_info.try_borrow_data()?)?;
+    if swap_pool_state.token_account_a != *pool_token_a_info.key
+        || swap_pool_state.token_account_b != *pool_token_b_info.key
+        || swap_pool_state.lp_mint != *lp_mint_info.key
+        || SwapPool::find_locked_lp_address(swap_pool_state_info.key, program_id).0 != *locked_lp_info.key {
+        return Err(InvalidAccountData);
+    }
+
+    let config = load_config(program_id, config_info)?;
+    let authority = if swap_pool_state.creator == Pubkey::default() {
+        config.admin
+    } else {
+        swap_pool_state.creator
+    };
+    if authority != *creator_info.key {
+        return Err(HodorError::InvalidAuthority.into());
+    }
+
+    if unpack_token_account(vault_a_info)?.owner != config.dao_fee_vault_owner
+        || unpack_token_account(vault_b_info)?.owner != config.dao_fee_vault_owner {
+        return Err(HodorError::InvalidDaoFeeVault.into());
+    }
+
+    let (creator_fee_a, creator_fee_b) = swap_pool_state.creator_fee()
+        .map_or((0, 0), |cf| (cf.balance_a, cf.balance_b));
+    if swap_pool_state.dao_fee_balance_a != 0 || swap_pool_state.dao_fee_balance_b != 0
+        || creator_fee_a != 0 || creator_fee_b != 0 {
+        return Err(HodorError::PoolNotEmpty.into());
+    }
+
+    // Locked minimum liquidity can't be burned by anyone, so pool is drained once it's the only LP supply left
+    let lp_supply = Mint::unpack(&lp_mint_info.try_borrow_data()?)?.supply;
+    let locked_lp_amount = unpack_token_account(locked_lp_info)?.amount;
+    if lp_supply != locked_lp_amount {
+        return Err(HodorError::PoolNotEmpty.into());
+    }
+
+    let signer_seeds = swap_pool_state.signer_seeds();
+
+    if locked_lp_amount > 0 {
+        let burn_instruction = spl_token::instruction::burn(
+            spl_token_program.key,
+            locked_lp_info.key,
+            lp_mint_info.key,
+            swap_pool_state_info.key,
+            &[],
+            locked_lp_amount,
+        )?;
+
+        invoke_signed(
+            &burn_instruction,
+            &[
+                spl_token_program.clone(),
+                locked_lp_info.clone(),
+                lp_mint_info.clone(),
+                swap_pool_state_info.clone(),
+            ],
+            &[signer_seeds.as_slice()],
+        )?;
+    }
+
+    // Reserves backing locked liquidity were never owned by creator, they go with untracked surplus to DAO vaults
+    for (pool_token_info, destination_info, mint_info, token_program) in [
+        (pool_token_a_info, vault_a_info, token_a_mint_info, token_a_program),
+        (pool_token_b_info, vault_b_info, token_b_mint_info, token_b_program),
+    ] {
+        let remaining_amount = unpack_token_account(pool_token_info)?.amount;
+        if remaining_amount > 0 {
+            transfer_spl_token_signed(
+                pool_token_info,
+                mint_info,
+                destination_info,
+                swap_pool_state_info,
+                token_program,
+                remaining_amount,
+                &signer_seeds,
+            )?;
+        }
+    }
+
+    // Token-2022 accounts with withheld transfer fees can't be closed until fees are harvested to mint
+    for (token_account_info, token_program) in [
+        (locked_lp_info, spl_token_program),
+        (pool_token_a_info, token_a_program),
+        (pool_token_b_info, token_b_program),
+    ] {
+        let close_instruction = spl_token_2022::instruction::close_account(
+            token_program.key,
+            token_account_info.key,
+            creator_info.key,
+            swap_pool_state_info.key,
+            &[],
+        )?;
+
+        invoke_signed(
+            &close_instruction,
+            &[
+                token_program.clone(),
+                token_account_info.clone(),
+                creator_info.clone(),
+                swap_pool_state_info.clone(),
+            ],
+            &[signer_seeds.as_slice()],
+        )?;
+    }
+
+    // Closing state account, its rent goes back to creator
+    let state_lamports = swap_pool_state_info.lamports();
+    **creator_info.try_borrow_mut_lamports()? = creator_info.lamports()
+        .checked_add(state_lamports)
+        .ok_or(HodorError::MathOverflow)?;
+    **swap_pool_state_info.try_borrow_mut_lamports()? = 0;
+    swap_pool_state_info.try_borrow_mut_data()?.fill(0);
+
+    Ok(())
+}
+
+// Returns amounts held by pool token accounts above tracked reserves and fees
+fn calculate_surplus_amounts(swap_pool_state: &SwapPool, pool_token_a_info: &AccountInfo,
+                             pool_token_b_info: &AccountInfo) -> Result<(u64, u64), ProgramError> {
+    let (tracked_a, tracked_b) = swap_pool_state.tracked_amounts().ok_or(HodorError::MathOverflow)?;
+    let actual_a = unpack_token_account(pool_token_a_info)?.amount;
+    let actual_b = unpack_token_account(pool_token_b_info)?.amount;
+
+    Ok((actual_a.saturating_sub(tracked_a), actual_b.saturating_sub(tracked_b)))
+}
+
+// Fees of pool with creator fee are changed by its withdraw authority. Pool without creator fee has nobody
+// representing it, its LP fee is changed by config admin
+fn check_fee_change_authority(swap_pool_state: &SwapPool, config: &HodorConfig, authority_info: &AccountInfo)
+                              -> ProgramResult {
+    let authority = match swap_pool_state.creator_fee() {
+        Some(creator_fee) => creator_fee.withdraw_authority,
+        None => config.admin,
+    };
+
+    if authority != *authority_info.key {
+        return Err(HodorError::InvalidAuthority.into());
+    }
+
+    Ok(())
+}
+
+// Mint has to be owned by provided token program, which is either SPL token or Token-2022 program.
+// Token program itself checks that mint matches transferred token accounts
+fn check_token_program(mint_info: &AccountInfo, token_program_info: &AccountInfo) -> ProgramResult {
+    if !is_token_program(token_program_info.key) {
+        return Err(IncorrectProgramId);
+    }
+
+    if mint_info.owner != token_program_info.key {
+        return Err(IllegalOwner);
+    }
+
+    Ok(())
+}
+
+// Non-transferable tokens can't be swapped and permanent delegate can drain pool token account
+fn check_mint_extensions(mint_info: &AccountInfo) -> ProgramResult {
+    if *mint_info.owner != spl_token_2022::id() {
+        return Ok(());
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let has_unsupported_extension = mint_state.get_extension_types()?.iter()
+        .any(|extension_type| matches!(extension_type,
+            ExtensionType::NonTransferable | ExtensionType::PermanentDelegate));
+
+    if has_unsupported_extension {
+        return Err(HodorError::UnsupportedMint.into());
+    }
 
     Ok(())
 }
\ No newline at end of file
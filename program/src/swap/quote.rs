@@ -0,0 +1,142 @@
+// Results of quote instructions, returned through set_return_data. Amounts are ones received by pool or sent
+// from pool, token transfer fees of Token-2022 mints are not included
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::program_error::ProgramError;
+use crate::swap::event::FeeAmounts;
+
+#[derive(Debug, PartialEq)]
+pub struct SwapQuote {
+    pub out_amount: u64,
+    pub fees: FeeAmounts,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DepositQuote {
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_amount: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WithdrawQuote {
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+impl SwapQuote {
+    pub const SIZE: usize = 8 + 8 + 8 + 8;
+
+    pub fn pack(&self) -> [u8; SwapQuote::SIZE] {
+        let mut dst = [0u8; SwapQuote::SIZE];
+        let dst_ref = array_mut_ref![dst, 0, SwapQuote::SIZE];
+        let (out_amount_dst, dao_fee_dst, lp_fee_dst, creator_fee_dst) = mut_array_refs![dst_ref, 8, 8, 8, 8];
+
+        *out_amount_dst = self.out_amount.to_le_bytes();
+        *dao_fee_dst = self.fees.dao.to_le_bytes();
+        *lp_fee_dst = self.fees.lp.to_le_bytes();
+        *creator_fee_dst = self.fees.creator.to_le_bytes();
+
+        dst
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != SwapQuote::SIZE {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let src_array_ref = array_ref![src, 0, SwapQuote::SIZE];
+        let (out_amount, dao_fee, lp_fee, creator_fee) = array_refs![src_array_ref, 8, 8, 8, 8];
+
+        Ok(SwapQuote {
+            out_amount: u64::from_le_bytes(*out_amount),
+            fees: FeeAmounts {
+                dao: u64::from_le_bytes(*dao_fee),
+                lp: u64::from_le_bytes(*lp_fee),
+                creator: u64::from_le_bytes(*creator_fee),
+            },
+        })
+    }
+}
+
+impl DepositQuote {
+    pub const SIZE: usize = 8 + 8 + 8;
+
+    pub fn pack(&self) -> [u8; DepositQuote::SIZE] {
+        let mut dst = [0u8; DepositQuote::SIZE];
+        let dst_ref = array_mut_ref![dst, 0, DepositQuote::SIZE];
+        let (amount_a_dst, amount_b_dst, lp_amount_dst) = mut_array_refs![dst_ref, 8, 8, 8];
+
+        *amount_a_dst = self.amount_a.to_le_bytes();
+        *amount_b_dst = self.amount_b.to_le_bytes();
+        *lp_amount_dst = self.lp_amount.to_le_bytes();
+
+        dst
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != DepositQuote::SIZE {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let src_array_ref = array_ref![src, 0, DepositQuote::SIZE];
+        let (amount_a, amount_b, lp_amount) = array_refs![src_array_ref, 8, 8, 8];
+
+        Ok(DepositQuote {
+            amount_a: u64::from_le_bytes(*amount_a),
+            amount_b: u64::from_le_bytes(*amount_b),
+            lp_amount: u64::from_le_bytes(*lp_amount),
+        })
+    }
+}
+
+impl WithdrawQuote {
+    pub const SIZE: usize = 8 + 8;
+
+    pub fn pack(&self) -> [u8; WithdrawQuote::SIZE] {
+        let mut dst = [0u8; WithdrawQuote::SIZE];
+        let dst_ref = array_mut_ref![dst, 0, WithdrawQuote::SIZE];
+        let (amount_a_dst, amount_b_dst) = mut_array_refs![dst_ref, 8, 8];
+
+        *amount_a_dst = self.amount_a.to_le_bytes();
+        *amount_b_dst = self.amount_b.to_le_bytes();
+
+        dst
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != WithdrawQuote::SIZE {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let src_array_ref = array_ref![src, 0, WithdrawQuote::SIZE];
+        let (amount_a, amount_b) = array_refs![src_array_ref, 8, 8];
+
+        Ok(WithdrawQuote {
+            amount_a: u64::from_le_bytes(*amount_a),
+            amount_b: u64::from_le_bytes(*amount_b),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_pack_unpack() {
+        let swap_quote = SwapQuote {
+            out_amount: 1_000,
+            fees: FeeAmounts { dao: 1, lp: 2, creator: u64::MAX },
+        };
+        assert_eq!(swap_quote, SwapQuote::unpack(&swap_quote.pack()).unwrap());
+        assert!(SwapQuote::unpack(&swap_quote.pack()[1..]).is_err());
+
+        let deposit_quote = DepositQuote { amount_a: 10, amount_b: 20, lp_amount: 15 };
+        assert_eq!(deposit_quote, DepositQuote::unpack(&deposit_quote.pack()).unwrap());
+        assert!(DepositQuote::unpack(&[0u8; DepositQuote::SIZE + 1]).is_err());
+
+        let withdraw_quote = WithdrawQuote { amount_a: u64::MAX, amount_b: 0 };
+        assert_eq!(withdraw_quote, WithdrawQuote::unpack(&withdraw_quote.pack()).unwrap());
+        assert!(WithdrawQuote::unpack(&[]).is_err());
+    }
+}
\ No newline at end of file
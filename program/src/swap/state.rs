@@ -1,8 +1,17 @@
+use std::mem::size_of;
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use bytemuck::{Pod, Zeroable};
+use solana_program::hash::hashv;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
+use crate::error::HodorError;
+use crate::swap::curve::Curve;
 
-#[derive(Debug, PartialEq)]
+// Space at the end of pool state for fields added in future layout versions
+const RESERVED_SIZE: usize = 96;
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
 pub struct CreatorFee {
     pub rate: u32,
     pub balance_a: u64,
@@ -10,8 +19,14 @@ pub struct CreatorFee {
     pub withdraw_authority: Pubkey,
 }
 
-#[derive(Debug, PartialEq)]
+// Pool state is accessed in place by casting account data, fields are packed so any alignment of account data
+// works. Layout has fixed size, creator fee is always present in it and unused tail is reserved,
+// so new fields don't require reallocation of state accounts
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 pub struct SwapPool {
+    type_marker: u8,
+    version: u8,
     pub seed: [u8; 32],
     pub token_account_a: Pubkey,
     pub token_account_b: Pubkey,
@@ -19,86 +34,352 @@ pub struct SwapPool {
     pub balance_b: u64,
     pub lp_mint: Pubkey,
     pub lp_fee_rate: u32,
-    pub creator_fee: Option<CreatorFee>,
+    pub dao_fee_balance_a: u64,
+    pub dao_fee_balance_b: u64,
+    curve: [u8; Curve::SIZE],
+    // Bump of canonical pool address with flag, unset for pools created with random seed
+    canonical_bump: [u8; 2],
+    // TWAP oracle accumulators, see swap::oracle
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+    pub last_update_timestamp: i64,
+    // Set for duration of flash loan, pool can't be used until loan is repaid
+    locked: u8,
+    // Set by guardian in emergency, pool can't be traded or deposited to, withdrawals still work
+    paused: u8,
+    // Account which created the pool and receives its rent when pool is closed,
+    // default for pools created before it was recorded
+    pub creator: Pubkey,
+    creator_fee_enabled: u8,
+    creator_fee: CreatorFee,
+    reserved: [u8; RESERVED_SIZE],
 }
 
+impl Default for SwapPool {
+    fn default() -> Self {
+        SwapPool {
+            type_marker: SwapPool::TYPE_MARKER,
+            version: SwapPool::VERSION,
+            ..Zeroable::zeroed()
+        }
+    }
+}
 
 impl SwapPool {
-    pub const BASE_SIZE: usize = 1 + 32 + 32 + 32 + 8 + 8 + 32 + 4;
-    pub const CREATOR_FEE_SIZE: usize = 4 + 8 + 8 + 32;
-    pub const WITH_CREATOR_FEE_SIZE: usize = SwapPool::BASE_SIZE + SwapPool::CREATOR_FEE_SIZE;
+    pub const LEN: usize = size_of::<SwapPool>();
     pub const TYPE_MARKER: u8 = 1;
+    // Bumped on every layout change, older layouts are upgraded by MigratePool instruction
+    pub const VERSION: u8 = 1;
 
-    pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
-        if (self.creator_fee.is_none() && dst.len() != SwapPool::BASE_SIZE) ||
-            (self.creator_fee.is_some() && dst.len() != SwapPool::WITH_CREATOR_FEE_SIZE) {
+    // Size of creator fee in legacy layout, where it was present only for pools with creator fee
+    pub const CREATOR_FEE_SIZE: usize = 4 + 8 + 8 + 32;
+
+    // Layout of pools created before layout version was introduced, without version byte
+    pub const LEGACY_BASE_SIZE: usize = 1 + 32 + 32 + 32 + 8 + 8 + 32 + 4;
+    pub const LEGACY_WITH_CREATOR_FEE_SIZE: usize = SwapPool::LEGACY_BASE_SIZE + SwapPool::CREATOR_FEE_SIZE;
+
+    pub const LOCKED_LP_SEED: &'static [u8] = b"LOCK";
+    pub const CANONICAL_SEED_PREFIX: &'static [u8] = b"pool";
+
+    // Seed of canonical pool, mints are sorted so it doesn't depend on their order
+    pub fn canonical_seed(mint_a: &Pubkey, mint_b: &Pubkey, lp_fee_rate: u32, curve_type: u8) -> [u8; 32] {
+        let (mint_low, mint_high) = if mint_a < mint_b { (mint_a, mint_b) } else { (mint_b, mint_a) };
+
+        hashv(&[
+            SwapPool::CANONICAL_SEED_PREFIX,
+            mint_low.as_ref(),
+            mint_high.as_ref(),
+            &lp_fee_rate.to_le_bytes(),
+            &[curve_type],
+        ]).to_bytes()
+    }
+
+    // Returns canonical pool state address, its bump and seed
+    pub fn find_canonical_address(mint_a: &Pubkey, mint_b: &Pubkey, lp_fee_rate: u32, curve_type: u8,
+                                  program_id: &Pubkey) -> (Pubkey, u8, [u8; 32]) {
+        let seed = SwapPool::canonical_seed(mint_a, mint_b, lp_fee_rate, curve_type);
+        let (address, bump) = Pubkey::find_program_address(&[&seed], program_id);
+
+        (address, bump, seed)
+    }
+
+    // Seeds signing for pool state PDA, canonical pools have additional bump seed
+    pub fn signer_seeds(&self) -> Vec<&[u8]> {
+        match self.canonical_bump {
+            [1, _] => vec![&self.seed, &self.canonical_bump[1..]],
+            _ => vec![&self.seed],
+        }
+    }
+
+    pub fn find_locked_lp_address(swap_pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[swap_pool.as_ref(), SwapPool::LOCKED_LP_SEED], program_id)
+    }
+
+    pub fn canonical_bump(&self) -> Option<u8> {
+        match self.canonical_bump {
+            [1, bump] => Some(bump),
+            _ => None,
+        }
+    }
+
+    pub fn set_canonical_bump(&mut self, canonical_bump: Option<u8>) {
+        self.canonical_bump = match canonical_bump {
+            Some(bump) => [1, bump],
+            None => [0, 0],
+        };
+    }
+
+    // Curve is validated when state is loaded, error is returned only for state which wasn't
+    pub fn curve(&self) -> Result<Curve, ProgramError> {
+        Curve::unpack(&self.curve)
+    }
+
+    pub fn set_curve(&mut self, curve: &Curve) {
+        curve.pack(&mut self.curve);
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked == 1
+    }
+
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked as u8;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused == 1
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused as u8;
+    }
+
+    pub fn creator_fee(&self) -> Option<&CreatorFee> {
+        if self.creator_fee_enabled == 1 {
+            Some(&self.creator_fee)
+        } else {
+            None
+        }
+    }
+
+    pub fn creator_fee_mut(&mut self) -> Option<&mut CreatorFee> {
+        if self.creator_fee_enabled == 1 {
+            Some(&mut self.creator_fee)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_creator_fee(&mut self, creator_fee: Option<CreatorFee>) {
+        self.creator_fee_enabled = creator_fee.is_some() as u8;
+        self.creator_fee = creator_fee.unwrap_or_default();
+    }
+
+    pub fn creator_fee_rate(&self) -> u32 {
+        self.creator_fee().map_or(0, |cf| cf.rate)
+    }
+
+    // Token amounts pool token accounts should hold: reserves and accrued DAO & creator fees,
+    // anything above them is untracked surplus
+    pub fn tracked_amounts(&self) -> Option<(u64, u64)> {
+        let (creator_fee_a, creator_fee_b) = self.creator_fee()
+            .map_or((0, 0), |cf| (cf.balance_a, cf.balance_b));
+
+        Some((
+            self.balance_a.checked_add(self.dao_fee_balance_a)?.checked_add(creator_fee_a)?,
+            self.balance_b.checked_add(self.dao_fee_balance_b)?.checked_add(creator_fee_b)?,
+        ))
+    }
+
+    // Returns pool state in place, older layouts have to be migrated first
+    pub fn load(src: &[u8]) -> Result<&SwapPool, ProgramError> {
+        if src.first() != Some(&SwapPool::TYPE_MARKER) {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let dst_ref = array_mut_ref![dst, 0, SwapPool::BASE_SIZE];
-        let (type_marker_dst, seed_dst, token_acc_a_dst, token_acc_b_dst, balance_a_dst, balance_b_dst, lp_mint_dst, lp_fee_rate_dst)
-            = mut_array_refs![dst_ref, 1, 32, 32, 32, 8, 8, 32, 4];
+        match src.len() {
+            SwapPool::LEN => {}
+            SwapPool::LEGACY_BASE_SIZE | SwapPool::LEGACY_WITH_CREATOR_FEE_SIZE => {
+                return Err(HodorError::OutdatedPoolVersion.into());
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
 
-        *type_marker_dst = [SwapPool::TYPE_MARKER];
-        seed_dst.copy_from_slice(self.seed.as_ref());
-        token_acc_a_dst.copy_from_slice(self.token_account_a.as_ref());
-        token_acc_b_dst.copy_from_slice(self.token_account_b.as_ref());
-        *balance_a_dst = self.balance_a.to_le_bytes();
-        *balance_b_dst = self.balance_b.to_le_bytes();
-        lp_mint_dst.copy_from_slice(self.lp_mint.as_ref());
-        *lp_fee_rate_dst = self.lp_fee_rate.to_le_bytes();
+        let swap_pool: &SwapPool = bytemuck::from_bytes(src);
+        if swap_pool.version != SwapPool::VERSION || !swap_pool.is_valid() {
+            return Err(ProgramError::InvalidAccountData);
+        }
 
-        if let Some(creator_fee) = &self.creator_fee {
-            let dst_ref = array_mut_ref![dst, SwapPool::BASE_SIZE, SwapPool::CREATOR_FEE_SIZE];
+        Ok(swap_pool)
+    }
+
+    // Loads pool which isn't locked by ongoing flash loan, every pool operation has to use it
+    pub fn load_unlocked(src: &[u8]) -> Result<&SwapPool, ProgramError> {
+        let swap_pool = SwapPool::load(src)?;
+        if swap_pool.is_locked() {
+            return Err(HodorError::PoolLocked.into());
+        }
+
+        Ok(swap_pool)
+    }
 
-            let (rate_dst, balance_a_dst, balance_b_dst, withdraw_authority_dst)
-                = mut_array_refs![dst_ref, 4, 8, 8, 32];
+    pub fn load_unlocked_mut(src: &mut [u8]) -> Result<&mut SwapPool, ProgramError> {
+        SwapPool::load_unlocked(src)?;
+        Ok(bytemuck::from_bytes_mut(src))
+    }
 
-            *rate_dst = creator_fee.rate.to_le_bytes();
-            *balance_a_dst = creator_fee.balance_a.to_le_bytes();
-            *balance_b_dst = creator_fee.balance_b.to_le_bytes();
-            withdraw_authority_dst.copy_from_slice(creator_fee.withdraw_authority.as_ref());
+    // Loads pool which can be traded and deposited to: neither locked nor paused
+    pub fn load_active(src: &[u8]) -> Result<&SwapPool, ProgramError> {
+        let swap_pool = SwapPool::load_unlocked(src)?;
+        if swap_pool.is_paused() {
+            return Err(HodorError::PoolPaused.into());
         }
 
-        Ok(())
+        Ok(swap_pool)
     }
 
+    pub fn load_active_mut(src: &mut [u8]) -> Result<&mut SwapPool, ProgramError> {
+        SwapPool::load_active(src)?;
+        Ok(bytemuck::from_bytes_mut(src))
+    }
+
+    // Copies of pool state are used by instructions invoking other programs, pool state account can't stay
+    // borrowed during invoke. Copy is written back by pack
     pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
-        // todo: size check
+        SwapPool::load(src).copied()
+    }
 
-        let src_array_ref = array_ref![src, 0, SwapPool::BASE_SIZE];
-        let (type_marker, seed, token_acc_a, token_acc_b,
-            balance_a, balance_b, lp_mint, lp_fee_rate)
-            = array_refs![src_array_ref, 1, 32, 32, 32, 8, 8, 32, 4];
+    pub fn unpack_unlocked(src: &[u8]) -> Result<Self, ProgramError> {
+        SwapPool::load_unlocked(src).copied()
+    }
 
-        if *type_marker != [SwapPool::TYPE_MARKER] {
+    pub fn unpack_active(src: &[u8]) -> Result<Self, ProgramError> {
+        SwapPool::load_active(src).copied()
+    }
+
+    pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() != SwapPool::LEN {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let creator_fee = if src.len() == SwapPool::WITH_CREATOR_FEE_SIZE {
-            let src_array_ref = array_ref![src, SwapPool::BASE_SIZE, SwapPool::CREATOR_FEE_SIZE];
-            let (rate, balance_a, balance_b, withdraw_authority)
-                = array_refs![src_array_ref, 4, 8, 8, 32];
+        dst.copy_from_slice(bytemuck::bytes_of(self));
 
-            Some(CreatorFee {
-                rate: u32::from_le_bytes(*rate),
-                balance_a: u64::from_le_bytes(*balance_a),
-                balance_b: u64::from_le_bytes(*balance_b),
-                withdraw_authority: Pubkey::new_from_array(*withdraw_authority),
-            })
-        } else {
-            None
+        Ok(())
+    }
+
+    fn is_valid(&self) -> bool {
+        self.canonical_bump[0] <= 1
+            && self.locked <= 1
+            && self.paused <= 1
+            && self.creator_fee_enabled <= 1
+            && self.curve().is_ok()
+    }
+
+    // Unpacks pool of legacy layout, to be upgraded by MigratePool instruction. Fields missing in it get values
+    // of newly created pool: no accrued DAO fees, constant product curve, empty oracle accumulators
+    pub fn unpack_outdated(src: &[u8]) -> Result<Self, ProgramError> {
+        let has_creator_fee = match src.len() {
+            SwapPool::LEGACY_BASE_SIZE => false,
+            SwapPool::LEGACY_WITH_CREATOR_FEE_SIZE => true,
+            _ => return Err(ProgramError::InvalidAccountData),
         };
 
-        Ok(SwapPool {
-            seed: *seed, // todo: should we clone ?
+        let src_array_ref = array_ref![src, 0, SwapPool::LEGACY_BASE_SIZE];
+        let (type_marker, seed, token_acc_a, token_acc_b, balance_a, balance_b, lp_mint, lp_fee_rate)
+            = array_refs![src_array_ref, 1, 32, 32, 32, 8, 8, 32, 4];
+
+        if *type_marker != [SwapPool::TYPE_MARKER] {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut swap_pool = SwapPool {
+            seed: *seed,
             token_account_a: Pubkey::new_from_array(*token_acc_a),
             token_account_b: Pubkey::new_from_array(*token_acc_b),
             balance_a: u64::from_le_bytes(*balance_a),
             balance_b: u64::from_le_bytes(*balance_b),
             lp_mint: Pubkey::new_from_array(*lp_mint),
             lp_fee_rate: u32::from_le_bytes(*lp_fee_rate),
-            creator_fee,
+            ..SwapPool::default()
+        };
+        swap_pool.set_curve(&Curve::ConstantProduct);
+
+        if has_creator_fee {
+            swap_pool.set_creator_fee(Some(
+                SwapPool::unpack_creator_fee(array_ref![src, SwapPool::LEGACY_BASE_SIZE, SwapPool::CREATOR_FEE_SIZE])
+            ));
+        }
+
+        Ok(swap_pool)
+    }
+
+    fn unpack_creator_fee(src: &[u8; SwapPool::CREATOR_FEE_SIZE]) -> CreatorFee {
+        let (rate, balance_a, balance_b, withdraw_authority) = array_refs![src, 4, 8, 8, 32];
+
+        CreatorFee {
+            rate: u32::from_le_bytes(*rate),
+            balance_a: u64::from_le_bytes(*balance_a),
+            balance_b: u64::from_le_bytes(*balance_b),
+            withdraw_authority: Pubkey::new_from_array(*withdraw_authority),
+        }
+    }
+}
+
+// Pending change of pool fee rates, PDA of pool and FEE_CHANGE_SEED. Change can be applied only after delay
+// configured by DAO, so LPs and traders can react to it
+#[derive(Debug, PartialEq)]
+pub struct FeeChangeProposal {
+    pub swap_pool: Pubkey,
+    pub lp_fee_rate: u32,
+    pub creator_fee_rate: u32,
+    pub apply_after: i64,
+}
+
+impl FeeChangeProposal {
+    pub const SIZE: usize = 1 + 32 + 4 + 4 + 8;
+    pub const TYPE_MARKER: u8 = 3;
+    pub const SEED: &'static [u8] = b"FEE";
+
+    pub fn find_address(swap_pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[swap_pool.as_ref(), FeeChangeProposal::SEED], program_id)
+    }
+
+    pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() != FeeChangeProposal::SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let dst_ref = array_mut_ref![dst, 0, FeeChangeProposal::SIZE];
+        let (type_marker_dst, swap_pool_dst, lp_fee_rate_dst, creator_fee_rate_dst, apply_after_dst)
+            = mut_array_refs![dst_ref, 1, 32, 4, 4, 8];
+
+        *type_marker_dst = [FeeChangeProposal::TYPE_MARKER];
+        swap_pool_dst.copy_from_slice(self.swap_pool.as_ref());
+        *lp_fee_rate_dst = self.lp_fee_rate.to_le_bytes();
+        *creator_fee_rate_dst = self.creator_fee_rate.to_le_bytes();
+        *apply_after_dst = self.apply_after.to_le_bytes();
+
+        Ok(())
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != FeeChangeProposal::SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src_array_ref = array_ref![src, 0, FeeChangeProposal::SIZE];
+        let (type_marker, swap_pool, lp_fee_rate, creator_fee_rate, apply_after)
+            = array_refs![src_array_ref, 1, 32, 4, 4, 8];
+
+        if *type_marker != [FeeChangeProposal::TYPE_MARKER] {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(FeeChangeProposal {
+            swap_pool: Pubkey::new_from_array(*swap_pool),
+            lp_fee_rate: u32::from_le_bytes(*lp_fee_rate),
+            creator_fee_rate: u32::from_le_bytes(*creator_fee_rate),
+            apply_after: i64::from_le_bytes(*apply_after),
         })
     }
 }
@@ -118,15 +399,23 @@ mod tests {
             balance_b: 150,
             lp_mint: Pubkey::new_unique(),
             lp_fee_rate: 5_000,
-            creator_fee: None,
+            dao_fee_balance_a: 10,
+            dao_fee_balance_b: 20,
+            creator: Pubkey::new_unique(),
+            ..SwapPool::default()
         };
-        let mut state_array = [0u8; SwapPool::BASE_SIZE];
+        let mut state_array = [0u8; SwapPool::LEN];
         pool_without_creator_fee.pack(&mut state_array).unwrap();
         assert_eq!(pool_without_creator_fee, SwapPool::unpack(&state_array).unwrap());
-        assert!(pool_without_creator_fee.pack(&mut [0u8; SwapPool::WITH_CREATOR_FEE_SIZE]).is_err());
-
+        assert_eq!(pool_without_creator_fee, SwapPool::unpack_unlocked(&state_array).unwrap());
+        assert_eq!(pool_without_creator_fee, SwapPool::unpack_active(&state_array).unwrap());
+        assert_eq!(&pool_without_creator_fee, SwapPool::load(&state_array).unwrap());
+        assert_eq!(Ok(Curve::ConstantProduct), pool_without_creator_fee.curve());
+        assert_eq!(None, pool_without_creator_fee.creator_fee());
+        assert_eq!(Some((110, 170)), pool_without_creator_fee.tracked_amounts());
+        assert!(pool_without_creator_fee.pack(&mut [0u8; SwapPool::LEN + 1]).is_err());
 
-        let pool_with_creator_fee = SwapPool {
+        let mut pool_with_creator_fee = SwapPool {
             seed: Pubkey::new_unique().to_bytes(),
             token_account_a: Pubkey::new_unique(),
             token_account_b: Pubkey::new_unique(),
@@ -134,16 +423,168 @@ mod tests {
             balance_b: 120,
             lp_mint: Pubkey::new_unique(),
             lp_fee_rate: 5_000,
-            creator_fee: Some(CreatorFee {
-                rate: 10_000,
-                balance_a: 5_000,
-                balance_b: 6_000,
-                withdraw_authority: Default::default()
-            }),
+            dao_fee_balance_a: 0,
+            dao_fee_balance_b: 7,
+            price_a_cumulative: u128::MAX - 5,
+            price_b_cumulative: 123_456_789 << 64,
+            last_update_timestamp: 1_650_000_000,
+            creator: Pubkey::new_unique(),
+            ..SwapPool::default()
         };
-        let mut state_array = [0u8; SwapPool::WITH_CREATOR_FEE_SIZE];
+        pool_with_creator_fee.set_curve(&Curve::StableSwap { amp: 100 });
+        pool_with_creator_fee.set_canonical_bump(Some(254));
+        pool_with_creator_fee.set_locked(true);
+        pool_with_creator_fee.set_paused(true);
+        pool_with_creator_fee.set_creator_fee(Some(CreatorFee {
+            rate: 10_000,
+            balance_a: 5_000,
+            balance_b: 6_000,
+            withdraw_authority: Default::default()
+        }));
+        let mut state_array = [0u8; SwapPool::LEN];
         pool_with_creator_fee.pack(&mut state_array).unwrap();
         assert_eq!(pool_with_creator_fee, SwapPool::unpack(&state_array).unwrap());
-        assert!(pool_with_creator_fee.pack(&mut [0u8; SwapPool::BASE_SIZE]).is_err());
+        assert_eq!(Ok(Curve::StableSwap { amp: 100 }), SwapPool::load(&state_array).unwrap().curve());
+        assert_eq!(Some(254), SwapPool::load(&state_array).unwrap().canonical_bump());
+        assert_eq!(Err(HodorError::PoolLocked.into()), SwapPool::unpack_unlocked(&state_array));
+        assert_eq!(Err(HodorError::PoolLocked.into()), SwapPool::unpack_active(&state_array));
+        assert_eq!(Some((5_000, 6_127)), pool_with_creator_fee.tracked_amounts());
+
+        // changes through loaded state are made in place
+        pool_with_creator_fee.set_locked(false);
+        pool_with_creator_fee.pack(&mut state_array).unwrap();
+        let loaded_pool = SwapPool::load_unlocked_mut(&mut state_array).unwrap();
+        loaded_pool.creator_fee_mut().unwrap().balance_a = 0;
+        loaded_pool.set_creator_fee(None);
+        assert_eq!(Err(HodorError::PoolPaused.into()), SwapPool::unpack_active(&state_array));
+        assert_eq!(None, SwapPool::load(&state_array).unwrap().creator_fee());
+        assert_eq!(Some((0, 127)), SwapPool::load(&state_array).unwrap().tracked_amounts());
+
+        // flags out of range are rejected
+        let locked_offset = 1 + 1 + 32 + 32 + 32 + 8 + 8 + 32 + 4 + 8 + 8 + Curve::SIZE + 2 + 16 + 16 + 8;
+        for flag_offset in [locked_offset, locked_offset + 1, locked_offset + 2 + 32] {
+            let mut invalid_state = state_array;
+            invalid_state[flag_offset] = 2;
+            assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack(&invalid_state));
+        }
+    }
+
+    #[test]
+    fn test_swap_pool_versions() {
+        let seed = Pubkey::new_unique().to_bytes();
+        let token_account_a = Pubkey::new_unique();
+        let token_account_b = Pubkey::new_unique();
+        let lp_mint = Pubkey::new_unique();
+        let withdraw_authority = Pubkey::new_unique();
+
+        let mut legacy_state = vec![SwapPool::TYPE_MARKER];
+        legacy_state.extend_from_slice(&seed);
+        legacy_state.extend_from_slice(token_account_a.as_ref());
+        legacy_state.extend_from_slice(token_account_b.as_ref());
+        legacy_state.extend_from_slice(&100u64.to_le_bytes());
+        legacy_state.extend_from_slice(&200u64.to_le_bytes());
+        legacy_state.extend_from_slice(lp_mint.as_ref());
+        legacy_state.extend_from_slice(&3_000u32.to_le_bytes());
+        assert_eq!(SwapPool::LEGACY_BASE_SIZE, legacy_state.len());
+
+        let mut legacy_state_with_creator_fee = legacy_state.clone();
+        legacy_state_with_creator_fee.extend_from_slice(&1_000u32.to_le_bytes());
+        legacy_state_with_creator_fee.extend_from_slice(&5u64.to_le_bytes());
+        legacy_state_with_creator_fee.extend_from_slice(&6u64.to_le_bytes());
+        legacy_state_with_creator_fee.extend_from_slice(withdraw_authority.as_ref());
+        assert_eq!(SwapPool::LEGACY_WITH_CREATOR_FEE_SIZE, legacy_state_with_creator_fee.len());
+
+        // legacy layout has to be migrated before use
+        assert_eq!(Err(HodorError::OutdatedPoolVersion.into()), SwapPool::unpack(&legacy_state));
+        assert_eq!(Err(HodorError::OutdatedPoolVersion.into()), SwapPool::unpack(&legacy_state_with_creator_fee));
+
+        let legacy_pool = SwapPool::unpack_outdated(&legacy_state).unwrap();
+        assert_eq!((seed, token_account_a, token_account_b, lp_mint), (legacy_pool.seed, legacy_pool.token_account_a,
+            legacy_pool.token_account_b, legacy_pool.lp_mint));
+        assert_eq!((100, 200, 3_000, None), (legacy_pool.balance_a, legacy_pool.balance_b, legacy_pool.lp_fee_rate,
+                                            legacy_pool.canonical_bump()));
+        assert_eq!(Ok(Curve::ConstantProduct), legacy_pool.curve());
+        assert_eq!(None, legacy_pool.creator_fee());
+        assert_eq!(Pubkey::default(), { legacy_pool.creator });
+
+        let legacy_pool_with_creator_fee = SwapPool::unpack_outdated(&legacy_state_with_creator_fee).unwrap();
+        assert_eq!(Some(&CreatorFee { rate: 1_000, balance_a: 5, balance_b: 6, withdraw_authority }),
+                   legacy_pool_with_creator_fee.creator_fee());
+
+        // migrated pools round trip in current layout
+        let mut pool_with_creator = SwapPool::unpack_outdated(&legacy_state).unwrap();
+        pool_with_creator.creator = Pubkey::new_unique();
+        pool_with_creator.set_curve(&Curve::StableSwap { amp: 50 });
+        pool_with_creator.set_canonical_bump(Some(253));
+        pool_with_creator.set_paused(true);
+
+        for pool in [legacy_pool, legacy_pool_with_creator_fee, pool_with_creator] {
+            let mut state = vec![0u8; SwapPool::LEN];
+            pool.pack(&mut state).unwrap();
+            assert_eq!([SwapPool::TYPE_MARKER, SwapPool::VERSION], state[..2]);
+            assert_eq!(pool, SwapPool::unpack(&state).unwrap());
+            assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack_outdated(&state));
+
+            // unknown version, trailing and missing bytes are rejected
+            let mut unknown_version_state = state.clone();
+            unknown_version_state[1] = SwapPool::VERSION + 1;
+            assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack(&unknown_version_state));
+
+            let mut longer_state = state.clone();
+            longer_state.push(0);
+            assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack(&longer_state));
+            assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack(&state[..state.len() - 1]));
+        }
+
+        assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack(&[]));
+        assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack(&[SwapPool::TYPE_MARKER]));
+        assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack_outdated(&legacy_state[..100]));
+        legacy_state[0] = 0;
+        assert_eq!(Err(ProgramError::InvalidAccountData), SwapPool::unpack_outdated(&legacy_state));
+    }
+
+    #[test]
+    fn test_fee_change_proposal_pack_unpack() {
+        let proposal = FeeChangeProposal {
+            swap_pool: Pubkey::new_unique(),
+            lp_fee_rate: 250_000,
+            creator_fee_rate: 0,
+            apply_after: 1_650_000_000,
+        };
+        let mut state_array = [0u8; FeeChangeProposal::SIZE];
+        proposal.pack(&mut state_array).unwrap();
+        assert_eq!(proposal, FeeChangeProposal::unpack(&state_array).unwrap());
+        assert!(proposal.pack(&mut [0u8; FeeChangeProposal::SIZE - 1]).is_err());
+        assert!(FeeChangeProposal::unpack(&state_array[1..]).is_err());
+
+        state_array[0] = SwapPool::TYPE_MARKER;
+        assert!(FeeChangeProposal::unpack(&state_array).is_err());
+    }
+
+    #[test]
+    fn test_canonical_address() {
+        let program_id = Pubkey::new_unique();
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+
+        let (address, bump, seed) = SwapPool::find_canonical_address(&mint_a, &mint_b, 300_000, 0, &program_id);
+        assert_eq!((address, bump, seed), SwapPool::find_canonical_address(&mint_b, &mint_a, 300_000, 0, &program_id));
+        assert_eq!(address, Pubkey::create_program_address(&[&seed, &[bump]], &program_id).unwrap());
+
+        // different fee tier or curve type is different pool
+        assert_ne!(address, SwapPool::find_canonical_address(&mint_a, &mint_b, 100_000, 0, &program_id).0);
+        assert_ne!(address, SwapPool::find_canonical_address(&mint_a, &mint_b, 300_000, 1, &program_id).0);
+
+        let mut pool = SwapPool {
+            seed,
+            token_account_a: Pubkey::new_unique(),
+            token_account_b: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            lp_fee_rate: 300_000,
+            creator: Pubkey::new_unique(),
+            ..SwapPool::default()
+        };
+        pool.set_canonical_bump(Some(bump));
+        assert_eq!(address, Pubkey::create_program_address(&pool.signer_seeds(), &program_id).unwrap());
     }
 }
\ No newline at end of file
@@ -0,0 +1,114 @@
+mod common;
+
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+use hodor_program::config::state::HodorConfig;
+use hodor_program::error::HodorError;
+use hodor_program::swap::instruction::SwapInstruction;
+use crate::common::{create_mints, create_pool_instruction, create_token_account, deposit_instruction, get_token_amount,
+                    process, refresh_blockhash, start, Pool};
+
+fn close_instruction(creator: &Pubkey, pool: &Pool, vault_a: &Pubkey, mint_a: &Pubkey, vault_b: &Pubkey,
+                     mint_b: &Pubkey) -> Instruction {
+    let program_id = hodor_program::id();
+    Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::ClosePool),
+        vec![
+            AccountMeta::new(*creator, true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(*vault_a, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(*vault_b, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(pool.locked_lp, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&program_id).0, false),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn test_close_pool() {
+    let mut context = start().await;
+    let (mint_a, mint_b) = create_mints(&mut context).await;
+    let (pool, seed) = Pool::find_canonical(&mint_a, &mint_b);
+    let payer = context.payer.pubkey();
+    let program_id = hodor_program::id();
+
+    let create_pool = create_pool_instruction(&payer, &pool, &mint_a, &mint_b, seed, 0, true);
+    process(&mut context, &[], std::slice::from_ref(&create_pool)).await.unwrap();
+
+    // payer is DAO fee vault owner in test config
+    let user_a = create_token_account(&mut context, &mint_a).await;
+    let user_b = create_token_account(&mut context, &mint_b).await;
+    let user_lp = create_token_account(&mut context, &pool.lp_mint).await;
+    let vault_a = create_token_account(&mut context, &mint_a).await;
+    let vault_b = create_token_account(&mut context, &mint_b).await;
+
+    process(&mut context, &[], &[
+        spl_token::instruction::mint_to(&spl_token::id(), &mint_a, &user_a, &payer, &[], 1_000_000).unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &mint_b, &user_b, &payer, &[], 4_000_000).unwrap(),
+        deposit_instruction(&payer, &pool, &user_a, &mint_a, &user_b, &mint_b, &user_lp, 1_000_000, 4_000_000),
+    ]).await.unwrap();
+
+    let close_pool = close_instruction(&payer, &pool, &vault_a, &mint_a, &vault_b, &mint_b);
+    assert_eq!(TransactionError::InstructionError(0, InstructionError::Custom(HodorError::PoolNotEmpty as u32)),
+               process(&mut context, &[], std::slice::from_ref(&close_pool)).await.unwrap_err().unwrap());
+
+    let lp_amount = get_token_amount(&mut context, &user_lp).await;
+    process(&mut context, &[], &[Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::Withdraw {
+            lp_amount,
+            min_a: 0,
+            min_b: 0,
+        }),
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(user_a, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(user_b, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(user_lp, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )]).await.unwrap();
+
+    // only locked minimum liquidity is left, reserves backing it can't be sent anywhere but DAO vaults
+    let remaining_a = get_token_amount(&mut context, &pool.token_a).await;
+    let remaining_b = get_token_amount(&mut context, &pool.token_b).await;
+    assert!(remaining_a > 0 && remaining_b > 0);
+
+    let not_vault_close = close_instruction(&payer, &pool, &pool.token_a, &mint_a, &vault_b, &mint_b);
+    assert_eq!(TransactionError::InstructionError(0, InstructionError::Custom(HodorError::InvalidDaoFeeVault as u32)),
+               process(&mut context, &[], &[not_vault_close]).await.unwrap_err().unwrap());
+
+    refresh_blockhash(&mut context).await;
+    process(&mut context, &[], &[close_pool]).await.unwrap();
+
+    assert_eq!(remaining_a, get_token_amount(&mut context, &vault_a).await);
+    assert_eq!(remaining_b, get_token_amount(&mut context, &vault_b).await);
+    for address in [pool.state, pool.token_a, pool.token_b, pool.locked_lp] {
+        assert_eq!(None, context.banks_client.get_account(address).await.unwrap());
+    }
+
+    // LP mint stays, so canonical pool can't be created again after close
+    assert!(context.banks_client.get_account(pool.lp_mint).await.unwrap().is_some());
+    refresh_blockhash(&mut context).await;
+    assert!(process(&mut context, &[], &[create_pool]).await.is_err());
+}
\ No newline at end of file
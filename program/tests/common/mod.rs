@@ -0,0 +1,241 @@
+// Helpers shared by integration tests running program natively in program test environment
+#![allow(dead_code)]
+
+use solana_program::bpf_loader_upgradeable;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use hodor_program::config::instruction::ConfigInstruction;
+use hodor_program::config::state::HodorConfig;
+use hodor_program::swap::curve::Curve;
+use hodor_program::swap::instruction::SwapInstruction;
+use hodor_program::swap::state::SwapPool;
+
+pub const LP_FEE_RATE: u32 = 300_000;
+pub const DAO_FEE_RATE: u32 = 50_000;
+
+pub struct Pool {
+    pub state: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp_mint: Pubkey,
+    pub locked_lp: Pubkey,
+}
+
+impl Pool {
+    pub fn find_canonical(mint_a: &Pubkey, mint_b: &Pubkey) -> (Pool, [u8; 32]) {
+        let program_id = hodor_program::id();
+        let (state, _, seed) = SwapPool::find_canonical_address(mint_a, mint_b, LP_FEE_RATE,
+                                                                 Curve::ConstantProduct.curve_type(), &program_id);
+        (Pool::find(state), seed)
+    }
+
+    // Pool created with random seed, its address is derived from seed only
+    pub fn find_random() -> (Pool, [u8; 32]) {
+        let program_id = hodor_program::id();
+        let (seed, state) = loop {
+            let seed = Pubkey::new_unique().to_bytes();
+            if let Ok(state) = Pubkey::create_program_address(&[&seed], &program_id) {
+                break (seed, state);
+            }
+        };
+
+        (Pool::find(state), seed)
+    }
+
+    fn find(state: Pubkey) -> Pool {
+        let program_id = hodor_program::id();
+        Pool {
+            state,
+            token_a: Pubkey::find_program_address(&[state.as_ref(), b"A"], &program_id).0,
+            token_b: Pubkey::find_program_address(&[state.as_ref(), b"B"], &program_id).0,
+            lp_mint: Pubkey::find_program_address(&[state.as_ref(), b"LP"], &program_id).0,
+            locked_lp: SwapPool::find_locked_lp_address(&state, &program_id).0,
+        }
+    }
+}
+
+// Starts program test with program and spl token processed natively and config initialized by payer.
+// Config can be initialized only by upgrade authority, so program data of upgradeable program is faked
+pub async fn start() -> ProgramTestContext {
+    let program_id = hodor_program::id();
+    let mut program_test = ProgramTest::new("hodor_program", program_id,
+                                            processor!(hodor_program::processor::process));
+    program_test.add_program("spl_token", spl_token::id(), processor!(spl_token::processor::Processor::process));
+
+    let mut context = program_test.start_with_context().await;
+
+    let (program_data_account, _) = Pubkey::find_program_address(
+        &[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let mut program_data = vec![3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    program_data.extend_from_slice(context.payer.pubkey().as_ref());
+    context.set_account(&program_data_account, &Account {
+        lamports: LAMPORTS_PER_SOL,
+        data: program_data,
+        owner: bpf_loader_upgradeable::id(),
+        ..Account::default()
+    }.into());
+
+    initialize_config(&mut context).await;
+
+    context
+}
+
+pub async fn process(context: &mut ProgramTestContext, signers: &[&Keypair], instructions: &[Instruction])
+                     -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers,
+                                                         blockhash);
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Transaction already processed with the same blockhash isn't processed again, even if it failed,
+// so resending it requires new blockhash
+pub async fn refresh_blockhash(context: &mut ProgramTestContext) {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    context.banks_client.get_new_latest_blockhash(&blockhash).await.unwrap();
+}
+
+async fn initialize_config(context: &mut ProgramTestContext) {
+    let program_id = hodor_program::id();
+    let (program_data_account, _) = Pubkey::find_program_address(
+        &[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let payer = context.payer.pubkey();
+
+    process(context, &[], &[Instruction::new_with_bytes(
+        program_id,
+        &ConfigInstruction::pack(&ConfigInstruction::InitializeConfig {
+            admin: payer,
+            dao_fee_rate: DAO_FEE_RATE,
+            dao_fee_vault_owner: payer,
+            max_lp_fee_rate: HodorConfig::DEFAULT_MAX_LP_FEE_RATE,
+            max_creator_fee_rate: HodorConfig::DEFAULT_MAX_CREATOR_FEE_RATE,
+            fee_change_delay: HodorConfig::DEFAULT_FEE_CHANGE_DELAY,
+            guardian: payer,
+        }),
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(HodorConfig::find_address(&program_id).0, false),
+            AccountMeta::new_readonly(program_data_account, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    )]).await.unwrap();
+}
+
+pub async fn create_mint(context: &mut ProgramTestContext) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    process(context, &[&mint], &[
+        system_instruction::create_account(&payer, &mint.pubkey(), rent.minimum_balance(spl_token::state::Mint::LEN),
+                                           spl_token::state::Mint::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &payer, None, 6).unwrap(),
+    ]).await.unwrap();
+
+    mint.pubkey()
+}
+
+pub async fn create_token_account(context: &mut ProgramTestContext, mint: &Pubkey) -> Pubkey {
+    let account = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    process(context, &[&account], &[
+        system_instruction::create_account(&payer, &account.pubkey(),
+                                           rent.minimum_balance(spl_token::state::Account::LEN),
+                                           spl_token::state::Account::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_account(&spl_token::id(), &account.pubkey(), mint, &payer).unwrap(),
+    ]).await.unwrap();
+
+    account.pubkey()
+}
+
+pub async fn get_token_amount(context: &mut ProgramTestContext, account: &Pubkey) -> u64 {
+    let account = context.banks_client.get_account(*account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+// Writes account with provided data owned by provided program directly into bank
+pub async fn set_account(context: &mut ProgramTestContext, address: &Pubkey, data: Vec<u8>, owner: &Pubkey) {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    context.set_account(address, &Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        owner: *owner,
+        ..Account::default()
+    }.into());
+}
+
+// Returns mint pair sorted the way pools expect it
+pub async fn create_mints(context: &mut ProgramTestContext) -> (Pubkey, Pubkey) {
+    let mint_1 = create_mint(context).await;
+    let mint_2 = create_mint(context).await;
+    if mint_1 < mint_2 { (mint_1, mint_2) } else { (mint_2, mint_1) }
+}
+
+pub fn create_pool_instruction(payer: &Pubkey, pool: &Pool, mint_a: &Pubkey, mint_b: &Pubkey, seed: [u8; 32],
+                               creator_fee_rate: u32, canonical: bool) -> Instruction {
+    let program_id = hodor_program::id();
+    Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::CreatePool {
+            seed,
+            lp_fee_rate: LP_FEE_RATE,
+            creator_fee_rate,
+            curve: Curve::ConstantProduct,
+            canonical,
+        }),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new_readonly(*mint_a, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(pool.locked_lp, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&program_id).0, false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_instruction(owner: &Pubkey, pool: &Pool, user_a: &Pubkey, mint_a: &Pubkey, user_b: &Pubkey,
+                           mint_b: &Pubkey, user_lp: &Pubkey, max_a: u64, max_b: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        hodor_program::id(),
+        &SwapInstruction::pack(&SwapInstruction::Deposit {
+            min_a: 0,
+            max_a,
+            min_b: 0,
+            max_b,
+        }),
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new(*user_a, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(*user_b, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(*user_lp, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(pool.locked_lp, false),
+            AccountMeta::new_readonly(*mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
\ No newline at end of file
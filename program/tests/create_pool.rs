@@ -0,0 +1,50 @@
+mod common;
+
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+use hodor_program::swap::state::SwapPool;
+use crate::common::{create_mints, create_pool_instruction, process, refresh_blockhash, start, Pool};
+
+#[tokio::test]
+async fn test_create_pool_at_pre_funded_addresses() {
+    let mut context = start().await;
+    let (mint_a, mint_b) = create_mints(&mut context).await;
+    let (pool, seed) = Pool::find_canonical(&mint_a, &mint_b);
+
+    // anyone can transfer lamports to addresses of pool accounts before pool is created
+    let payer = context.payer.pubkey();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let addresses = [pool.state, pool.token_a, pool.token_b, pool.lp_mint, pool.locked_lp];
+    let transfers: Vec<_> = addresses.iter()
+        .map(|address| system_instruction::transfer(&payer, address, rent.minimum_balance(0)))
+        .collect();
+    process(&mut context, &[], &transfers).await.unwrap();
+
+    let create_pool = create_pool_instruction(&payer, &pool, &mint_a, &mint_b, seed, 0, true);
+    process(&mut context, &[], &[create_pool]).await.unwrap();
+
+    let state_account = context.banks_client.get_account(pool.state).await.unwrap().unwrap();
+    assert_eq!(hodor_program::id(), state_account.owner);
+    assert_eq!(SwapPool::LEN, state_account.data.len());
+    for address in addresses {
+        let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+        assert!(rent.is_exempt(account.lamports, account.data.len()));
+        assert_ne!(Pubkey::default(), account.owner);
+    }
+}
+
+#[tokio::test]
+async fn test_create_pool_twice() {
+    let mut context = start().await;
+    let (mint_a, mint_b) = create_mints(&mut context).await;
+    let (pool, seed) = Pool::find_canonical(&mint_a, &mint_b);
+    let payer = context.payer.pubkey();
+
+    let create_pool = create_pool_instruction(&payer, &pool, &mint_a, &mint_b, seed, 0, true);
+    process(&mut context, &[], std::slice::from_ref(&create_pool)).await.unwrap();
+
+    refresh_blockhash(&mut context).await;
+    assert!(process(&mut context, &[], &[create_pool]).await.is_err());
+}
\ No newline at end of file
@@ -0,0 +1,138 @@
+mod common;
+
+use solana_program::clock::Clock;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+use hodor_program::config::state::HodorConfig;
+use hodor_program::error::HodorError;
+use hodor_program::swap::instruction::SwapInstruction;
+use hodor_program::swap::state::{FeeChangeProposal, SwapPool};
+use crate::common::{create_mints, create_pool_instruction, process, refresh_blockhash, start, Pool, LP_FEE_RATE};
+
+fn propose_instruction(authority: &Pubkey, pool: &Pubkey, lp_fee_rate: u32, creator_fee_rate: u32) -> Instruction {
+    let program_id = hodor_program::id();
+    Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::ProposeFeeChange { lp_fee_rate, creator_fee_rate }),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*pool, false),
+            AccountMeta::new(FeeChangeProposal::find_address(pool, &program_id).0, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&program_id).0, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    )
+}
+
+fn apply_instruction(authority: &Pubkey, pool: &Pubkey) -> Instruction {
+    let program_id = hodor_program::id();
+    Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::ApplyFeeChange),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(FeeChangeProposal::find_address(pool, &program_id).0, false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&program_id).0, false),
+        ],
+    )
+}
+
+fn hodor_error(error: HodorError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+}
+
+async fn get_pool(context: &mut ProgramTestContext, pool: &Pubkey) -> SwapPool {
+    let account = context.banks_client.get_account(*pool).await.unwrap().unwrap();
+    SwapPool::unpack(&account.data).unwrap()
+}
+
+async fn skip_fee_change_delay(context: &mut ProgramTestContext) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += HodorConfig::DEFAULT_FEE_CHANGE_DELAY;
+    context.set_sysvar(&clock);
+}
+
+#[tokio::test]
+async fn test_fee_change_of_pool_without_creator_fee() {
+    let mut context = start().await;
+    let (mint_a, mint_b) = create_mints(&mut context).await;
+    let admin = context.payer.pubkey();
+
+    let creator = Keypair::new();
+    process(&mut context, &[], &[system_instruction::transfer(&admin, &creator.pubkey(), LAMPORTS_PER_SOL)])
+        .await.unwrap();
+    let (pool, seed) = Pool::find_random();
+    process(&mut context, &[&creator], &[
+        create_pool_instruction(&creator.pubkey(), &pool, &mint_a, &mint_b, seed, 0, false),
+    ]).await.unwrap();
+
+    // pool without creator fee has no withdraw authority, its creator can't change fees
+    assert_eq!(hodor_error(HodorError::InvalidAuthority), process(&mut context, &[&creator], &[
+        propose_instruction(&creator.pubkey(), &pool.state, 500_000, 0),
+    ]).await.unwrap_err().unwrap());
+
+    // config admin can, but creator fee can't be enabled
+    assert_eq!(hodor_error(HodorError::CreatorFeeNotEnabled), process(&mut context, &[], &[
+        propose_instruction(&admin, &pool.state, 500_000, 1_000),
+    ]).await.unwrap_err().unwrap());
+    process(&mut context, &[], &[propose_instruction(&admin, &pool.state, 500_000, 0)]).await.unwrap();
+
+    assert_eq!(hodor_error(HodorError::FeeChangeNotReady), process(&mut context, &[], &[
+        apply_instruction(&admin, &pool.state),
+    ]).await.unwrap_err().unwrap());
+
+    skip_fee_change_delay(&mut context).await;
+    refresh_blockhash(&mut context).await;
+    assert_eq!(hodor_error(HodorError::InvalidAuthority), process(&mut context, &[&creator], &[
+        apply_instruction(&creator.pubkey(), &pool.state),
+    ]).await.unwrap_err().unwrap());
+    process(&mut context, &[], &[apply_instruction(&admin, &pool.state)]).await.unwrap();
+
+    let swap_pool = get_pool(&mut context, &pool.state).await;
+    assert_eq!(500_000, { swap_pool.lp_fee_rate });
+    assert_eq!(None, swap_pool.creator_fee());
+    let proposal_address = FeeChangeProposal::find_address(&pool.state, &hodor_program::id()).0;
+    assert_eq!(None, context.banks_client.get_account(proposal_address).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_fee_change_of_pool_with_creator_fee() {
+    let mut context = start().await;
+    let (mint_a, mint_b) = create_mints(&mut context).await;
+    let admin = context.payer.pubkey();
+
+    let creator = Keypair::new();
+    process(&mut context, &[], &[system_instruction::transfer(&admin, &creator.pubkey(), LAMPORTS_PER_SOL)])
+        .await.unwrap();
+    let (pool, seed) = Pool::find_random();
+    process(&mut context, &[&creator], &[
+        create_pool_instruction(&creator.pubkey(), &pool, &mint_a, &mint_b, seed, 10_000, false),
+    ]).await.unwrap();
+
+    // creator fee withdraw authority changes fees, config admin can't
+    assert_eq!(hodor_error(HodorError::InvalidAuthority), process(&mut context, &[], &[
+        propose_instruction(&admin, &pool.state, 500_000, 0),
+    ]).await.unwrap_err().unwrap());
+
+    // proposal address can be pre-funded by anyone
+    let proposal_address = FeeChangeProposal::find_address(&pool.state, &hodor_program::id()).0;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    process(&mut context, &[], &[system_instruction::transfer(&admin, &proposal_address, rent.minimum_balance(0))])
+        .await.unwrap();
+
+    process(&mut context, &[&creator], &[propose_instruction(&creator.pubkey(), &pool.state, LP_FEE_RATE, 0)])
+        .await.unwrap();
+    skip_fee_change_delay(&mut context).await;
+    process(&mut context, &[&creator], &[apply_instruction(&creator.pubkey(), &pool.state)]).await.unwrap();
+
+    let swap_pool = get_pool(&mut context, &pool.state).await;
+    assert_eq!(LP_FEE_RATE, { swap_pool.lp_fee_rate });
+    assert_eq!(0, swap_pool.creator_fee_rate());
+    assert_eq!(creator.pubkey(), swap_pool.creator_fee().unwrap().withdraw_authority);
+}
\ No newline at end of file
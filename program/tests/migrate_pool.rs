@@ -0,0 +1,147 @@
+mod common;
+
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::signature::Signer;
+use hodor_program::swap::instruction::SwapInstruction;
+use hodor_program::swap::state::SwapPool;
+use crate::common::{create_mints, create_token_account, get_token_amount, process, refresh_blockhash, set_account,
+                    start, LP_FEE_RATE};
+
+struct LegacyPool {
+    state: Pubkey,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    lp_mint: Pubkey,
+}
+
+// Writes pool of legacy layout holding provided amounts, pools created with random seed were its only kind
+async fn set_legacy_pool(context: &mut ProgramTestContext, mint_a: &Pubkey, mint_b: &Pubkey, balances: (u64, u64),
+                         amounts: (u64, u64)) -> LegacyPool {
+    let program_id = hodor_program::id();
+    let (seed, state) = loop {
+        let seed = Pubkey::new_unique().to_bytes();
+        if let Ok(state) = Pubkey::create_program_address(&[&seed], &program_id) {
+            break (seed, state);
+        }
+    };
+    let pool = LegacyPool {
+        state,
+        token_a: Pubkey::new_unique(),
+        token_b: Pubkey::new_unique(),
+        lp_mint: Pubkey::new_unique(),
+    };
+
+    for (address, mint, amount) in [(pool.token_a, mint_a, amounts.0), (pool.token_b, mint_b, amounts.1)] {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: *mint,
+            owner: state,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        }.pack_into_slice(&mut data);
+        set_account(context, &address, data, &spl_token::id()).await;
+    }
+
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(state),
+        supply: 1_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }.pack_into_slice(&mut data);
+    set_account(context, &pool.lp_mint, data, &spl_token::id()).await;
+
+    let mut data = vec![SwapPool::TYPE_MARKER];
+    data.extend_from_slice(&seed);
+    data.extend_from_slice(pool.token_a.as_ref());
+    data.extend_from_slice(pool.token_b.as_ref());
+    data.extend_from_slice(&balances.0.to_le_bytes());
+    data.extend_from_slice(&balances.1.to_le_bytes());
+    data.extend_from_slice(pool.lp_mint.as_ref());
+    data.extend_from_slice(&LP_FEE_RATE.to_le_bytes());
+    assert_eq!(SwapPool::LEGACY_BASE_SIZE, data.len());
+    set_account(context, &state, data, &program_id).await;
+
+    pool
+}
+
+fn migrate_instruction(payer: &Pubkey, pool: &LegacyPool) -> Instruction {
+    let program_id = hodor_program::id();
+    Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::MigratePool),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new_readonly(pool.token_a, false),
+            AccountMeta::new_readonly(pool.token_b, false),
+            AccountMeta::new_readonly(pool.lp_mint, false),
+            AccountMeta::new(SwapPool::find_locked_lp_address(&pool.state, &program_id).0, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+    )
+}
+
+fn skim_instruction(pool: &LegacyPool, destination_a: &Pubkey, mint_a: &Pubkey, destination_b: &Pubkey,
+                    mint_b: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        hodor_program::id(),
+        &SwapInstruction::pack(&SwapInstruction::Skim),
+        vec![
+            AccountMeta::new_readonly(pool.state, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(*destination_a, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(*destination_b, false),
+            AccountMeta::new_readonly(*mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_pool_with_untracked_fees_then_skim() {
+    let mut context = start().await;
+    let (mint_a, mint_b) = create_mints(&mut context).await;
+    let payer = context.payer.pubkey();
+
+    // legacy pool didn't track DAO fees, its token accounts hold them above reserves
+    let pool = set_legacy_pool(&mut context, &mint_a, &mint_b, (600, 1_500), (1_000, 2_000)).await;
+    let destination_a = create_token_account(&mut context, &mint_a).await;
+    let destination_b = create_token_account(&mut context, &mint_b).await;
+
+    // fees can't be skimmed before migration records them
+    assert!(process(&mut context, &[], &[skim_instruction(&pool, &destination_a, &mint_a, &destination_b, &mint_b)])
+        .await.is_err());
+
+    process(&mut context, &[], &[migrate_instruction(&payer, &pool)]).await.unwrap();
+    refresh_blockhash(&mut context).await;
+
+    let state_account = context.banks_client.get_account(pool.state).await.unwrap().unwrap();
+    let swap_pool = SwapPool::unpack(&state_account.data).unwrap();
+    assert_eq!((600, 1_500), (swap_pool.balance_a, swap_pool.balance_b));
+    assert_eq!((400, 500), (swap_pool.dao_fee_balance_a, swap_pool.dao_fee_balance_b));
+
+    // migrated fees aren't surplus anymore, only tokens sent to pool afterwards are skimmed
+    process(&mut context, &[], &[skim_instruction(&pool, &destination_a, &mint_a, &destination_b, &mint_b)])
+        .await.unwrap();
+    assert_eq!(0, get_token_amount(&mut context, &destination_a).await);
+    assert_eq!(0, get_token_amount(&mut context, &destination_b).await);
+
+    process(&mut context, &[], &[
+        spl_token::instruction::mint_to(&spl_token::id(), &mint_a, &pool.token_a, &payer, &[], 7).unwrap(),
+        skim_instruction(&pool, &destination_a, &mint_a, &destination_b, &mint_b),
+    ]).await.unwrap();
+    assert_eq!(7, get_token_amount(&mut context, &destination_a).await);
+    assert_eq!(0, get_token_amount(&mut context, &destination_b).await);
+    assert_eq!(1_000, get_token_amount(&mut context, &pool.token_a).await);
+}
\ No newline at end of file
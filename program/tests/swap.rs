@@ -0,0 +1,62 @@
+mod common;
+
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+use hodor_program::config::state::HodorConfig;
+use hodor_program::swap::instruction::SwapInstruction;
+use hodor_program::swap::state::SwapPool;
+use crate::common::{create_mints, create_pool_instruction, create_token_account, deposit_instruction, get_token_amount,
+                    process, start, Pool};
+
+#[tokio::test]
+async fn test_swap_updates_pool_state() {
+    let mut context = start().await;
+    let (mint_a, mint_b) = create_mints(&mut context).await;
+    let (pool, seed) = Pool::find_canonical(&mint_a, &mint_b);
+    let payer = context.payer.pubkey();
+    let program_id = hodor_program::id();
+
+    process(&mut context, &[], &[create_pool_instruction(&payer, &pool, &mint_a, &mint_b, seed, 0, true)])
+        .await.unwrap();
+
+    let user_a = create_token_account(&mut context, &mint_a).await;
+    let user_b = create_token_account(&mut context, &mint_b).await;
+    let user_lp = create_token_account(&mut context, &pool.lp_mint).await;
+    process(&mut context, &[], &[
+        spl_token::instruction::mint_to(&spl_token::id(), &mint_a, &user_a, &payer, &[], 2_000_000).unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &mint_b, &user_b, &payer, &[], 1_000_000).unwrap(),
+        deposit_instruction(&payer, &pool, &user_a, &mint_a, &user_b, &mint_b, &user_lp, 1_000_000, 1_000_000),
+    ]).await.unwrap();
+
+    process(&mut context, &[], &[Instruction::new_with_bytes(
+        program_id,
+        &SwapInstruction::pack(&SwapInstruction::Swap {
+            in_amount: 100_000,
+            min_out_amount: 1,
+        }),
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(pool.state, false),
+            AccountMeta::new(user_a, false),
+            AccountMeta::new(pool.token_a, false),
+            AccountMeta::new(pool.token_b, false),
+            AccountMeta::new(user_b, false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(HodorConfig::find_address(&program_id).0, false),
+        ],
+    )]).await.unwrap();
+
+    // state changed in place tracks every token held by pool
+    let state_account = context.banks_client.get_account(pool.state).await.unwrap().unwrap();
+    let swap_pool = SwapPool::unpack(&state_account.data).unwrap();
+    let out_amount = get_token_amount(&mut context, &user_b).await;
+    assert!(out_amount > 0 && swap_pool.dao_fee_balance_a > 0);
+    assert_eq!(Some((1_100_000, 1_000_000 - out_amount)), swap_pool.tracked_amounts());
+    assert_eq!(1_100_000, get_token_amount(&mut context, &pool.token_a).await);
+    assert_eq!(1_000_000 - out_amount, get_token_amount(&mut context, &pool.token_b).await);
+    assert!({ swap_pool.last_update_timestamp } > 0);
+}
\ No newline at end of file